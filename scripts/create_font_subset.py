@@ -30,9 +30,36 @@ CHARS = (
     "\u00B1"  # ± plus-minus
     "\u221A"  # √ square root
     "\u00B2"  # ² superscript 2
+    "\u00B3"  # ³ superscript 3
     "\u02B8"  # ʸ superscript y
     "\u03C0"  # π pi
     "\u2E23"  # ⸣ corner bracket (for paren indicator)
+    # Statistics
+    "\u0177"  # ŷ predicted y
+    "\u0302"  # combining circumflex (x̂)
+    "\u00B7"  # · middle dot
+    "\u2014"  # — em dash (undefined value)
+    "\u2026"  # … any number of arguments (completions)
+    # Distribution parameters
+    "\u03BC"  # μ mean
+    "\u03C3"  # σ standard deviation
+    "\u03BD"  # ν degrees of freedom
+    "\u03BB"  # λ rate
+    "\u2264"  # ≤ less than or equal
+    "\u2265"  # ≥ greater than or equal
+    "\u2248"  # ≈ approximately equal
+    # Calculus
+    "\u222B"  # ∫ integral
+    "\u03A3"  # Σ summation
+    "\u03A0"  # Π product
+    # Exact results
+    "\u21D4"  # ⇔ exact/decimal toggle (S⇔D)
+    # Percentages
+    "\u0394"  # Δ percentage change (Δ%)
+    # Plots
+    "\u03B8"  # θ polar angle
+    # Display formats
+    "\u00B5"  # µ micro prefix (ENG)
     # Whitespace
     " "
 )
@@ -1,41 +1,318 @@
 use eframe::egui;
+use egui::text::CCursor;
 use egui::{
     Align, Color32, FontId, Layout, RichText, Rounding, Stroke, UiBuilder, Vec2, ViewportCommand,
 };
 
-use crate::calculator::{AngleMode, Calculator};
+use crate::calculator::{format_result, value_text, AngleMode, Calculator, PercentMode};
+use crate::completion::{self, Completion};
+use crate::complex::Complex;
+use crate::currency::RateTable;
+use crate::dates;
+use crate::distributions::{Distribution, Kind};
+use crate::finance::{self, Tvm, TvmVariable};
+use crate::format::{DisplayFormat, Locale, RoundingMode, MAX_DIGITS, MAX_PRECISION};
+use crate::highlight;
+use crate::linear::{self, MAX_UNKNOWNS};
+use crate::matrix::{self, Matrix};
+use crate::plot::{self, Feature, FeatureKind, View};
+use crate::polynomial::{self, MAX_DEGREE};
+use crate::regression::Model;
+use crate::solver::{self, Report};
+use crate::stats::MAX_FREQUENCY;
+use crate::table;
+use crate::typeset;
+use crate::units;
 
 const CALC_WIDTH: f32 = 320.0;
 const BUTTON_HEIGHT: f32 = 48.0;
 const SCI_BUTTON_HEIGHT: f32 = 32.0;
 const SPACING: f32 = 4.0;
 const TITLE_BAR_HEIGHT: f32 = 32.0;
+/// Colors of the graphs, one per function
+const PLOT_COLORS: [Color32; 3] = [
+    Color32::from_rgb(100, 180, 255),
+    Color32::from_rgb(255, 150, 100),
+    Color32::from_rgb(130, 220, 130),
+];
+
+/// Panel shown below the mode bar
+#[derive(Clone, Copy, Default, PartialEq)]
+enum Panel {
+    #[default]
+    Keypad,
+    History,
+    Stats,
+    Distributions,
+    Solver,
+    Matrix,
+    Plot,
+    Table,
+    Finance,
+    Currency,
+    Dates,
+    Menu,
+}
+
+impl Panel {
+    /// Panels offered by the menu, with their labels and descriptions
+    const MENU: [(Panel, &'static str, &'static str); 9] = [
+        (Panel::Stats, "STAT", "Statistics and regression"),
+        (Panel::Distributions, "DIST", "Probability distributions"),
+        (Panel::Solver, "SOLV", "Equations and systems"),
+        (Panel::Matrix, "MAT", "Matrices and vectors"),
+        (Panel::Plot, "PLOT", "Function graphs"),
+        (Panel::Table, "TABLE", "Table of values"),
+        (Panel::Finance, "FIN", "Loans, cash flows, depreciation"),
+        (Panel::Currency, "CUR", "Currency rates and units"),
+        (Panel::Dates, "DATE", "Dates, times and durations"),
+    ];
+}
+
+/// Kind of curve in the plot panel
+#[derive(Clone, Copy, Default, PartialEq)]
+enum PlotMode {
+    #[default]
+    Function,
+    Parametric,
+    Polar,
+}
+
+impl PlotMode {
+    const ALL: [PlotMode; 3] = [PlotMode::Function, PlotMode::Parametric, PlotMode::Polar];
+
+    fn label(self) -> &'static str {
+        match self {
+            PlotMode::Function => "y(x)",
+            PlotMode::Parametric => "x,y(t)",
+            PlotMode::Polar => "r(θ)",
+        }
+    }
+
+    /// Labels and hints of the fields of one curve
+    fn fields(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            PlotMode::Function => &[("y=", "x²−2")],
+            PlotMode::Parametric => &[("x=", "cos(3t)"), ("y=", "sin(2t)")],
+            PlotMode::Polar => &[("r=", "1+cos(θ)")],
+        }
+    }
+
+    /// Variable of the expressions, as known to the parser
+    fn variable(self) -> &'static str {
+        match self {
+            PlotMode::Function => "x",
+            PlotMode::Parametric => "t",
+            PlotMode::Polar => "theta",
+        }
+    }
+}
+
+/// Function of x to plot, or the error in its expression
+type Graph = Result<Box<dyn Fn(f64) -> f64>, String>;
+
+/// Lines of each curve for a view and number of samples across it
+type Curves<'a> = dyn Fn(&View, usize) -> Vec<Vec<Vec<(f64, f64)>>> + 'a;
+
+/// Inputs of the marked plot features; they are recomputed when these change
+type PlotKey = (PlotMode, Vec<[String; 2]>, View, AngleMode, [bool; 3]);
+
+/// Tab of the solver panel
+#[derive(Clone, Copy, Default, PartialEq)]
+enum SolverTab {
+    #[default]
+    Equation,
+    Polynomial,
+    Linear,
+}
+
+impl SolverTab {
+    const ALL: [SolverTab; 3] = [
+        SolverTab::Equation,
+        SolverTab::Polynomial,
+        SolverTab::Linear,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            SolverTab::Equation => "EQN",
+            SolverTab::Polynomial => "POLY",
+            SolverTab::Linear => "LIN",
+        }
+    }
+}
+
+/// Tab of the financial panel
+#[derive(Clone, Copy, Default, PartialEq)]
+enum FinanceTab {
+    #[default]
+    Tvm,
+    Amortization,
+    CashFlow,
+    Depreciation,
+}
+
+impl FinanceTab {
+    const ALL: [FinanceTab; 4] = [
+        FinanceTab::Tvm,
+        FinanceTab::Amortization,
+        FinanceTab::CashFlow,
+        FinanceTab::Depreciation,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            FinanceTab::Tvm => "TVM",
+            FinanceTab::Amortization => "AMORT",
+            FinanceTab::CashFlow => "CF",
+            FinanceTab::Depreciation => "DEP",
+        }
+    }
+}
+
+/// Input from the date panel
+enum DateInput {
+    Literal(String),
+    Constant(&'static str),
+    Unit(&'static str),
+    Function(&'static str),
+}
 
 #[derive(Default)]
 pub struct CalculatorApp {
     calc: Calculator,
     error_message: Option<String>,
-    show_history: bool,
+    panel: Panel,
     is_maximized: bool,
+    stat_value_input: String,
+    stat_y_input: String,
+    stat_freq_input: String,
+    stat_two_var: bool,
+    dist: Distribution,
+    dist_kind: Kind,
+    dist_args: [String; 3],
+    solve_equation: String,
+    solve_lo: String,
+    solve_hi: String,
+    solve_guess: String,
+    solve_result: Option<Result<Report, String>>,
+    solver_tab: SolverTab,
+    poly_degree: usize,
+    poly_coefs: Vec<String>,
+    poly_result: Option<Result<Vec<Complex>, String>>,
+    lin_size: usize,
+    lin_cells: Vec<Vec<String>>,
+    lin_result: Option<Result<Vec<f64>, String>>,
+    mat_rows: usize,
+    mat_cols: usize,
+    mat_cells: Vec<Vec<String>>,
+    plot_mode: PlotMode,
+    /// Expressions of the curves for each mode; the second one is y(t) of
+    /// parametric curves
+    plot_entries: [Vec<[String; 2]>; 3],
+    /// Parameter range and number of steps of parametric and polar curves
+    plot_range: [String; 3],
+    plot_view: View,
+    /// Mark roots, extrema and intersections
+    plot_marks: [bool; 3],
+    plot_features: Option<(PlotKey, Vec<Feature>)>,
+    table_expression: String,
+    /// Start, stop and step
+    table_range: [String; 3],
+    table_rows: Option<Result<Vec<(f64, f64)>, String>>,
+    table_status: Option<String>,
+    fin_tab: FinanceTab,
+    /// N, I/Y (percent per period), PV, PMT and FV
+    fin_tvm: [String; 5],
+    /// Payments at the beginning of each period
+    fin_due: bool,
+    fin_result: Option<Result<(TvmVariable, f64), String>>,
+    fin_flows: String,
+    fin_rate: String,
+    /// Cost, salvage value and life
+    fin_asset: [String; 3],
+    fin_declining: bool,
+    fin_factor: String,
+    /// Rate fields of the currencies in the rate table
+    cur_fields: Vec<String>,
+    /// Code and rate of a currency to add
+    cur_new: [String; 2],
+    cur_status: Option<Result<String, String>>,
+    /// Date and duration literals to insert
+    date_fields: [String; 2],
+    date_error: Option<String>,
+    /// Show `a÷b` as a stacked fraction in the display
+    stacked_fractions: bool,
+    /// Show the input as typed instead of typeset, for editing
+    plain_input: bool,
+    /// Letters typed from the keyboard, completed to a function or constant
+    completion: String,
+    /// Highlighted entry of the completion popup
+    completion_index: usize,
 }
 
 impl CalculatorApp {
     pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
         configure_fonts(&cc.egui_ctx);
         configure_style(&cc.egui_ctx);
-        Self::default()
+        let (rates, cur_status) = match RateTable::load() {
+            Ok(rates) => (rates, None),
+            Err(e) => (RateTable::default(), Some(Err(format!("Rate file: {}", e)))),
+        };
+        let mut calc = Calculator::default();
+        calc.rates = rates;
+        Self {
+            calc,
+            cur_status,
+            poly_degree: 2,
+            lin_size: 2,
+            mat_rows: 2,
+            mat_cols: 2,
+            plot_entries: std::array::from_fn(|_| vec![Default::default()]),
+            ..Self::default()
+        }
     }
 
     fn handle_keyboard(&mut self, ctx: &egui::Context) {
+        let mut copy: Option<String> = None;
         ctx.input(|i| {
             for event in &i.events {
+                if !self.completion.is_empty() && self.complete_event(event) {
+                    continue;
+                }
+                match event {
+                    // Ctrl+C copies the result, Ctrl+Shift+C the calculation
+                    egui::Event::Copy => copy = self.calc.copy_text(i.modifiers.shift),
+                    // Ctrl+X copies and deletes the selection
+                    egui::Event::Cut if self.calc.selection().is_some() => {
+                        copy = self.calc.copy_text(false);
+                        self.calc.clear_entry();
+                    }
+                    egui::Event::Paste(text) => {
+                        self.error_message = None;
+                        self.calc.paste(text);
+                    }
+                    _ => {}
+                }
                 if let egui::Event::Key {
-                    key, pressed: true, ..
+                    key,
+                    pressed: true,
+                    modifiers,
+                    ..
                 } = event
                 {
+                    let select = modifiers.shift;
                     match key {
+                        egui::Key::Z if modifiers.command && modifiers.shift => self.calc.redo(),
+                        egui::Key::Z if modifiers.command => self.calc.undo(),
+                        egui::Key::Y if modifiers.command => self.calc.redo(),
+                        egui::Key::A if modifiers.command => self.calc.select_all(),
+                        egui::Key::ArrowLeft => self.calc.move_cursor(-1, select),
+                        egui::Key::ArrowRight => self.calc.move_cursor(1, select),
+                        egui::Key::Home => self.calc.set_cursor(0, select),
+                        egui::Key::End => self.calc.set_cursor(usize::MAX, select),
                         egui::Key::Backspace => self.calc.clear_entry(),
-                        egui::Key::Delete => self.calc.clear(),
+                        egui::Key::Delete => self.calc.delete_forward(),
                         egui::Key::Escape => {
                             self.calc.clear();
                             self.error_message = None;
@@ -50,7 +327,8 @@ impl CalculatorApp {
                         "0" | "1" | "2" | "3" | "4" | "5" | "6" | "7" | "8" | "9" => {
                             self.calc.input_digit(text);
                         }
-                        "." | "," => self.calc.input_decimal(),
+                        "." => self.calc.input_decimal_key('.'),
+                        "," => self.calc.input_decimal_key(','),
                         "+" => self.calc.input_operator("+"),
                         "-" => self.calc.input_operator("−"),
                         "*" => self.calc.input_operator("×"),
@@ -58,18 +336,130 @@ impl CalculatorApp {
                         "^" => self.calc.input_power(),
                         "(" => self.calc.input_open_paren(),
                         ")" => self.calc.input_close_paren(),
+                        ";" => self.calc.input_separator(),
                         "%" => self.calc.input_percent(),
                         "=" => self.do_calculate(),
+                        // Letters start a name to complete
+                        _ if text.len() == 1 && text.chars().all(|c| c.is_ascii_alphabetic()) => {
+                            self.completion = text.clone();
+                            self.completion_index = 0;
+                        }
                         _ => {}
                     }
                 }
             }
         });
+        // The clipboard is written outside of the input lock
+        if let Some(text) = copy {
+            ctx.copy_text(text);
+        }
+    }
+
+    /// Handle `event` while a name is being completed. Letters extend it,
+    /// arrows pick an entry and `Enter` or `Tab` accepts it. Any other
+    /// character accepts an exact match and is then typed as usual, except
+    /// `(` after a function. A name without a match, or any name after
+    /// `to`, is typed as it is, e.g. a unit such as `USD` or `h`. Returns
+    /// whether the event was used up.
+    fn complete_event(&mut self, event: &egui::Event) -> bool {
+        let matches = completion::complete(&self.completion);
+        match event {
+            egui::Event::Key {
+                key,
+                pressed: true,
+                modifiers,
+                ..
+            } if !modifiers.command => match key {
+                egui::Key::ArrowUp | egui::Key::ArrowDown if !matches.is_empty() => {
+                    let step = if *key == egui::Key::ArrowUp {
+                        matches.len() - 1
+                    } else {
+                        1
+                    };
+                    self.completion_index = (self.completion_index + step) % matches.len();
+                    true
+                }
+                egui::Key::Enter | egui::Key::Tab => {
+                    match matches.get(self.completion_index) {
+                        Some(selected) if !self.calc.converting() => {
+                            self.accept_completion(selected);
+                            true
+                        }
+                        // Enter then calculates
+                        _ => {
+                            self.input_typed_name();
+                            *key == egui::Key::Tab
+                        }
+                    }
+                }
+                egui::Key::Escape => {
+                    self.completion.clear();
+                    true
+                }
+                egui::Key::Backspace => {
+                    self.completion.pop();
+                    self.completion_index = 0;
+                    true
+                }
+                egui::Key::ArrowLeft
+                | egui::Key::ArrowRight
+                | egui::Key::Home
+                | egui::Key::End
+                | egui::Key::Delete => {
+                    self.completion.clear();
+                    false
+                }
+                _ => false,
+            },
+            egui::Event::Text(text) => {
+                let mut chars = text.chars();
+                if let (Some(c), None) = (chars.next(), chars.next()) {
+                    if completion::continues(&self.completion, c) {
+                        self.completion.push(c);
+                        self.completion_index = 0;
+                        return true;
+                    }
+                }
+                let exact = matches
+                    .first()
+                    .filter(|m| m.name.eq_ignore_ascii_case(&self.completion))
+                    .cloned();
+                match exact {
+                    Some(exact) if !self.calc.converting() => {
+                        self.accept_completion(&exact);
+                        exact.is_function() && text == "("
+                    }
+                    _ => {
+                        self.input_typed_name();
+                        false
+                    }
+                }
+            }
+            _ => false,
+        }
+    }
+
+    /// Insert a completion as a function call, a value, a unit or `to`
+    fn accept_completion(&mut self, completion: &Completion) {
+        self.error_message = None;
+        if completion.is_function() {
+            self.calc.input_function(completion.insert);
+        } else {
+            self.calc.input_name(completion.insert);
+        }
+        self.completion.clear();
+    }
+
+    /// Insert the name being completed as it was typed
+    fn input_typed_name(&mut self) {
+        self.error_message = None;
+        let name = std::mem::take(&mut self.completion);
+        self.calc.input_name(&name);
     }
 
     fn do_calculate(&mut self) {
         self.error_message = None;
-        if let Err(e) = self.calc.calculate() {
+        if let Err(e) = self.calc.evaluate() {
             self.error_message = Some(e);
         }
     }
@@ -81,7 +471,10 @@ impl eframe::App for CalculatorApp {
     }
 
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        self.handle_keyboard(ctx);
+        // Text fields in panels take keyboard input for themselves
+        if !ctx.wants_keyboard_input() {
+            self.handle_keyboard(ctx);
+        }
 
         // Window frame with rounded corners
         egui::CentralPanel::default()
@@ -115,8 +508,8 @@ impl eframe::App for CalculatorApp {
                         let available = ui.available_size();
 
                         // Calculate content height (approximate)
-                        // Display: 70 + margins, mode bar: ~30, keypad: ~360
-                        let content_height = 490.0;
+                        // Display: 70 + margins, mode bar: ~30, keypad: ~396
+                        let content_height = 526.0;
                         let content_width = CALC_WIDTH + 16.0; // with margins
 
                         // Center horizontally and vertically
@@ -139,10 +532,19 @@ impl eframe::App for CalculatorApp {
                                         self.render_mode_bar(ui);
                                         ui.add_space(6.0);
 
-                                        if self.show_history {
-                                            self.render_history(ui);
-                                        } else {
-                                            self.render_keypad(ui);
+                                        match self.panel {
+                                            Panel::Keypad => self.render_keypad(ui),
+                                            Panel::History => self.render_history(ui),
+                                            Panel::Stats => self.render_stats(ui),
+                                            Panel::Distributions => self.render_distributions(ui),
+                                            Panel::Solver => self.render_solver(ui),
+                                            Panel::Matrix => self.render_matrix_editor(ui),
+                                            Panel::Plot => self.render_plot(ui),
+                                            Panel::Table => self.render_table(ui),
+                                            Panel::Finance => self.render_finance(ui),
+                                            Panel::Currency => self.render_currency(ui),
+                                            Panel::Dates => self.render_dates(ui),
+                                            Panel::Menu => self.render_menu(ui),
                                         }
                                     });
                                 });
@@ -258,7 +660,7 @@ impl CalculatorApp {
     fn render_display(&mut self, ui: &mut egui::Ui) {
         let display_height = 70.0;
 
-        egui::Frame::none()
+        let display = egui::Frame::none()
             .fill(Color32::from_rgb(28, 28, 36))
             .rounding(Rounding::same(12.0))
             .inner_margin(egui::Margin::symmetric(12.0, 8.0))
@@ -275,8 +677,10 @@ impl CalculatorApp {
                                 .color(Color32::from_rgb(255, 120, 120))
                                 .font(FontId::monospace(20.0)),
                         );
+                    } else if let Some(m) = self.calc.matrix_result() {
+                        render_matrix_grid(ui, m, display_height);
                     } else {
-                        let text = &self.calc.display;
+                        let text = &self.calc.locale.localize(&self.calc.display);
                         let font_size = adaptive_font_size(text.len());
 
                         // Show open parens indicator
@@ -290,14 +694,259 @@ impl CalculatorApp {
                             text.clone()
                         };
 
-                        ui.label(
-                            RichText::new(&display_text)
-                                .color(Color32::WHITE)
-                                .font(FontId::monospace(font_size)),
-                        );
+                        if self.calc.result_shown() || self.calc.expression.is_empty() {
+                            self.plain_input = false;
+                            self.render_input(ui, display_text, font_size);
+                            return;
+                        }
+                        // While typing, the result so far is previewed under the input
+                        let preview = self.calc.preview();
+                        ui.with_layout(Layout::top_down(Align::Max), |ui| {
+                            let size = Vec2::new(ui.available_width(), display_height - 22.0);
+                            ui.allocate_ui_with_layout(
+                                size,
+                                Layout::right_to_left(Align::Center),
+                                |ui| {
+                                    ui.set_height(size.y);
+                                    if !self.render_typeset(ui, font_size) {
+                                        self.render_input(ui, display_text, font_size);
+                                    }
+                                },
+                            );
+                            if let Some(preview) = preview {
+                                ui.label(
+                                    RichText::new(format!(
+                                        "= {}",
+                                        self.calc.locale.localize(&preview)
+                                    ))
+                                    .color(Color32::from_gray(120))
+                                    .font(FontId::monospace(16.0)),
+                                );
+                            }
+                        });
                     }
                 });
             });
+        self.render_completions(ui, display.response.rect);
+    }
+
+    /// Popup under the display listing completions of the letters typed,
+    /// with their signatures and descriptions; a click accepts one
+    fn render_completions(&mut self, ui: &mut egui::Ui, display: egui::Rect) {
+        if self.completion.is_empty() {
+            return;
+        }
+        let matches = completion::complete(&self.completion);
+        let mut accepted = None;
+        egui::Area::new(egui::Id::new("completions"))
+            .order(egui::Order::Foreground)
+            .fixed_pos(display.left_bottom() + Vec2::new(0.0, 4.0))
+            .show(ui.ctx(), |ui| {
+                egui::Frame::popup(ui.style())
+                    .fill(Color32::from_rgb(34, 34, 46))
+                    .show(ui, |ui| {
+                        ui.set_width(display.width() - 12.0);
+                        ui.label(
+                            RichText::new(&self.completion)
+                                .font(FontId::monospace(14.0))
+                                .color(Color32::from_rgb(100, 180, 255)),
+                        );
+                        if matches.is_empty() {
+                            ui.label(RichText::new("No matches").color(Color32::from_gray(140)));
+                        }
+                        for (i, entry) in matches.iter().enumerate() {
+                            let kind = if entry.is_function() {
+                                highlight::Kind::Function
+                            } else {
+                                highlight::classify(entry.insert, self.calc.locale)[0]
+                            };
+                            ui.horizontal(|ui| {
+                                let signature = RichText::new(entry.signature())
+                                    .font(FontId::monospace(14.0))
+                                    .color(syntax_color(kind));
+                                if ui
+                                    .selectable_label(i == self.completion_index, signature)
+                                    .clicked()
+                                {
+                                    accepted = Some(entry.clone());
+                                }
+                                ui.label(
+                                    RichText::new(&entry.description)
+                                        .size(12.0)
+                                        .color(Color32::from_gray(140)),
+                                );
+                            });
+                        }
+                    });
+            });
+        if let Some(entry) = accepted {
+            self.accept_completion(&entry);
+        }
+    }
+
+    /// Draw the input as typeset math while typing at its end, scaled down
+    /// to fit. Returns false if it is to be shown as typed instead: while
+    /// editing in the middle, after a click into it or when it does not
+    /// parse.
+    fn render_typeset(&mut self, ui: &mut egui::Ui, font_size: f32) -> bool {
+        let calc = &self.calc;
+        let at_end = calc.cursor() == calc.expression.chars().count() && calc.selection().is_none();
+        if self.plain_input || !at_end {
+            return false;
+        }
+        let Some(input) = calc.typeset_input() else {
+            return false;
+        };
+        let painter = ui.painter().clone();
+        let measure = |text: &str, size: f32| {
+            painter
+                .layout_no_wrap(text.to_string(), FontId::monospace(size), Color32::WHITE)
+                .size()
+                .x
+        };
+        let markers = "⸣".repeat(calc.get_open_parens().max(0) as usize);
+        let mut style = typeset::Style {
+            size: font_size,
+            stacked_fractions: self.stacked_fractions,
+            locale: calc.locale,
+        };
+        let mut block = typeset::layout(&input, style, &measure);
+        let width = block.width + measure(&markers, font_size);
+        let scale = (ui.available_height() / block.height()).min(ui.available_width() / width);
+        if scale < 1.0 {
+            style.size = (font_size * scale).max(10.0);
+            block = typeset::layout(&input, style, &measure);
+        }
+        let caret = block.width;
+        block.push_text(&markers, style.size, calc.locale, &measure);
+
+        let size = Vec2::new(block.width, block.height());
+        let (rect, response) = ui.allocate_exact_size(size, egui::Sense::click());
+        let origin = rect.left_top() + Vec2::new(0.0, block.ascent);
+        for piece in &block.pieces {
+            match piece {
+                typeset::Piece::Text {
+                    at,
+                    text,
+                    size,
+                    kind,
+                } => {
+                    painter.text(
+                        origin + Vec2::new(at.0, at.1),
+                        egui::Align2::LEFT_CENTER,
+                        text,
+                        FontId::monospace(*size),
+                        syntax_color(*kind),
+                    );
+                }
+                typeset::Piece::Line {
+                    points,
+                    width,
+                    kind,
+                } => {
+                    let points = points
+                        .iter()
+                        .map(|&(x, y)| origin + Vec2::new(x, y))
+                        .collect();
+                    painter.add(egui::Shape::line(
+                        points,
+                        Stroke::new(*width, syntax_color(*kind)),
+                    ));
+                }
+            }
+        }
+        let half = style.size * 0.6;
+        painter.vline(
+            origin.x + caret,
+            (origin.y - half)..=(origin.y + half),
+            Stroke::new(1.5, Color32::from_rgb(100, 180, 255)),
+        );
+
+        // A click shows the input as typed, to place the cursor in it
+        if response.on_hover_cursor(egui::CursorIcon::Text).clicked() {
+            self.plain_input = true;
+        }
+        true
+    }
+
+    /// Draw the input with its cursor and selection. A click places the
+    /// cursor, Shift+click selects up to it.
+    fn render_input(&mut self, ui: &mut egui::Ui, text: String, font_size: f32) {
+        let calc = &self.calc;
+        // The display shows the expression itself unless it holds a result
+        let editing = calc.display == calc.expression;
+        let positions = calc.locale.positions(&calc.expression);
+        let cursor = if editing {
+            positions[calc.cursor()]
+        } else {
+            calc.locale.localize(&calc.display).chars().count()
+        };
+
+        // Syntax colours, and the bracket next to the cursor with its
+        // partner (or alone and red if it has none) on a background
+        let mut marked = Vec::new();
+        let mut unmatched = false;
+        if !calc.result_shown() {
+            if let Some((bracket, partner)) = highlight::bracket_at(&text, cursor) {
+                marked.push(bracket);
+                marked.extend(partner);
+                unmatched = partner.is_none();
+            }
+        }
+        let kinds = highlight::classify(&text, calc.locale);
+        let mut job = egui::text::LayoutJob::default();
+        for (i, c) in text.chars().enumerate() {
+            let background = match marked.contains(&i) {
+                true if unmatched => Color32::from_rgb(120, 50, 60),
+                true => Color32::from_rgb(70, 70, 110),
+                false => Color32::TRANSPARENT,
+            };
+            let format = egui::TextFormat {
+                font_id: FontId::monospace(font_size),
+                color: syntax_color(kinds[i]),
+                background,
+                ..Default::default()
+            };
+            job.append(c.encode_utf8(&mut [0; 4]), 0.0, format);
+        }
+        let galley = ui.painter().layout_job(job);
+        let (rect, response) = ui.allocate_exact_size(galley.size(), egui::Sense::click());
+        let response = response.on_hover_cursor(egui::CursorIcon::Text);
+        let x_at = |index: usize| rect.min.x + galley.pos_from_ccursor(CCursor::new(index)).min.x;
+
+        if let Some((start, end)) = calc.selection().filter(|_| editing) {
+            let selection = egui::Rect::from_x_y_ranges(
+                x_at(positions[start])..=x_at(positions[end]),
+                rect.y_range(),
+            );
+            ui.painter()
+                .rect_filled(selection, 2.0, Color32::from_rgb(60, 80, 140));
+        }
+        if !calc.result_shown() {
+            let x = x_at(cursor);
+            ui.painter().vline(
+                x,
+                rect.y_range(),
+                Stroke::new(1.5, Color32::from_rgb(100, 180, 255)),
+            );
+        }
+        ui.painter()
+            .galley(rect.min, galley.clone(), Color32::WHITE);
+
+        if response.clicked() {
+            let shift = ui.input(|i| i.modifiers.shift);
+            let cursor = match response.interact_pointer_pos() {
+                Some(pos) if editing => {
+                    let index = galley.cursor_from_pos(pos - rect.min).ccursor.index;
+                    positions
+                        .iter()
+                        .position(|&p| p >= index)
+                        .unwrap_or(positions.len() - 1)
+                }
+                _ => usize::MAX,
+            };
+            self.calc.set_cursor(cursor, shift);
+        }
     }
 
     fn render_mode_bar(&mut self, ui: &mut egui::Ui) {
@@ -314,6 +963,106 @@ impl CalculatorApp {
                 self.calc.toggle_angle_mode();
             }
 
+            // Percent mode: business (200+10% = 220) or plain (10% = 0.1)
+            let percent_text = match self.calc.percent_mode {
+                PercentMode::Business => "BIZ%",
+                PercentMode::Plain => "STD%",
+            };
+            if ui.add(mode_button(percent_text)).clicked() {
+                self.calc.toggle_percent_mode();
+            }
+
+            // Display format, locale and rounding of numbers, chosen in a popup
+            let format = self.calc.display_format;
+            let response = ui.add(mode_button(&format.name()));
+            let popup_id = ui.make_persistent_id("display_format");
+            if response.clicked() {
+                ui.memory_mut(|mem| mem.toggle_popup(popup_id));
+            }
+            let mut chosen = format;
+            let mut rounding = self.calc.rounding;
+            egui::popup_below_widget(
+                ui,
+                popup_id,
+                &response,
+                egui::PopupCloseBehavior::CloseOnClickOutside,
+                |ui| {
+                    ui.set_min_width(200.0);
+                    ui.horizontal(|ui| {
+                        for option in DisplayFormat::ALL {
+                            let selected = option.label() == chosen.label();
+                            if ui.selectable_label(selected, option.label()).clicked() && !selected
+                            {
+                                chosen = option;
+                            }
+                        }
+                    });
+                    if let Some(digits) = chosen.digits() {
+                        let text = match chosen {
+                            DisplayFormat::Fix(_) => "decimals",
+                            DisplayFormat::Sig(_) => "figures",
+                            _ => "mantissa decimals",
+                        };
+                        if let Some(digits) = digit_stepper(ui, text, digits, 0..=MAX_DIGITS) {
+                            chosen = chosen.with_digits(digits);
+                        }
+                    }
+                    if let DisplayFormat::Eng(digits, mut prefixes) = chosen {
+                        if ui
+                            .checkbox(&mut prefixes, "SI prefixes (k, M, µ, n)")
+                            .changed()
+                        {
+                            chosen = DisplayFormat::Eng(digits, prefixes);
+                        }
+                    }
+                    ui.separator();
+                    let locale = &mut self.calc.locale;
+                    ui.horizontal(|ui| {
+                        for (comma, text) in [(false, "1,234.5"), (true, "1.234,5")] {
+                            if ui
+                                .selectable_label(locale.decimal_comma == comma, text)
+                                .clicked()
+                            {
+                                locale.decimal_comma = comma;
+                            }
+                        }
+                    });
+                    ui.checkbox(&mut locale.grouping, "Digit grouping");
+
+                    ui.separator();
+                    ui.horizontal_wrapped(|ui| {
+                        for mode in RoundingMode::ALL {
+                            if ui
+                                .selectable_label(rounding.mode == mode, mode.label())
+                                .clicked()
+                            {
+                                rounding.mode = mode;
+                            }
+                        }
+                    });
+                    let range = 1..=MAX_PRECISION;
+                    if let Some(digits) = digit_stepper(ui, "precision", rounding.precision, range)
+                    {
+                        rounding.precision = digits;
+                    }
+                    ui.checkbox(&mut rounding.stored, "Store results as shown");
+
+                    ui.separator();
+                    ui.checkbox(&mut self.stacked_fractions, "Stacked fractions for ÷");
+                },
+            );
+            if chosen != format {
+                self.calc.set_display_format(chosen);
+            }
+            if rounding != self.calc.rounding {
+                self.calc.set_rounding(rounding);
+            }
+
+            // Exact/decimal form of the shown result
+            if self.calc.exact_result().is_some() && ui.add(mode_button("S⇔D")).clicked() {
+                self.calc.toggle_exact_form();
+            }
+
             ui.add_space(8.0);
 
             // Open parens indicator
@@ -325,18 +1074,76 @@ impl CalculatorApp {
                 );
             }
 
+            // Error estimate of a numerical derivative or integral
+            if let Some(error) = self.calc.error_estimate() {
+                ui.label(
+                    RichText::new(format!("±{:.1e}", error))
+                        .color(Color32::from_rgb(140, 140, 160))
+                        .font(FontId::monospace(12.0)),
+                );
+            }
+
+            // Date of the currency rates behind the shown result
+            if let Some(as_of) = self.calc.rates_as_of() {
+                ui.label(
+                    RichText::new(format!("rates {}", as_of))
+                        .color(Color32::from_rgb(140, 140, 160))
+                        .font(FontId::monospace(12.0)),
+                );
+            }
+
             ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
-                // History toggle
-                let hist_icon = if self.show_history { "123" } else { "H" };
-                if ui.add(mode_button(hist_icon)).clicked() {
-                    self.show_history = !self.show_history;
+                // History and the menu of the other panels; the button of the
+                // active panel switches back to the keypad
+                let text = if self.panel == Panel::History {
+                    "123"
+                } else {
+                    "H"
+                };
+                if ui.add(mode_button(text)).clicked() {
+                    self.panel = if self.panel == Panel::History {
+                        Panel::Keypad
+                    } else {
+                        Panel::History
+                    };
+                }
+                let in_menu = !matches!(self.panel, Panel::Keypad | Panel::History);
+                let text = if in_menu { "123" } else { "MENU" };
+                if ui.add(mode_button(text)).clicked() {
+                    self.panel = if in_menu { Panel::Keypad } else { Panel::Menu };
                 }
             });
         });
     }
 
+    fn render_menu(&mut self, ui: &mut egui::Ui) {
+        egui::Frame::none()
+            .fill(Color32::from_rgb(28, 28, 36))
+            .rounding(Rounding::same(12.0))
+            .inner_margin(egui::Margin::same(12.0))
+            .show(ui, |ui| {
+                ui.set_width(ui.available_width());
+                ui.set_min_height(320.0);
+
+                for (panel, label, description) in Panel::MENU {
+                    ui.horizontal(|ui| {
+                        if ui.add(mode_button(label)).clicked() {
+                            self.panel = panel;
+                        }
+                        ui.label(
+                            RichText::new(description)
+                                .color(Color32::from_rgb(200, 200, 215))
+                                .font(FontId::monospace(13.0)),
+                        );
+                    });
+                    ui.add_space(4.0);
+                }
+            });
+    }
+
     fn render_history(&mut self, ui: &mut egui::Ui) {
         let mut clicked_result: Option<String> = None;
+        let locale = self.calc.locale;
 
         egui::Frame::none()
             .fill(Color32::from_rgb(28, 28, 36))
@@ -360,11 +1167,11 @@ impl CalculatorApp {
                             });
                         } else {
                             for entry in self.calc.history.iter().rev() {
-                                let result_clone = entry.result.clone();
+                                let result_clone = entry.value.clone();
 
                                 let response = ui.horizontal(|ui| {
                                     ui.label(
-                                        RichText::new(&entry.expression)
+                                        RichText::new(locale.localize(&entry.expression))
                                             .color(Color32::from_rgb(140, 140, 160))
                                             .font(FontId::monospace(13.0)),
                                     );
@@ -374,7 +1181,7 @@ impl CalculatorApp {
                                             .font(FontId::monospace(13.0)),
                                     );
                                     ui.label(
-                                        RichText::new(&entry.result)
+                                        RichText::new(locale.localize(&entry.result))
                                             .color(Color32::WHITE)
                                             .font(FontId::monospace(14.0)),
                                     );
@@ -394,67 +1201,2071 @@ impl CalculatorApp {
         // Apply clicked result after iteration
         if let Some(result) = clicked_result {
             self.calc.use_history(&result);
-            self.show_history = false;
+            self.panel = Panel::Keypad;
         }
     }
 
-    fn render_keypad(&mut self, ui: &mut egui::Ui) {
-        let available_width = ui.available_width();
-        let btn_width = (available_width - SPACING * 5.0) / 6.0;
-        let main_btn_width = (available_width - SPACING * 3.0) / 4.0;
+    fn render_stats(&mut self, ui: &mut egui::Ui) {
+        let mut remove_index: Option<usize> = None;
+        let mut insert_name: Option<&'static str> = None;
+        let mut insert_function: Option<&'static str> = None;
+        let field_width = if self.stat_two_var { 80.0 } else { 130.0 };
 
-        // Scientific row 1
-        ui.horizontal(|ui| {
-            ui.spacing_mut().item_spacing.x = SPACING;
+        egui::Frame::none()
+            .fill(Color32::from_rgb(28, 28, 36))
+            .rounding(Rounding::same(12.0))
+            .inner_margin(egui::Margin::same(12.0))
+            .show(ui, |ui| {
+                ui.set_width(ui.available_width());
+                ui.set_min_height(320.0);
 
-            if ui.add(sci_button("sin", btn_width)).clicked() {
-                self.error_message = None;
-                self.calc.input_function("sin");
-            }
-            if ui.add(sci_button("cos", btn_width)).clicked() {
-                self.error_message = None;
-                self.calc.input_function("cos");
-            }
-            if ui.add(sci_button("tan", btn_width)).clicked() {
-                self.error_message = None;
-                self.calc.input_function("tan");
-            }
-            if ui.add(sci_button("log", btn_width)).clicked() {
-                self.error_message = None;
-                self.calc.input_function("log10");
-            }
-            if ui.add(sci_button("ln", btn_width)).clicked() {
-                self.error_message = None;
-                self.calc.input_function("ln");
-            }
-            if ui.add(sci_button("√", btn_width)).clicked() {
-                self.error_message = None;
-                self.calc.input_function("sqrt");
-            }
-        });
+                // Entry row: value(s), paired value(s) and frequency
+                ui.horizontal(|ui| {
+                    let value_edit = ui.add(
+                        egui::TextEdit::singleline(&mut self.stat_value_input)
+                            .hint_text("x")
+                            .font(FontId::monospace(13.0))
+                            .desired_width(field_width),
+                    );
+                    if self.stat_two_var {
+                        ui.add(
+                            egui::TextEdit::singleline(&mut self.stat_y_input)
+                                .hint_text("y")
+                                .font(FontId::monospace(13.0))
+                                .desired_width(field_width),
+                        );
+                    }
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.stat_freq_input)
+                            .hint_text("freq")
+                            .font(FontId::monospace(13.0))
+                            .desired_width(50.0),
+                    );
+                    let submitted =
+                        value_edit.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                    if ui.add(mode_button("ADD")).clicked() || submitted {
+                        self.add_stat_points();
+                        value_edit.request_focus();
+                    }
+                });
 
-        ui.add_space(SPACING);
+                ui.add_space(6.0);
 
-        // Scientific row 2
-        ui.horizontal(|ui| {
-            ui.spacing_mut().item_spacing.x = SPACING;
+                // Data points
+                egui::ScrollArea::vertical()
+                    .id_salt("stat_points")
+                    .max_height(110.0)
+                    .auto_shrink([false, true])
+                    .show(ui, |ui| {
+                        if self.calc.stats.points.is_empty() {
+                            ui.label(
+                                RichText::new("No data")
+                                    .color(Color32::from_rgb(100, 100, 120))
+                                    .font(FontId::monospace(13.0)),
+                            );
+                        }
+                        for (i, point) in self.calc.stats.points.iter().enumerate() {
+                            let value = match point.y {
+                                Some(y) => {
+                                    format!("{}, {}", format_value(point.x), format_value(y))
+                                }
+                                None => format_value(point.x),
+                            };
+                            ui.horizontal(|ui| {
+                                ui.label(
+                                    RichText::new(format!(
+                                        "{:>3}  {}  ×{}",
+                                        i + 1,
+                                        value,
+                                        point.freq
+                                    ))
+                                    .color(Color32::from_rgb(200, 200, 215))
+                                    .font(FontId::monospace(13.0)),
+                                );
+                                ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                                    if ui.small_button("−").clicked() {
+                                        remove_index = Some(i);
+                                    }
+                                });
+                            });
+                        }
+                    });
 
-            if ui.add(sci_button("x²", btn_width)).clicked() {
-                self.error_message = None;
-                self.calc.input_square();
-            }
-            if ui.add(sci_button("xʸ", btn_width)).clicked() {
-                self.error_message = None;
-                self.calc.input_power();
-            }
-            if ui.add(sci_button("(", btn_width)).clicked() {
-                self.error_message = None;
-                self.calc.input_open_paren();
-            }
-            if ui.add(sci_button(")", btn_width)).clicked() {
-                self.error_message = None;
-                self.calc.input_close_paren();
-            }
+                ui.separator();
+
+                // Summary (click a row to insert the named value)
+                egui::ScrollArea::vertical()
+                    .id_salt("stat_summary")
+                    .auto_shrink([false, false])
+                    .show(ui, |ui| {
+                        if self.stat_two_var {
+                            self.render_regression(ui, &mut insert_function);
+                            ui.separator();
+                        }
+                        self.render_summary(ui, &mut insert_name);
+                    });
+
+                ui.horizontal(|ui| {
+                    let mode_text = if self.stat_two_var { "2VAR" } else { "1VAR" };
+                    if ui.add(mode_button(mode_text)).clicked() {
+                        self.stat_two_var = !self.stat_two_var;
+                    }
+                    ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                        if ui.add(mode_button("CLR")).clicked() {
+                            self.calc.stats.clear();
+                        }
+                    });
+                });
+            });
+
+        if let Some(i) = remove_index {
+            self.calc.stats.remove(i);
+        }
+        if let Some(name) = insert_name {
+            self.error_message = None;
+            self.calc.input_constant(name);
+            self.panel = Panel::Keypad;
+        }
+        if let Some(func) = insert_function {
+            self.error_message = None;
+            self.calc.input_function(func);
+            self.panel = Panel::Keypad;
+        }
+    }
+
+    /// One-variable summary (click a row to insert the named value)
+    fn render_summary(&self, ui: &mut egui::Ui, insert_name: &mut Option<&'static str>) {
+        match self.calc.stats.summary() {
+            Some(summary) => {
+                for (name, value) in summary.named_values() {
+                    let response = ui.horizontal(|ui| {
+                        ui.label(
+                            RichText::new(format!("{:<7}", name))
+                                .color(Color32::from_rgb(140, 190, 255))
+                                .font(FontId::monospace(13.0)),
+                        );
+                        ui.label(
+                            RichText::new(format_value(value))
+                                .color(Color32::WHITE)
+                                .font(FontId::monospace(13.0)),
+                        );
+                    });
+                    if response.response.interact(egui::Sense::click()).clicked() {
+                        *insert_name = Some(name);
+                    }
+                }
+            }
+            None => {
+                ui.label(
+                    RichText::new("Enter values to see the summary")
+                        .color(Color32::from_rgb(100, 100, 120))
+                        .font(FontId::monospace(12.0)),
+                );
+            }
+        }
+    }
+
+    /// Regression model selection, coefficients and prediction functions
+    fn render_regression(&mut self, ui: &mut egui::Ui, insert_function: &mut Option<&'static str>) {
+        ui.horizontal(|ui| {
+            for model in Model::ALL {
+                let label = if self.calc.regression_model == model {
+                    format!("[{}]", model.label())
+                } else {
+                    model.label().to_string()
+                };
+                if ui.small_button(label).clicked() {
+                    self.calc.regression_model = model;
+                }
+            }
+        });
+
+        ui.label(
+            RichText::new(self.calc.regression_model.formula())
+                .color(Color32::from_rgb(160, 160, 180))
+                .font(FontId::monospace(13.0)),
+        );
+
+        match self.calc.regression() {
+            Ok(fit) => {
+                let mut rows = vec![("a", fit.a), ("b", fit.b)];
+                if fit.model == Model::Quadratic {
+                    rows.push(("c", fit.c));
+                }
+                rows.push(("r", fit.r));
+                rows.push(("r²", fit.r2));
+
+                for (name, value) in rows {
+                    ui.horizontal(|ui| {
+                        ui.label(
+                            RichText::new(format!("{:<7}", name))
+                                .color(Color32::from_rgb(140, 190, 255))
+                                .font(FontId::monospace(13.0)),
+                        );
+                        ui.label(
+                            RichText::new(format_value(value))
+                                .color(Color32::WHITE)
+                                .font(FontId::monospace(13.0)),
+                        );
+                    });
+                }
+
+                ui.horizontal(|ui| {
+                    for func in ["ŷ", "x̂"] {
+                        if ui.add(mode_button(&format!("{}(", func))).clicked() {
+                            *insert_function = Some(func);
+                        }
+                    }
+                });
+            }
+            Err(e) => {
+                ui.label(
+                    RichText::new(e)
+                        .color(Color32::from_rgb(255, 120, 120))
+                        .font(FontId::monospace(12.0)),
+                );
+            }
+        }
+    }
+
+    /// Dialog that fills in distribution parameters and inserts the call
+    fn render_distributions(&mut self, ui: &mut egui::Ui) {
+        let locale = self.calc.locale;
+        let mut insert = false;
+
+        egui::Frame::none()
+            .fill(Color32::from_rgb(28, 28, 36))
+            .rounding(Rounding::same(12.0))
+            .inner_margin(egui::Margin::same(12.0))
+            .show(ui, |ui| {
+                ui.set_width(ui.available_width());
+                ui.set_min_height(320.0);
+
+                ui.horizontal_wrapped(|ui| {
+                    for dist in Distribution::ALL {
+                        if ui
+                            .selectable_label(self.dist == dist, dist.label())
+                            .clicked()
+                        {
+                            self.dist = dist;
+                        }
+                    }
+                });
+                ui.horizontal(|ui| {
+                    for kind in Kind::ALL {
+                        if ui
+                            .selectable_label(self.dist_kind == kind, kind.label())
+                            .clicked()
+                        {
+                            self.dist_kind = kind;
+                        }
+                    }
+                });
+
+                ui.add_space(8.0);
+
+                let func = self.dist.function(self.dist_kind);
+                ui.label(
+                    RichText::new(format!("{}({})", func.name, func.params.join(", ")))
+                        .color(Color32::WHITE)
+                        .font(FontId::monospace(14.0)),
+                );
+                ui.label(
+                    RichText::new(func.description)
+                        .color(Color32::from_rgb(140, 140, 160))
+                        .font(FontId::monospace(12.0)),
+                );
+
+                ui.add_space(8.0);
+
+                egui::Grid::new("dist_params")
+                    .num_columns(2)
+                    .spacing([8.0, 6.0])
+                    .show(ui, |ui| {
+                        for (i, param) in func.params.iter().enumerate() {
+                            ui.label(
+                                RichText::new(*param)
+                                    .color(Color32::from_rgb(140, 190, 255))
+                                    .font(FontId::monospace(14.0)),
+                            );
+                            let hint = if i < func.required { "" } else { "optional" };
+                            ui.add(
+                                egui::TextEdit::singleline(&mut self.dist_args[i])
+                                    .hint_text(hint)
+                                    .font(FontId::monospace(13.0))
+                                    .desired_width(160.0),
+                            );
+                            ui.end_row();
+                        }
+                    });
+
+                ui.add_space(8.0);
+
+                ui.with_layout(Layout::right_to_left(Align::Min), |ui| {
+                    if ui.add(mode_button("INS")).clicked() {
+                        insert = true;
+                    }
+                });
+            });
+
+        if insert {
+            let func = self.dist.function(self.dist_kind);
+            let args: Option<Vec<String>> = self.dist_args[..func.params.len()]
+                .iter()
+                .map(|a| a.trim())
+                .take_while(|a| !a.is_empty())
+                .map(|a| locale.parse_number(a).map(format_result))
+                .collect();
+
+            match args {
+                Some(args) if args.len() >= func.required => {
+                    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+                    self.error_message = None;
+                    self.calc.input_call(func.name, &args);
+                    self.panel = Panel::Keypad;
+                }
+                _ => self.error_message = Some("Invalid parameters".to_string()),
+            }
+        }
+    }
+
+    /// Matrix editor: inserts the entered matrix as a literal, and the
+    /// matrix functions
+    fn render_matrix_editor(&mut self, ui: &mut egui::Ui) {
+        let locale = self.calc.locale;
+        let mut insert = false;
+
+        egui::Frame::none()
+            .fill(Color32::from_rgb(28, 28, 36))
+            .rounding(Rounding::same(12.0))
+            .inner_margin(egui::Margin::same(12.0))
+            .show(ui, |ui| {
+                ui.set_width(ui.available_width());
+                ui.set_min_height(320.0);
+
+                for (label, size) in [("rows", &mut self.mat_rows), ("cols", &mut self.mat_cols)] {
+                    ui.horizontal(|ui| {
+                        ui.label(
+                            RichText::new(label)
+                                .color(Color32::from_rgb(140, 190, 255))
+                                .font(FontId::monospace(13.0)),
+                        );
+                        for n in 1..=matrix::MAX_SIZE {
+                            if ui.selectable_label(*size == n, n.to_string()).clicked() {
+                                *size = n;
+                            }
+                        }
+                    });
+                }
+                let (rows, cols) = (self.mat_rows, self.mat_cols);
+                self.mat_cells.resize(rows, Vec::new());
+                for row in &mut self.mat_cells {
+                    row.resize(cols, String::new());
+                }
+
+                ui.add_space(4.0);
+
+                let cell_width =
+                    ((ui.available_width() - 4.0 * cols as f32) / cols as f32 - 8.0).min(60.0);
+                egui::Grid::new("mat_cells")
+                    .num_columns(cols)
+                    .spacing([4.0, 4.0])
+                    .show(ui, |ui| {
+                        for row in &mut self.mat_cells {
+                            for cell in row.iter_mut() {
+                                ui.add(
+                                    egui::TextEdit::singleline(cell)
+                                        .hint_text("0")
+                                        .font(FontId::monospace(12.0))
+                                        .desired_width(cell_width),
+                                );
+                            }
+                            ui.end_row();
+                        }
+                    });
+
+                ui.with_layout(Layout::right_to_left(Align::Min), |ui| {
+                    if ui.add(mode_button("INS")).clicked() {
+                        insert = true;
+                    }
+                });
+
+                ui.separator();
+
+                // Functions are inserted with an open parenthesis for the
+                // matrix that follows
+                ui.horizontal_wrapped(|ui| {
+                    for (label, func) in [
+                        ("det", "det"),
+                        ("inv", "inv"),
+                        ("trn", "transpose"),
+                        ("rank", "rank"),
+                        ("norm", "norm"),
+                        ("dot", "dot"),
+                        ("cross", "cross"),
+                        ("eig", "eig"),
+                    ] {
+                        if ui.add(mode_button(label)).clicked() {
+                            self.error_message = None;
+                            self.calc.input_function(func);
+                        }
+                    }
+                    if ui.add(mode_button(",")).clicked() {
+                        self.calc.input_separator();
+                    }
+                });
+            });
+
+        if insert {
+            let matrix = self
+                .mat_cells
+                .iter()
+                .map(|row| parse_fields(row, locale))
+                .collect::<Result<Vec<_>, _>>()
+                .and_then(|rows| Matrix::from_rows(&rows));
+            match matrix {
+                Ok(m) => {
+                    self.error_message = None;
+                    self.calc.use_history(&m.to_string());
+                    self.panel = Panel::Keypad;
+                }
+                Err(e) => self.error_message = Some(e),
+            }
+        }
+    }
+
+    /// Graphs of up to three functions of x, parametric curves or polar
+    /// curves, with pan (drag), zoom (scroll) and, for functions, marked
+    /// roots, extrema and intersections
+    fn render_plot(&mut self, ui: &mut egui::Ui) {
+        egui::Frame::none()
+            .fill(Color32::from_rgb(28, 28, 36))
+            .rounding(Rounding::same(12.0))
+            .inner_margin(egui::Margin::same(12.0))
+            .show(ui, |ui| {
+                ui.set_width(ui.available_width());
+                ui.set_min_height(320.0);
+
+                ui.horizontal(|ui| {
+                    for mode in PlotMode::ALL {
+                        if ui
+                            .selectable_label(self.plot_mode == mode, mode.label())
+                            .clicked()
+                        {
+                            self.plot_mode = mode;
+                        }
+                    }
+                });
+
+                let mode = self.plot_mode;
+                let fields = mode.fields();
+                let entries = &mut self.plot_entries[mode as usize];
+                let mut add = false;
+                let mut remove: Option<usize> = None;
+                let count = entries.len();
+                for (i, entry) in entries.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        let width = (ui.available_width() - 28.0) / fields.len() as f32 - 24.0;
+                        for ((label, hint), text) in fields.iter().zip(entry.iter_mut()) {
+                            ui.label(
+                                RichText::new(*label)
+                                    .color(PLOT_COLORS[i])
+                                    .font(FontId::monospace(13.0)),
+                            );
+                            ui.add(
+                                egui::TextEdit::singleline(text)
+                                    .hint_text(*hint)
+                                    .font(FontId::monospace(13.0))
+                                    .desired_width(width),
+                            );
+                        }
+                        if i + 1 == count && count < PLOT_COLORS.len() {
+                            add = ui.small_button("+").clicked();
+                        } else if ui.small_button("−").clicked() {
+                            remove = Some(i);
+                        }
+                    });
+                }
+                if add {
+                    entries.push(Default::default());
+                }
+                if let Some(i) = remove {
+                    entries.remove(i);
+                }
+
+                // One function per field of each curve
+                let mut graphs: Vec<Vec<Graph>> = self.plot_entries[mode as usize]
+                    .iter()
+                    .map(|entry| {
+                        entry[..fields.len()]
+                            .iter()
+                            .zip(fields)
+                            .map(|(text, (label, _))| {
+                                let text = text.trim();
+                                let text = text.strip_prefix(label).unwrap_or(text);
+                                if text.is_empty() {
+                                    Err(String::new())
+                                } else {
+                                    self.calc.bind(text, mode.variable())
+                                }
+                            })
+                            .collect()
+                    })
+                    .collect();
+                let error = graphs.iter().flatten().find_map(|g| match g {
+                    Err(e) if !e.is_empty() => Some(e.clone()),
+                    _ => None,
+                });
+                if let Some(e) = error {
+                    ui.label(
+                        RichText::new(e)
+                            .color(Color32::from_rgb(255, 120, 120))
+                            .font(FontId::monospace(12.0)),
+                    );
+                }
+
+                ui.horizontal(|ui| {
+                    if mode == PlotMode::Function {
+                        for (mark, label) in self.plot_marks.iter_mut().zip(["ROOT", "EXT", "INT"])
+                        {
+                            if ui.selectable_label(*mark, label).clicked() {
+                                *mark = !*mark;
+                            }
+                        }
+                    } else {
+                        let name = if mode == PlotMode::Polar { "θ" } else { "t" };
+                        for (field, hint) in self.plot_range.iter_mut().zip([
+                            format!("{} min", name),
+                            format!("{} max", name),
+                            "steps".into(),
+                        ]) {
+                            ui.add(
+                                egui::TextEdit::singleline(field)
+                                    .hint_text(hint)
+                                    .font(FontId::monospace(12.0))
+                                    .desired_width(44.0),
+                            );
+                        }
+                    }
+                    ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                        if ui.add(mode_button("RST")).clicked() {
+                            self.plot_view = View::default();
+                        }
+                        let view = self.plot_view;
+                        let (cx, cy) = (
+                            0.5 * (view.x_min + view.x_max),
+                            0.5 * (view.y_min + view.y_max),
+                        );
+                        if ui.add(mode_button("−")).clicked() {
+                            self.plot_view.zoom(2.0, cx, cy);
+                        }
+                        if ui.add(mode_button("+")).clicked() {
+                            self.plot_view.zoom(0.5, cx, cy);
+                        }
+                    });
+                });
+
+                let range = match self.plot_range() {
+                    Ok(range) => range,
+                    Err(e) => {
+                        ui.label(
+                            RichText::new(e)
+                                .color(Color32::from_rgb(255, 120, 120))
+                                .font(FontId::monospace(12.0)),
+                        );
+                        return;
+                    }
+                };
+                let radians = match self.calc.angle_mode {
+                    AngleMode::Degrees => std::f64::consts::PI / 180.0,
+                    AngleMode::Radians => 1.0,
+                };
+                // Functions of x also get marked points and hover values
+                let functions: Vec<Graph> = match mode {
+                    PlotMode::Function => {
+                        std::mem::take(&mut graphs).into_iter().flatten().collect()
+                    }
+                    _ => Vec::new(),
+                };
+                let curves = |view: &View, samples: usize| match mode {
+                    PlotMode::Function => functions
+                        .iter()
+                        .map(|f| match f {
+                            Ok(f) => plot::sample(f, view, samples),
+                            Err(_) => Vec::new(),
+                        })
+                        .collect(),
+                    _ => graphs
+                        .iter()
+                        .map(|graph| match graph.as_slice() {
+                            [Ok(x), Ok(y)] => plot::sample_curve(x, y, range.0, range.1, view),
+                            [Ok(r)] => plot::sample_polar(r, range.0, range.1, radians, view),
+                            _ => Vec::new(),
+                        })
+                        .collect(),
+                };
+
+                let height = 200.0 - 26.0 * (count - 1) as f32;
+                self.render_plot_canvas(ui, &functions, &curves, height);
+            });
+    }
+
+    /// Range and number of steps of the curve parameter; the range defaults
+    /// to one full turn in the current angle mode
+    fn plot_range(&self) -> Result<((f64, f64), usize), String> {
+        let turn = match self.calc.angle_mode {
+            AngleMode::Degrees => 360.0,
+            AngleMode::Radians => 2.0 * std::f64::consts::PI,
+        };
+        let bound = |text: &str, default: f64| match text.trim() {
+            "" => Ok(default),
+            t => self.calc.evaluate_text(t),
+        };
+        let lo = bound(&self.plot_range[0], 0.0)?;
+        let hi = bound(&self.plot_range[1], turn)?;
+        let steps = match self.plot_range[2].trim() {
+            "" => plot::DEFAULT_STEPS,
+            t => t
+                .parse()
+                .ok()
+                .filter(|n| (1..=plot::MAX_STEPS).contains(n))
+                .ok_or_else(|| format!("Steps must be 1 to {}", plot::MAX_STEPS))?,
+        };
+        if !lo.is_finite() || !hi.is_finite() || lo >= hi {
+            return Err("Invalid range".to_string());
+        }
+        Ok(((lo, hi), steps))
+    }
+
+    /// Plot canvas drawing the lines from `curves` for the view and sample
+    /// count; marked points and hover values are shown for `functions`
+    fn render_plot_canvas(
+        &mut self,
+        ui: &mut egui::Ui,
+        functions: &[Graph],
+        curves: &Curves,
+        height: f32,
+    ) {
+        let (response, painter) = ui.allocate_painter(
+            Vec2::new(ui.available_width(), height),
+            egui::Sense::click_and_drag(),
+        );
+        let rect = response.rect;
+        painter.rect_filled(rect, Rounding::same(6.0), Color32::from_rgb(20, 20, 28));
+
+        // Pan by dragging, zoom with the scroll wheel or pinch
+        let delta = response.drag_delta();
+        if delta != Vec2::ZERO {
+            let view = self.plot_view;
+            self.plot_view.pan(
+                -(delta.x / rect.width()) as f64 * view.width(),
+                (delta.y / rect.height()) as f64 * view.height(),
+            );
+        }
+        if let Some(pointer) = response.hover_pos() {
+            let (scroll, pinch) = ui.input(|i| (i.smooth_scroll_delta.y, i.zoom_delta()));
+            let factor = (-scroll as f64 * 0.003).exp() / pinch as f64;
+            if factor != 1.0 {
+                let (x, y) = to_graph(&self.plot_view, rect, pointer);
+                self.plot_view.zoom(factor, x, y);
+            }
+        }
+
+        let view = self.plot_view;
+        let to_screen = |x: f64, y: f64| {
+            // Far away points are pulled in to keep the coordinates small
+            let y = y.clamp(view.y_min - view.height(), view.y_max + view.height());
+            egui::pos2(
+                rect.left() + ((x - view.x_min) / view.width()) as f32 * rect.width(),
+                rect.bottom() - ((y - view.y_min) / view.height()) as f32 * rect.height(),
+            )
+        };
+
+        // Grid, axes and tick labels
+        let grid = Stroke::new(1.0, Color32::from_rgb(38, 38, 50));
+        let axis = Stroke::new(1.0, Color32::from_rgb(100, 100, 120));
+        let label_font = FontId::monospace(9.0);
+        let label_color = Color32::from_rgb(120, 120, 140);
+        let axis_x = to_screen(0.0, 0.0)
+            .x
+            .clamp(rect.left(), rect.right() - 24.0);
+        let axis_y = to_screen(0.0, 0.0)
+            .y
+            .clamp(rect.top() + 10.0, rect.bottom());
+        for x in plot::ticks(view.x_min, view.x_max, 6) {
+            let px = to_screen(x, 0.0).x;
+            let stroke = if x == 0.0 { axis } else { grid };
+            painter.vline(px, rect.y_range(), stroke);
+            if x != 0.0 {
+                painter.text(
+                    egui::pos2(px + 2.0, axis_y),
+                    egui::Align2::LEFT_BOTTOM,
+                    solver::short(x),
+                    label_font.clone(),
+                    label_color,
+                );
+            }
+        }
+        for y in plot::ticks(view.y_min, view.y_max, 5) {
+            let py = to_screen(0.0, y).y;
+            let stroke = if y == 0.0 { axis } else { grid };
+            painter.hline(rect.x_range(), py, stroke);
+            if y != 0.0 {
+                painter.text(
+                    egui::pos2(axis_x + 2.0, py),
+                    egui::Align2::LEFT_BOTTOM,
+                    solver::short(y),
+                    label_font.clone(),
+                    label_color,
+                );
+            }
+        }
+
+        // Graphs
+        let samples = (rect.width() as usize).max(100);
+        for (lines, color) in curves(&view, samples).into_iter().zip(PLOT_COLORS) {
+            for line in lines {
+                let points = line.iter().map(|&(x, y)| to_screen(x, y)).collect();
+                painter.add(egui::Shape::line(points, Stroke::new(2.0, color)));
+            }
+        }
+
+        // Marked points, recomputed when the mode, the functions or the view change
+        let key: PlotKey = (
+            self.plot_mode,
+            self.plot_entries[PlotMode::Function as usize].clone(),
+            view,
+            self.calc.angle_mode,
+            self.plot_marks,
+        );
+        if self.plot_features.as_ref().map(|(k, _)| k) != Some(&key) {
+            let features = plot_features(functions, &view, self.plot_marks);
+            self.plot_features = Some((key, features));
+        }
+        let features = self.plot_features.as_ref().map_or(&[][..], |(_, f)| f);
+        for feature in features {
+            let center = to_screen(feature.x, feature.y);
+            let color = match feature.kind {
+                FeatureKind::Root => Color32::WHITE,
+                FeatureKind::Minimum | FeatureKind::Maximum => Color32::from_rgb(255, 210, 90),
+                FeatureKind::Intersection => Color32::from_rgb(230, 120, 230),
+            };
+            painter.circle(center, 3.5, color, Stroke::new(1.0, Color32::BLACK));
+        }
+
+        // Coordinates under the pointer, or of a marked point next to it
+        if let Some(pointer) = response.hover_pos() {
+            let near = features
+                .iter()
+                .find(|f| to_screen(f.x, f.y).distance(pointer) < 8.0);
+            let text = match near {
+                Some(f) => format!(
+                    "{} ({}, {})",
+                    f.kind.label(),
+                    solver::short(f.x),
+                    solver::short(f.y)
+                ),
+                None => {
+                    let (x, y) = to_graph(&view, rect, pointer);
+                    let mut text = format!("x={}  y={}", short_coordinate(x), short_coordinate(y));
+                    for (i, f) in functions.iter().enumerate() {
+                        if let Ok(f) = f {
+                            let fx = f(x);
+                            if fx.is_finite() {
+                                painter.circle_filled(to_screen(x, fx), 3.0, PLOT_COLORS[i]);
+                                text.push_str(&format!("\ny{}={}", i + 1, short_coordinate(fx)));
+                            }
+                        }
+                    }
+                    text
+                }
+            };
+            painter.text(
+                rect.left_top() + Vec2::new(6.0, 4.0),
+                egui::Align2::LEFT_TOP,
+                text,
+                FontId::monospace(11.0),
+                Color32::WHITE,
+            );
+        }
+    }
+
+    /// Table of values of an expression in x over a start, stop and step
+    /// range; rows are copied by clicking them
+    fn render_table(&mut self, ui: &mut egui::Ui) {
+        let mut generate = false;
+        let mut copy: Option<String> = None;
+        let format = self.calc.display_format;
+        let rounding = self.calc.rounding.mode;
+        let locale = self.calc.locale;
+
+        egui::Frame::none()
+            .fill(Color32::from_rgb(28, 28, 36))
+            .rounding(Rounding::same(12.0))
+            .inner_margin(egui::Margin::same(12.0))
+            .show(ui, |ui| {
+                ui.set_width(ui.available_width());
+                ui.set_min_height(320.0);
+
+                let edit = ui.add(
+                    egui::TextEdit::singleline(&mut self.table_expression)
+                        .hint_text("x²−2")
+                        .font(FontId::monospace(14.0))
+                        .desired_width(f32::INFINITY),
+                );
+                generate |= edit.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+
+                ui.horizontal(|ui| {
+                    for (field, hint) in self.table_range.iter_mut().zip(["start", "stop", "step"])
+                    {
+                        ui.add(
+                            egui::TextEdit::singleline(field)
+                                .hint_text(hint)
+                                .font(FontId::monospace(13.0))
+                                .desired_width(56.0),
+                        );
+                    }
+                    ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                        generate |= ui.add(mode_button("GEN")).clicked();
+                    });
+                });
+
+                ui.separator();
+
+                egui::ScrollArea::vertical()
+                    .id_salt("table_rows")
+                    .max_height(210.0)
+                    .auto_shrink([false, false])
+                    .show(ui, |ui| match &self.table_rows {
+                        Some(Ok(rows)) => {
+                            egui::Grid::new("table_grid")
+                                .num_columns(2)
+                                .min_col_width(120.0)
+                                .striped(true)
+                                .show(ui, |ui| {
+                                    for header in ["x", "y"] {
+                                        ui.label(
+                                            RichText::new(header)
+                                                .color(Color32::from_rgb(140, 190, 255))
+                                                .font(FontId::monospace(13.0)),
+                                        );
+                                    }
+                                    ui.end_row();
+
+                                    for &(x, y) in rows {
+                                        let mut clicked = false;
+                                        for value in [x, y] {
+                                            let color = if value.is_finite() {
+                                                Color32::WHITE
+                                            } else {
+                                                Color32::from_rgb(255, 120, 120)
+                                            };
+                                            let label = ui.label(
+                                                RichText::new(locale.localize(&table::cell(
+                                                    value, format, rounding,
+                                                )))
+                                                .color(color)
+                                                .font(FontId::monospace(13.0)),
+                                            );
+                                            clicked |=
+                                                label.interact(egui::Sense::click()).clicked();
+                                        }
+                                        if clicked {
+                                            copy = Some(table::csv_row((x, y), format, rounding));
+                                        }
+                                        ui.end_row();
+                                    }
+                                });
+                        }
+                        Some(Err(e)) => {
+                            ui.label(
+                                RichText::new(e)
+                                    .color(Color32::from_rgb(255, 120, 120))
+                                    .font(FontId::monospace(13.0)),
+                            );
+                        }
+                        None => {}
+                    });
+
+                ui.horizontal(|ui| {
+                    if let Some(status) = &self.table_status {
+                        ui.label(
+                            RichText::new(status)
+                                .color(Color32::from_rgb(140, 140, 160))
+                                .font(FontId::monospace(12.0)),
+                        );
+                    }
+                    ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                        let rows = match &self.table_rows {
+                            Some(Ok(rows)) => Some(rows),
+                            _ => None,
+                        };
+                        if let Some(rows) = rows {
+                            if ui.add(mode_button("CSV")).clicked() {
+                                copy = Some(table::to_csv(
+                                    self.table_expression.trim(),
+                                    rows,
+                                    format,
+                                    rounding,
+                                ));
+                            }
+                        }
+                    });
+                });
+            });
+
+        if generate {
+            self.table_rows = Some(self.run_table());
+            self.table_status = None;
+        }
+        if let Some(text) = copy {
+            self.table_status = Some(if text.contains('\n') {
+                "Table copied as CSV".to_string()
+            } else {
+                format!("Copied {}", text)
+            });
+            ui.ctx().copy_text(text);
+        }
+    }
+
+    fn run_table(&self) -> Result<Vec<(f64, f64)>, String> {
+        let locale = self.calc.locale;
+        let f = self.calc.bind_x(&self.table_expression)?;
+        let range = parse_fields(&self.table_range, locale)?;
+        table::generate(f, range[0], range[1], range[2])
+    }
+
+    /// Financial panel with tabs for the time value of money, the
+    /// amortisation schedule, cash flows and depreciation
+    fn render_finance(&mut self, ui: &mut egui::Ui) {
+        let mut picked: Option<f64> = None;
+
+        egui::Frame::none()
+            .fill(Color32::from_rgb(28, 28, 36))
+            .rounding(Rounding::same(12.0))
+            .inner_margin(egui::Margin::same(12.0))
+            .show(ui, |ui| {
+                ui.set_width(ui.available_width());
+                ui.set_min_height(320.0);
+
+                ui.horizontal(|ui| {
+                    for tab in FinanceTab::ALL {
+                        if ui
+                            .selectable_label(self.fin_tab == tab, tab.label())
+                            .clicked()
+                        {
+                            self.fin_tab = tab;
+                        }
+                    }
+                });
+                ui.add_space(6.0);
+
+                picked = match self.fin_tab {
+                    FinanceTab::Tvm => self.render_tvm(ui),
+                    FinanceTab::Amortization => {
+                        self.render_amortization(ui);
+                        None
+                    }
+                    FinanceTab::CashFlow => self.render_cash_flows(ui),
+                    FinanceTab::Depreciation => {
+                        self.render_depreciation(ui);
+                        None
+                    }
+                };
+            });
+
+        // Picking a result inserts it into the expression
+        if let Some(value) = picked {
+            self.error_message = None;
+            self.calc.use_history(&value_text(value));
+            self.panel = Panel::Keypad;
+        }
+    }
+
+    /// Fields for N, I/Y, PV, PMT and FV; CPT solves for one of them from
+    /// the other four
+    fn render_tvm(&mut self, ui: &mut egui::Ui) -> Option<f64> {
+        let mut picked: Option<f64> = None;
+        let mut compute: Option<TvmVariable> = None;
+
+        egui::Grid::new("tvm_fields")
+            .num_columns(3)
+            .spacing([8.0, 6.0])
+            .show(ui, |ui| {
+                for (variable, text) in TvmVariable::ALL.into_iter().zip(&mut self.fin_tvm) {
+                    ui.label(
+                        RichText::new(variable.label())
+                            .color(Color32::from_rgb(140, 190, 255))
+                            .font(FontId::monospace(13.0)),
+                    );
+                    ui.add(
+                        egui::TextEdit::singleline(text)
+                            .hint_text("0")
+                            .font(FontId::monospace(13.0))
+                            .desired_width(140.0),
+                    );
+                    if ui.add(mode_button("CPT")).clicked() {
+                        compute = Some(variable);
+                    }
+                    ui.end_row();
+                }
+            });
+
+        ui.horizontal(|ui| {
+            for (due, label) in [(false, "END"), (true, "BGN")] {
+                if ui.selectable_label(self.fin_due == due, label).clicked() {
+                    self.fin_due = due;
+                }
+            }
+            ui.label(
+                RichText::new("I/Y in % per period")
+                    .color(Color32::from_rgb(140, 140, 160))
+                    .font(FontId::monospace(12.0)),
+            );
+        });
+
+        if let Some(variable) = compute {
+            let result = self.tvm_problem().and_then(|tvm| tvm.solve(variable));
+            if let Ok(value) = result {
+                let shown = match variable {
+                    TvmVariable::Rate => value * 100.0,
+                    _ => value,
+                };
+                self.fin_tvm[variable as usize] = format_result(shown).replace('-', "−");
+            }
+            self.fin_result = Some(result.map(|value| (variable, value)));
+        }
+
+        ui.separator();
+
+        match &self.fin_result {
+            Some(Ok((variable, value))) => {
+                let response = ui.label(
+                    RichText::new(format!("{} = {}", variable.label(), format_result(*value)))
+                        .color(Color32::WHITE)
+                        .font(FontId::monospace(14.0)),
+                );
+                if response.interact(egui::Sense::click()).clicked() {
+                    picked = Some(*value);
+                }
+            }
+            Some(Err(e)) => {
+                ui.label(
+                    RichText::new(e)
+                        .color(Color32::from_rgb(255, 120, 120))
+                        .font(FontId::monospace(13.0)),
+                );
+            }
+            None => {}
+        }
+        picked
+    }
+
+    /// Time value of money problem from the TVM fields
+    fn tvm_problem(&self) -> Result<Tvm, String> {
+        let locale = self.calc.locale;
+        let values = parse_fields(&self.fin_tvm, locale)?;
+        Ok(Tvm {
+            n: values[0],
+            rate: values[1] / 100.0,
+            pv: values[2],
+            pmt: values[3],
+            fv: values[4],
+            due: self.fin_due,
+        })
+    }
+
+    /// Interest, principal and balance of each payment of the TVM problem
+    fn render_amortization(&mut self, ui: &mut egui::Ui) {
+        let schedule = self.tvm_problem().and_then(|tvm| tvm.amortization());
+        let rows = match schedule {
+            Ok(rows) => rows,
+            Err(e) => {
+                ui.label(
+                    RichText::new(format!("{} (set N, I/Y, PV and PMT in TVM)", e))
+                        .color(Color32::from_rgb(255, 120, 120))
+                        .font(FontId::monospace(13.0)),
+                );
+                return;
+            }
+        };
+
+        let total: f64 = rows.iter().map(|row| row.interest).sum();
+        ui.label(
+            RichText::new(format!("Σ INT = {}", format_money(total)))
+                .color(Color32::from_rgb(140, 140, 160))
+                .font(FontId::monospace(12.0)),
+        );
+
+        egui::ScrollArea::vertical()
+            .id_salt("amortization_rows")
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                egui::Grid::new("amortization_grid")
+                    .num_columns(4)
+                    .spacing([12.0, 2.0])
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for header in ["#", "INT", "PRN", "BAL"] {
+                            ui.label(
+                                RichText::new(header)
+                                    .color(Color32::from_rgb(140, 190, 255))
+                                    .font(FontId::monospace(12.0)),
+                            );
+                        }
+                        ui.end_row();
+
+                        for row in rows {
+                            ui.label(
+                                RichText::new(row.period.to_string())
+                                    .color(Color32::from_rgb(140, 140, 160))
+                                    .font(FontId::monospace(12.0)),
+                            );
+                            for value in [row.interest, row.principal, row.balance] {
+                                ui.label(
+                                    RichText::new(format_money(value))
+                                        .color(Color32::WHITE)
+                                        .font(FontId::monospace(12.0)),
+                                );
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+    }
+
+    /// NPV and IRR of a list of cash flows starting at time 0
+    fn render_cash_flows(&mut self, ui: &mut egui::Ui) -> Option<f64> {
+        let locale = self.calc.locale;
+        let mut picked: Option<f64> = None;
+
+        ui.add(
+            egui::TextEdit::singleline(&mut self.fin_flows)
+                .hint_text("CF0 CF1 ... e.g. −1000 300 400 500")
+                .font(FontId::monospace(14.0))
+                .desired_width(f32::INFINITY),
+        );
+        ui.horizontal(|ui| {
+            ui.label(
+                RichText::new("I %")
+                    .color(Color32::from_rgb(140, 190, 255))
+                    .font(FontId::monospace(13.0)),
+            );
+            ui.add(
+                egui::TextEdit::singleline(&mut self.fin_rate)
+                    .hint_text("10")
+                    .font(FontId::monospace(13.0))
+                    .desired_width(80.0),
+            );
+        });
+
+        ui.separator();
+
+        let flows = match parse_number_list(&self.fin_flows, locale) {
+            Some(flows) if flows.len() >= 2 => flows,
+            Some(_) => return None,
+            None => {
+                ui.label(
+                    RichText::new("Invalid cash flow")
+                        .color(Color32::from_rgb(255, 120, 120))
+                        .font(FontId::monospace(13.0)),
+                );
+                return None;
+            }
+        };
+        let rate = parse_fields(std::slice::from_ref(&self.fin_rate), locale).map(|r| r[0] / 100.0);
+        let results = [
+            ("NPV", rate.map(|r| finance::npv(r, &flows))),
+            ("IRR %", Ok(finance::irr(&flows) * 100.0)),
+        ];
+        for (label, result) in results {
+            let (text, color) = match &result {
+                Ok(value) if value.is_finite() => (
+                    format!("{} = {}", label, format_result(*value)),
+                    Color32::WHITE,
+                ),
+                Ok(_) => (
+                    format!("{} = none", label),
+                    Color32::from_rgb(140, 140, 160),
+                ),
+                Err(e) => (e.clone(), Color32::from_rgb(255, 120, 120)),
+            };
+            let response = ui.label(
+                RichText::new(text)
+                    .color(color)
+                    .font(FontId::monospace(14.0)),
+            );
+            if response.interact(egui::Sense::click()).clicked() {
+                picked = result.ok().filter(|v| v.is_finite());
+            }
+        }
+        picked
+    }
+
+    /// Straight-line or declining-balance depreciation schedule
+    fn render_depreciation(&mut self, ui: &mut egui::Ui) {
+        let locale = self.calc.locale;
+        egui::Grid::new("asset_fields")
+            .num_columns(2)
+            .spacing([8.0, 6.0])
+            .show(ui, |ui| {
+                for (label, text) in ["cost", "salvage", "life"]
+                    .into_iter()
+                    .zip(&mut self.fin_asset)
+                {
+                    ui.label(
+                        RichText::new(label)
+                            .color(Color32::from_rgb(140, 190, 255))
+                            .font(FontId::monospace(13.0)),
+                    );
+                    ui.add(
+                        egui::TextEdit::singleline(text)
+                            .hint_text("0")
+                            .font(FontId::monospace(13.0))
+                            .desired_width(120.0),
+                    );
+                    ui.end_row();
+                }
+            });
+
+        ui.horizontal(|ui| {
+            for (declining, label) in [(false, "SL"), (true, "DB")] {
+                if ui
+                    .selectable_label(self.fin_declining == declining, label)
+                    .clicked()
+                {
+                    self.fin_declining = declining;
+                }
+            }
+            if self.fin_declining {
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.fin_factor)
+                        .hint_text("factor 2")
+                        .font(FontId::monospace(12.0))
+                        .desired_width(64.0),
+                );
+            }
+        });
+
+        ui.separator();
+
+        let factor = match self.fin_factor.trim() {
+            "" => Ok(2.0),
+            _ => parse_fields(std::slice::from_ref(&self.fin_factor), locale).map(|f| f[0]),
+        };
+        let schedule = parse_fields(&self.fin_asset, locale).and_then(|asset| {
+            let factor = if self.fin_declining {
+                Some(factor?)
+            } else {
+                None
+            };
+            finance::depreciation(asset[0], asset[1], asset[2], factor)
+        });
+        let rows = match schedule {
+            Ok(rows) => rows,
+            Err(e) => {
+                ui.label(
+                    RichText::new(e)
+                        .color(Color32::from_rgb(255, 120, 120))
+                        .font(FontId::monospace(13.0)),
+                );
+                return;
+            }
+        };
+
+        egui::ScrollArea::vertical()
+            .id_salt("depreciation_rows")
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                egui::Grid::new("depreciation_grid")
+                    .num_columns(3)
+                    .spacing([16.0, 2.0])
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for header in ["#", "DEP", "BOOK"] {
+                            ui.label(
+                                RichText::new(header)
+                                    .color(Color32::from_rgb(140, 190, 255))
+                                    .font(FontId::monospace(12.0)),
+                            );
+                        }
+                        ui.end_row();
+
+                        for (i, (amount, book)) in rows.into_iter().enumerate() {
+                            ui.label(
+                                RichText::new((i + 1).to_string())
+                                    .color(Color32::from_rgb(140, 140, 160))
+                                    .font(FontId::monospace(12.0)),
+                            );
+                            for value in [amount, book] {
+                                ui.label(
+                                    RichText::new(format_money(value))
+                                        .color(Color32::WHITE)
+                                        .font(FontId::monospace(12.0)),
+                                );
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+    }
+
+    /// Currency rate table with its date, editable rates and buttons that
+    /// insert currencies, time units and `to`
+    fn render_currency(&mut self, ui: &mut egui::Ui) {
+        let locale = self.calc.locale;
+        let mut insert: Option<String> = None;
+        let mut remove: Option<String> = None;
+        let rates = &mut self.calc.rates;
+        if self.cur_fields.len() != rates.rates.len() {
+            self.cur_fields = rates.rates.iter().map(|(_, r)| r.to_string()).collect();
+        }
+
+        egui::Frame::none()
+            .fill(Color32::from_rgb(28, 28, 36))
+            .rounding(Rounding::same(12.0))
+            .inner_margin(egui::Margin::same(12.0))
+            .show(ui, |ui| {
+                ui.set_width(ui.available_width());
+                ui.set_min_height(320.0);
+
+                ui.horizontal(|ui| {
+                    ui.label(
+                        RichText::new("as of")
+                            .color(Color32::from_rgb(140, 190, 255))
+                            .font(FontId::monospace(13.0)),
+                    );
+                    ui.add(
+                        egui::TextEdit::singleline(&mut rates.as_of)
+                            .hint_text("YYYY-MM-DD")
+                            .font(FontId::monospace(13.0))
+                            .desired_width(100.0),
+                    );
+                    ui.label(
+                        RichText::new(format!("per 1 {}", rates.base))
+                            .color(Color32::from_rgb(140, 140, 160))
+                            .font(FontId::monospace(12.0)),
+                    );
+                });
+
+                ui.horizontal(|ui| {
+                    if ui.add(mode_button("to")).clicked() {
+                        insert = Some(String::new());
+                    }
+                    for (unit, _) in units::TIME_UNITS {
+                        if ui.add(mode_button(unit)).clicked() {
+                            insert = Some(unit.to_string());
+                        }
+                    }
+                });
+
+                ui.separator();
+
+                egui::ScrollArea::vertical()
+                    .id_salt("currency_rates")
+                    .max_height(150.0)
+                    .auto_shrink([false, false])
+                    .show(ui, |ui| {
+                        egui::Grid::new("currency_grid")
+                            .num_columns(3)
+                            .spacing([8.0, 4.0])
+                            .show(ui, |ui| {
+                                for ((code, rate), field) in
+                                    rates.rates.iter_mut().zip(&mut self.cur_fields)
+                                {
+                                    if ui.add(mode_button(code)).clicked() {
+                                        insert = Some(code.clone());
+                                    }
+                                    let edit = ui.add(
+                                        egui::TextEdit::singleline(field)
+                                            .font(FontId::monospace(13.0))
+                                            .desired_width(120.0),
+                                    );
+                                    if edit.changed() {
+                                        match locale.parse_number(field) {
+                                            Some(r) if r.is_finite() && r > 0.0 => *rate = r,
+                                            _ => {}
+                                        }
+                                    }
+                                    if *code != rates.base && ui.small_button("−").clicked() {
+                                        remove = Some(code.clone());
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                    });
+
+                ui.horizontal(|ui| {
+                    let [code, rate] = &mut self.cur_new;
+                    ui.add(
+                        egui::TextEdit::singleline(code)
+                            .hint_text("XAU")
+                            .font(FontId::monospace(13.0))
+                            .desired_width(44.0),
+                    );
+                    ui.add(
+                        egui::TextEdit::singleline(rate)
+                            .hint_text("rate")
+                            .font(FontId::monospace(13.0))
+                            .desired_width(96.0),
+                    );
+                    if ui.small_button("+").clicked() {
+                        let result = parse_fields(std::slice::from_ref(rate), locale)
+                            .and_then(|r| rates.set(code.trim(), r[0]));
+                        match result {
+                            Ok(()) => self.cur_new = Default::default(),
+                            Err(e) => self.cur_status = Some(Err(e)),
+                        }
+                    }
+                    ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                        if ui.add(mode_button("SAVE")).clicked() {
+                            self.cur_status = Some(
+                                rates
+                                    .save()
+                                    .map(|path| format!("Saved to {}", path.display())),
+                            );
+                        }
+                    });
+                });
+
+                match &self.cur_status {
+                    Some(Ok(message)) => {
+                        ui.label(
+                            RichText::new(message)
+                                .color(Color32::from_rgb(140, 140, 160))
+                                .font(FontId::monospace(11.0)),
+                        );
+                    }
+                    Some(Err(e)) => {
+                        ui.label(
+                            RichText::new(e)
+                                .color(Color32::from_rgb(255, 120, 120))
+                                .font(FontId::monospace(12.0)),
+                        );
+                    }
+                    None => {}
+                }
+            });
+
+        if let Some(code) = remove {
+            self.calc.rates.remove(&code);
+        }
+        // An empty unit stands for `to`
+        if let Some(unit) = insert {
+            self.error_message = None;
+            if unit.is_empty() {
+                self.calc.input_conversion();
+            } else {
+                self.calc.input_unit(&unit);
+            }
+            self.panel = Panel::Keypad;
+        }
+    }
+
+    /// Date and duration entry with buttons for date units and functions.
+    /// Dates are UTC.
+    fn render_dates(&mut self, ui: &mut egui::Ui) {
+        let mut input: Option<DateInput> = None;
+
+        egui::Frame::none()
+            .fill(Color32::from_rgb(28, 28, 36))
+            .rounding(Rounding::same(12.0))
+            .inner_margin(egui::Margin::same(12.0))
+            .show(ui, |ui| {
+                ui.set_width(ui.available_width());
+                ui.set_min_height(320.0);
+
+                let [date, duration] = &mut self.date_fields;
+                let rows = [
+                    (
+                        "date",
+                        date,
+                        "2026-03-01T08:30",
+                        dates::is_date as fn(&str) -> bool,
+                    ),
+                    ("h:m:s", duration, "1:45:30", dates::is_duration),
+                ];
+                for (label, field, hint, valid) in rows {
+                    ui.horizontal(|ui| {
+                        ui.add_sized(
+                            [48.0, 24.0],
+                            egui::Label::new(
+                                RichText::new(label)
+                                    .color(Color32::from_rgb(140, 190, 255))
+                                    .font(FontId::monospace(13.0)),
+                            ),
+                        );
+                        ui.add(
+                            egui::TextEdit::singleline(field)
+                                .hint_text(hint)
+                                .font(FontId::monospace(13.0))
+                                .desired_width(150.0),
+                        );
+                        if ui.add(mode_button("INS")).clicked() {
+                            let text = field.trim();
+                            if valid(text) {
+                                input = Some(DateInput::Literal(text.to_string()));
+                            } else {
+                                self.date_error = Some(format!("Enter a {} like {}", label, hint));
+                            }
+                        }
+                    });
+                }
+
+                ui.add_space(6.0);
+                ui.horizontal(|ui| {
+                    if ui.add(mode_button("today")).clicked() {
+                        input = Some(DateInput::Constant("today"));
+                    }
+                    for unit in ["days", "wk", "h", "s"] {
+                        if ui.add(mode_button(unit)).clicked() {
+                            input = Some(DateInput::Unit(unit));
+                        }
+                    }
+                    if ui.add(mode_button("to hms")).clicked() {
+                        input = Some(DateInput::Unit(units::HMS));
+                    }
+                });
+                ui.horizontal(|ui| {
+                    for function in ["workdays", "workday", "unix", "fromunix"] {
+                        if ui
+                            .add(
+                                egui::Button::new(
+                                    RichText::new(function).font(FontId::monospace(11.0)),
+                                )
+                                .min_size(Vec2::new(60.0, 24.0)),
+                            )
+                            .clicked()
+                        {
+                            input = Some(DateInput::Function(function));
+                        }
+                    }
+                });
+
+                ui.add_space(6.0);
+                let help = [
+                    "2026-03-01 + 90 days",
+                    "2026-12-25 − today",
+                    "workdays(start,end): Mon-Fri, both included",
+                    "workday(start,n): n weekdays later",
+                    "unix(date) / fromunix(seconds)",
+                    "Dates are in UTC",
+                ];
+                for line in help {
+                    ui.label(
+                        RichText::new(line)
+                            .color(Color32::from_rgb(140, 140, 160))
+                            .font(FontId::monospace(11.0)),
+                    );
+                }
+
+                if let Some(e) = &self.date_error {
+                    ui.label(
+                        RichText::new(e)
+                            .color(Color32::from_rgb(255, 120, 120))
+                            .font(FontId::monospace(12.0)),
+                    );
+                }
+            });
+
+        if let Some(input) = input {
+            self.error_message = None;
+            self.date_error = None;
+            match input {
+                DateInput::Literal(text) => self.calc.input_constant(&text),
+                DateInput::Constant(name) => self.calc.input_constant(name),
+                DateInput::Unit(units::HMS) => {
+                    self.calc.input_conversion();
+                    self.calc.input_unit(units::HMS);
+                }
+                DateInput::Unit(unit) => self.calc.input_unit(unit),
+                DateInput::Function(name) => self.calc.input_function(name),
+            }
+            self.panel = Panel::Keypad;
+        }
+    }
+
+    /// Solver panel with tabs for equations, polynomials and linear systems
+    fn render_solver(&mut self, ui: &mut egui::Ui) {
+        let mut picked: Option<f64> = None;
+
+        egui::Frame::none()
+            .fill(Color32::from_rgb(28, 28, 36))
+            .rounding(Rounding::same(12.0))
+            .inner_margin(egui::Margin::same(12.0))
+            .show(ui, |ui| {
+                ui.set_width(ui.available_width());
+                ui.set_min_height(320.0);
+
+                ui.horizontal(|ui| {
+                    for tab in SolverTab::ALL {
+                        if ui
+                            .selectable_label(self.solver_tab == tab, tab.label())
+                            .clicked()
+                        {
+                            self.solver_tab = tab;
+                        }
+                    }
+                });
+                ui.add_space(6.0);
+
+                picked = match self.solver_tab {
+                    SolverTab::Equation => self.render_equation_solver(ui),
+                    SolverTab::Polynomial => self.render_polynomial_solver(ui),
+                    SolverTab::Linear => self.render_linear_solver(ui),
+                };
+            });
+
+        // Picking a result stores it in x and inserts it into the expression
+        if let Some(value) = picked {
+            self.error_message = None;
+            self.calc.x = value;
+            self.calc.use_history(&value_text(value));
+            self.panel = Panel::Keypad;
+        }
+    }
+
+    /// Equation in x, search interval and optional guess
+    fn render_equation_solver(&mut self, ui: &mut egui::Ui) -> Option<f64> {
+        let mut solve = false;
+        let mut picked_root: Option<f64> = None;
+
+        let equation_edit = ui.add(
+            egui::TextEdit::singleline(&mut self.solve_equation)
+                .hint_text("x²−5x+6=0")
+                .font(FontId::monospace(14.0))
+                .desired_width(f32::INFINITY),
+        );
+        if equation_edit.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+            solve = true;
+        }
+
+        ui.add_space(4.0);
+
+        egui::Grid::new("solve_params")
+            .num_columns(2)
+            .spacing([8.0, 6.0])
+            .show(ui, |ui| {
+                for (label, text, hint) in [
+                    ("from", &mut self.solve_lo, "−10"),
+                    ("to", &mut self.solve_hi, "10"),
+                    ("guess", &mut self.solve_guess, "optional"),
+                ] {
+                    ui.label(
+                        RichText::new(label)
+                            .color(Color32::from_rgb(140, 190, 255))
+                            .font(FontId::monospace(13.0)),
+                    );
+                    ui.add(
+                        egui::TextEdit::singleline(text)
+                            .hint_text(hint)
+                            .font(FontId::monospace(13.0))
+                            .desired_width(120.0),
+                    );
+                    ui.end_row();
+                }
+            });
+
+        ui.horizontal(|ui| {
+            ui.label(
+                RichText::new(format!("x = {}", format_value(self.calc.x)))
+                    .color(Color32::from_rgb(140, 140, 160))
+                    .font(FontId::monospace(12.0)),
+            );
+            ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                if ui.add(mode_button("SOLVE")).clicked() {
+                    solve = true;
+                }
+            });
+        });
+
+        ui.separator();
+
+        egui::ScrollArea::vertical()
+            .id_salt("solve_roots")
+            .auto_shrink([false, false])
+            .show(ui, |ui| match &self.solve_result {
+                Some(Ok(report)) => {
+                    if report.roots.is_empty() {
+                        ui.label(
+                            RichText::new("No roots found")
+                                .color(Color32::from_rgb(100, 100, 120))
+                                .font(FontId::monospace(13.0)),
+                        );
+                    }
+                    for root in &report.roots {
+                        let response = ui.label(
+                            RichText::new(format!("x = {}", format_result(*root)))
+                                .color(Color32::WHITE)
+                                .font(FontId::monospace(14.0)),
+                        );
+                        if response.interact(egui::Sense::click()).clicked() {
+                            picked_root = Some(*root);
+                        }
+                    }
+                    for warning in &report.warnings {
+                        ui.label(
+                            RichText::new(warning)
+                                .color(Color32::from_rgb(255, 200, 100))
+                                .font(FontId::monospace(12.0)),
+                        );
+                    }
+                }
+                Some(Err(e)) => {
+                    ui.label(
+                        RichText::new(e)
+                            .color(Color32::from_rgb(255, 120, 120))
+                            .font(FontId::monospace(13.0)),
+                    );
+                }
+                None => {}
+            });
+
+        if solve {
+            self.solve_result = Some(self.run_solver());
+        }
+        picked_root
+    }
+
+    /// Coefficients of a polynomial up to degree 6 and its (complex) roots
+    fn render_polynomial_solver(&mut self, ui: &mut egui::Ui) -> Option<f64> {
+        let locale = self.calc.locale;
+        let mut picked: Option<f64> = None;
+
+        ui.horizontal(|ui| {
+            ui.label(
+                RichText::new("degree")
+                    .color(Color32::from_rgb(140, 190, 255))
+                    .font(FontId::monospace(13.0)),
+            );
+            for degree in 1..=MAX_DEGREE {
+                if ui
+                    .selectable_label(self.poly_degree == degree, degree.to_string())
+                    .clicked()
+                {
+                    self.poly_degree = degree;
+                    self.poly_result = None;
+                }
+            }
+        });
+        self.poly_coefs.resize(self.poly_degree + 1, String::new());
+
+        ui.add_space(4.0);
+
+        egui::Grid::new("poly_coefs")
+            .num_columns(4)
+            .spacing([6.0, 6.0])
+            .show(ui, |ui| {
+                for (i, text) in self.poly_coefs.iter_mut().enumerate() {
+                    let power = self.poly_degree - i;
+                    let label = match power {
+                        0 => "1".to_string(),
+                        1 => "x".to_string(),
+                        p => format!("x^{}", p),
+                    };
+                    ui.label(
+                        RichText::new(label)
+                            .color(Color32::from_rgb(140, 190, 255))
+                            .font(FontId::monospace(13.0)),
+                    );
+                    ui.add(
+                        egui::TextEdit::singleline(text)
+                            .hint_text("0")
+                            .font(FontId::monospace(13.0))
+                            .desired_width(90.0),
+                    );
+                    if i % 2 == 1 {
+                        ui.end_row();
+                    }
+                }
+            });
+
+        ui.with_layout(Layout::right_to_left(Align::Min), |ui| {
+            if ui.add(mode_button("SOLVE")).clicked() {
+                self.poly_result = Some(
+                    parse_fields(&self.poly_coefs, locale)
+                        .and_then(|coefs| polynomial::roots(&coefs)),
+                );
+            }
+        });
+
+        ui.separator();
+
+        egui::ScrollArea::vertical()
+            .id_salt("poly_roots")
+            .auto_shrink([false, false])
+            .show(ui, |ui| match &self.poly_result {
+                Some(Ok(roots)) => {
+                    for (i, root) in roots.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            let response = ui.label(
+                                RichText::new(format!("x{} = {}", i + 1, format_complex(*root)))
+                                    .color(Color32::WHITE)
+                                    .font(FontId::monospace(14.0)),
+                            );
+                            if root.is_real() {
+                                if response.interact(egui::Sense::click()).clicked() {
+                                    picked = Some(root.re);
+                                }
+                            } else {
+                                ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                                    if ui.add(mode_button("Im")).clicked() {
+                                        picked = Some(root.im);
+                                    }
+                                    if ui.add(mode_button("Re")).clicked() {
+                                        picked = Some(root.re);
+                                    }
+                                });
+                            }
+                        });
+                    }
+                }
+                Some(Err(e)) => {
+                    ui.label(
+                        RichText::new(e)
+                            .color(Color32::from_rgb(255, 120, 120))
+                            .font(FontId::monospace(13.0)),
+                    );
+                }
+                None => {}
+            });
+
+        picked
+    }
+
+    /// Coefficient grid [A | b] of an n×n linear system and its solution
+    fn render_linear_solver(&mut self, ui: &mut egui::Ui) -> Option<f64> {
+        let mut picked: Option<f64> = None;
+
+        ui.horizontal(|ui| {
+            ui.label(
+                RichText::new("unknowns")
+                    .color(Color32::from_rgb(140, 190, 255))
+                    .font(FontId::monospace(13.0)),
+            );
+            for n in 2..=MAX_UNKNOWNS {
+                if ui
+                    .selectable_label(self.lin_size == n, n.to_string())
+                    .clicked()
+                {
+                    self.lin_size = n;
+                    self.lin_result = None;
+                }
+            }
+        });
+        let n = self.lin_size;
+        self.lin_cells.resize(n, Vec::new());
+        for row in &mut self.lin_cells {
+            row.resize(n + 1, String::new());
+        }
+
+        ui.add_space(4.0);
+
+        // Narrow the cells so that six unknowns still fit the panel width
+        let cell_width = ((ui.available_width() - 4.0 * n as f32) / (n + 1) as f32 - 8.0).min(60.0);
+        egui::Grid::new("lin_cells")
+            .num_columns(n + 1)
+            .spacing([4.0, 4.0])
+            .show(ui, |ui| {
+                for j in 0..n {
+                    ui.label(
+                        RichText::new(format!("x{}", j + 1))
+                            .color(Color32::from_rgb(140, 190, 255))
+                            .font(FontId::monospace(12.0)),
+                    );
+                }
+                ui.label(
+                    RichText::new("=")
+                        .color(Color32::from_rgb(140, 190, 255))
+                        .font(FontId::monospace(12.0)),
+                );
+                ui.end_row();
+
+                for row in &mut self.lin_cells {
+                    for cell in row.iter_mut() {
+                        ui.add(
+                            egui::TextEdit::singleline(cell)
+                                .hint_text("0")
+                                .font(FontId::monospace(12.0))
+                                .desired_width(cell_width),
+                        );
+                    }
+                    ui.end_row();
+                }
+            });
+
+        ui.with_layout(Layout::right_to_left(Align::Min), |ui| {
+            if ui.add(mode_button("SOLVE")).clicked() {
+                self.lin_result = Some(self.run_linear_solver());
+            }
+        });
+
+        ui.separator();
+
+        egui::ScrollArea::vertical()
+            .id_salt("lin_solution")
+            .auto_shrink([false, false])
+            .show(ui, |ui| match &self.lin_result {
+                Some(Ok(solution)) => {
+                    for (i, value) in solution.iter().enumerate() {
+                        let response = ui.label(
+                            RichText::new(format!("x{} = {}", i + 1, format_result(*value)))
+                                .color(Color32::WHITE)
+                                .font(FontId::monospace(14.0)),
+                        );
+                        if response.interact(egui::Sense::click()).clicked() {
+                            picked = Some(*value);
+                        }
+                    }
+                }
+                Some(Err(e)) => {
+                    ui.label(
+                        RichText::new(e)
+                            .color(Color32::from_rgb(255, 120, 120))
+                            .font(FontId::monospace(13.0)),
+                    );
+                }
+                None => {}
+            });
+
+        picked
+    }
+
+    fn run_linear_solver(&self) -> Result<Vec<f64>, String> {
+        let locale = self.calc.locale;
+        let mut a = Vec::with_capacity(self.lin_size);
+        let mut b = Vec::with_capacity(self.lin_size);
+        for row in &self.lin_cells {
+            let mut values = parse_fields(row, locale)?;
+            b.push(values.pop().unwrap_or(0.0));
+            a.push(values);
+        }
+        linear::solve(&a, &b)
+    }
+
+    fn run_solver(&self) -> Result<Report, String> {
+        let locale = self.calc.locale;
+        let parse = |text: &str, default: Option<f64>| -> Result<Option<f64>, String> {
+            match text.trim() {
+                "" => Ok(default),
+                t => locale
+                    .parse_number(t)
+                    .map(Some)
+                    .ok_or_else(|| format!("Invalid number: {}", t)),
+            }
+        };
+
+        let lo = parse(&self.solve_lo, Some(-10.0))?.unwrap_or(-10.0);
+        let hi = parse(&self.solve_hi, Some(10.0))?.unwrap_or(10.0);
+        let guess = parse(&self.solve_guess, None)?;
+        self.calc
+            .solve_equation(&self.solve_equation, lo, hi, guess)
+    }
+
+    /// Parse the entry fields and add the values to the data set.
+    /// Several values may be entered at once, separated by spaces or `;`.
+    fn add_stat_points(&mut self) {
+        let freq = match self.stat_freq_input.trim() {
+            "" => 1,
+            text => match text.parse::<u32>() {
+                Ok(f) if f <= MAX_FREQUENCY => f,
+                _ => {
+                    self.error_message = Some(format!("Frequency must be 0 to {}", MAX_FREQUENCY));
+                    return;
+                }
+            },
+        };
+
+        let Some(xs) = parse_number_list(&self.stat_value_input, self.calc.locale) else {
+            self.error_message = Some("Invalid x value".to_string());
+            return;
+        };
+
+        if self.stat_two_var {
+            let Some(ys) = parse_number_list(&self.stat_y_input, self.calc.locale) else {
+                self.error_message = Some("Invalid y value".to_string());
+                return;
+            };
+            if xs.len() != ys.len() {
+                self.error_message = Some("Need as many y values as x values".to_string());
+                return;
+            }
+            for (x, y) in xs.into_iter().zip(ys) {
+                self.calc.stats.push_pair(x, y, freq);
+            }
+            self.stat_y_input.clear();
+        } else {
+            for x in xs {
+                self.calc.stats.push(x, freq);
+            }
+        }
+        self.error_message = None;
+        self.stat_value_input.clear();
+        self.stat_freq_input.clear();
+    }
+
+    fn render_keypad(&mut self, ui: &mut egui::Ui) {
+        let decimal = if self.calc.locale.decimal_comma {
+            ","
+        } else {
+            "."
+        };
+        let available_width = ui.available_width();
+        let btn_width = (available_width - SPACING * 5.0) / 6.0;
+        let main_btn_width = (available_width - SPACING * 3.0) / 4.0;
+
+        // Scientific row 1
+        ui.horizontal(|ui| {
+            ui.spacing_mut().item_spacing.x = SPACING;
+
+            if ui.add(sci_button("sin", btn_width)).clicked() {
+                self.error_message = None;
+                self.calc.input_function("sin");
+            }
+            if ui.add(sci_button("cos", btn_width)).clicked() {
+                self.error_message = None;
+                self.calc.input_function("cos");
+            }
+            if ui.add(sci_button("tan", btn_width)).clicked() {
+                self.error_message = None;
+                self.calc.input_function("tan");
+            }
+            if ui.add(sci_button("log", btn_width)).clicked() {
+                self.error_message = None;
+                self.calc.input_function("log10");
+            }
+            if ui.add(sci_button("ln", btn_width)).clicked() {
+                self.error_message = None;
+                self.calc.input_function("ln");
+            }
+            if ui.add(sci_button("√", btn_width)).clicked() {
+                self.error_message = None;
+                self.calc.input_function("sqrt");
+            }
+        });
+
+        ui.add_space(SPACING);
+
+        // Scientific row 2
+        ui.horizontal(|ui| {
+            ui.spacing_mut().item_spacing.x = SPACING;
+
+            if ui.add(sci_button("x²", btn_width)).clicked() {
+                self.error_message = None;
+                self.calc.input_square();
+            }
+            if ui.add(sci_button("xʸ", btn_width)).clicked() {
+                self.error_message = None;
+                self.calc.input_power();
+            }
+            if ui.add(sci_button("(", btn_width)).clicked() {
+                self.error_message = None;
+                self.calc.input_open_paren();
+            }
+            if ui.add(sci_button(")", btn_width)).clicked() {
+                self.error_message = None;
+                self.calc.input_close_paren();
+            }
             if ui.add(sci_button("π", btn_width)).clicked() {
                 self.error_message = None;
                 self.calc.input_constant("π");
@@ -467,6 +3278,30 @@ impl CalculatorApp {
 
         ui.add_space(SPACING);
 
+        // Calculus row: d/dx(f,x,a), ∫(f,x,a,b), Σ(f,k,m,n), Π(f,k,m,n) and
+        // the percentage change Δ%(old,new)
+        let fn_width = (available_width - SPACING * 6.0) / 7.0;
+        ui.horizontal(|ui| {
+            ui.spacing_mut().item_spacing.x = SPACING;
+
+            for func in ["d/dx", "∫", "Σ", "Π", "Δ%"] {
+                if ui.add(sci_button(func, fn_width)).clicked() {
+                    self.error_message = None;
+                    self.calc.input_function(func);
+                }
+            }
+            if ui.add(sci_button("x", fn_width)).clicked() {
+                self.error_message = None;
+                self.calc.input_constant("x");
+            }
+            if ui.add(sci_button(",", fn_width)).clicked() {
+                self.error_message = None;
+                self.calc.input_separator();
+            }
+        });
+
+        ui.add_space(SPACING);
+
         // Main keypad
         let rows = [
             vec![
@@ -496,7 +3331,7 @@ impl CalculatorApp {
             vec![
                 ("±", BtnStyle::Num),
                 ("0", BtnStyle::Num),
-                (".", BtnStyle::Num),
+                (decimal, BtnStyle::Num),
                 ("=", BtnStyle::Eq),
             ],
         ];
@@ -517,7 +3352,7 @@ impl CalculatorApp {
                             "−" => self.calc.input_operator("−"),
                             "+" => self.calc.input_operator("+"),
                             "±" => self.calc.toggle_sign(),
-                            "." => self.calc.input_decimal(),
+                            _ if *label == decimal => self.calc.input_decimal(),
                             "=" => self.do_calculate(),
                             digit => self.calc.input_digit(digit),
                         }
@@ -530,6 +3365,179 @@ impl CalculatorApp {
     }
 }
 
+/// Parse numbers separated by spaces or `;` (None if any is invalid)
+fn parse_number_list(text: &str, locale: Locale) -> Option<Vec<f64>> {
+    text.split(|c: char| c.is_whitespace() || c == ';')
+        .filter(|t| !t.is_empty())
+        .map(|t| locale.parse_number(t))
+        .collect()
+}
+
+/// Parse coefficient fields; empty fields count as 0
+fn parse_fields(fields: &[String], locale: Locale) -> Result<Vec<f64>, String> {
+    fields
+        .iter()
+        .map(|text| match text.trim() {
+            "" => Ok(0.0),
+            t => locale
+                .parse_number(t)
+                .ok_or_else(|| format!("Invalid number: {}", t)),
+        })
+        .collect()
+}
+
+/// Matrix result as a grid of right-aligned columns between brackets,
+/// scaled down to fit the display
+fn render_matrix_grid(ui: &mut egui::Ui, m: &Matrix, height: f32) {
+    let cells: Vec<Vec<String>> = (0..m.rows())
+        .map(|i| (0..m.cols()).map(|j| format_result(m.get(i, j))).collect())
+        .collect();
+    let widths: Vec<usize> = (0..m.cols())
+        .map(|j| {
+            cells
+                .iter()
+                .map(|row| row[j].chars().count())
+                .max()
+                .unwrap_or(1)
+        })
+        .collect();
+    let line_chars = widths.iter().sum::<usize>() + 2 * m.cols() + 2;
+    let font_size = (height / m.rows() as f32 * 0.8)
+        .min(ui.available_width() / line_chars as f32 * 1.6)
+        .clamp(6.0, 18.0);
+
+    let text = cells
+        .iter()
+        .map(|row| {
+            let row: Vec<String> = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, w)| format!("{:>w$}", cell, w = w))
+                .collect();
+            row.join("  ")
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let response = ui.label(
+        RichText::new(text)
+            .color(Color32::WHITE)
+            .font(FontId::monospace(font_size)),
+    );
+
+    // Brackets on both sides
+    let rect = response.rect.expand2(Vec2::new(6.0, 2.0));
+    let stroke = Stroke::new(1.5, Color32::from_rgb(150, 150, 170));
+    for (x, dx) in [(rect.left(), 4.0), (rect.right(), -4.0)] {
+        ui.painter().line_segment(
+            [egui::pos2(x + dx, rect.top()), egui::pos2(x, rect.top())],
+            stroke,
+        );
+        ui.painter().line_segment(
+            [egui::pos2(x, rect.top()), egui::pos2(x, rect.bottom())],
+            stroke,
+        );
+        ui.painter().line_segment(
+            [
+                egui::pos2(x, rect.bottom()),
+                egui::pos2(x + dx, rect.bottom()),
+            ],
+            stroke,
+        );
+    }
+}
+
+/// Graph coordinates of a point on the plot canvas
+fn to_graph(view: &View, rect: egui::Rect, pos: egui::Pos2) -> (f64, f64) {
+    (
+        view.x_min + ((pos.x - rect.left()) / rect.width()) as f64 * view.width(),
+        view.y_min + ((rect.bottom() - pos.y) / rect.height()) as f64 * view.height(),
+    )
+}
+
+/// Pointer coordinate rounded to 4 significant digits
+fn short_coordinate(v: f64) -> String {
+    if v == 0.0 {
+        return "0".to_string();
+    }
+    let scale = 10f64.powi(3 - v.abs().log10().floor() as i32);
+    format_result((v * scale).round() / scale)
+}
+
+/// Roots, extrema and intersections in the view, as selected by `marks`
+fn plot_features(functions: &[Graph], view: &View, marks: [bool; 3]) -> Vec<Feature> {
+    let valid: Vec<&dyn Fn(f64) -> f64> = functions
+        .iter()
+        .filter_map(|f| f.as_ref().ok().map(|f| f.as_ref()))
+        .collect();
+    let (lo, hi) = (view.x_min, view.x_max);
+    let mut features = Vec::new();
+    for (i, f) in valid.iter().enumerate() {
+        if marks[0] {
+            features.extend(plot::roots(f, lo, hi));
+        }
+        if marks[1] {
+            features.extend(plot::extrema(f, lo, hi));
+        }
+        if marks[2] {
+            for g in &valid[i + 1..] {
+                features.extend(plot::intersections(f, g, lo, hi));
+            }
+        }
+    }
+    features
+}
+
+/// Complex number as `a + bi`
+fn format_complex(z: Complex) -> String {
+    if z.is_real() {
+        format_result(z.re)
+    } else {
+        let sign = if z.im < 0.0 { '−' } else { '+' };
+        format!(
+            "{} {} {}i",
+            format_result(z.re),
+            sign,
+            format_result(z.im.abs())
+        )
+    }
+}
+
+/// Amount of money with two decimals
+fn format_money(value: f64) -> String {
+    // Avoid showing −0.00 for rounding noise
+    let value = if value.abs() < 0.005 { 0.0 } else { value };
+    format!("{:.2}", value).replace('-', "−")
+}
+
+/// Compact number formatting for panel listings
+fn format_value(value: f64) -> String {
+    if value.is_nan() {
+        "—".to_string()
+    } else if value.fract() == 0.0 && value.abs() < 1e12 {
+        format!("{}", value as i64)
+    } else {
+        let formatted = format!("{:.6}", value);
+        formatted
+            .trim_end_matches('0')
+            .trim_end_matches('.')
+            .to_string()
+    }
+}
+
+/// Colour of numbers, operators, names and brackets in the display
+fn syntax_color(kind: highlight::Kind) -> Color32 {
+    match kind {
+        highlight::Kind::Number => Color32::WHITE,
+        highlight::Kind::Operator => Color32::from_rgb(255, 170, 100),
+        highlight::Kind::Function => Color32::from_rgb(90, 210, 200),
+        highlight::Kind::Constant => Color32::from_rgb(200, 150, 255),
+        highlight::Kind::Variable => Color32::from_rgb(140, 220, 130),
+        highlight::Kind::Bracket => Color32::from_rgb(160, 160, 185),
+        highlight::Kind::Other => Color32::from_rgb(200, 200, 210),
+    }
+}
+
 fn adaptive_font_size(len: usize) -> f32 {
     match len {
         0..=10 => 32.0,
@@ -679,9 +3687,33 @@ fn sci_button(text: &str, width: f32) -> impl egui::Widget + '_ {
     }
 }
 
+/// Label with − and + buttons for a number of digits; returns the new
+/// number when one of them is clicked
+fn digit_stepper(
+    ui: &mut egui::Ui,
+    text: &str,
+    digits: u8,
+    range: std::ops::RangeInclusive<u8>,
+) -> Option<u8> {
+    let mut changed = None;
+    ui.horizontal(|ui| {
+        ui.label(RichText::new(text).font(FontId::monospace(12.0)));
+        let less = egui::Button::new("−").small();
+        if ui.add_enabled(digits > *range.start(), less).clicked() {
+            changed = Some(digits - 1);
+        }
+        ui.label(RichText::new(digits.to_string()).font(FontId::monospace(13.0)));
+        let more = egui::Button::new("+").small();
+        if ui.add_enabled(digits < *range.end(), more).clicked() {
+            changed = Some(digits + 1);
+        }
+    });
+    changed
+}
+
 fn mode_button(text: &str) -> impl egui::Widget + '_ {
     move |ui: &mut egui::Ui| {
-        let size = Vec2::new(48.0, 24.0);
+        let size = Vec2::new(40.0, 24.0);
         let (rect, response) = ui.allocate_exact_size(size, egui::Sense::click());
 
         if ui.is_rect_visible(rect) {
@@ -1,4 +1,23 @@
-use std::f64::consts::PI;
+use crate::currency::{self, RateTable};
+use crate::dates;
+use crate::distributions;
+use crate::exact;
+use crate::expr::{self, Context, Expr};
+use crate::finance;
+use crate::format::{DisplayFormat, Locale, Rounding};
+use crate::matrix::{self, Matrix, Value};
+use crate::regression::{self, Fit, Model};
+use crate::solver;
+use crate::stats::{self, DataSet, SUMMARY_NAMES};
+use crate::symbolic;
+use crate::typeset::Typeset;
+use crate::units;
+
+/// Separator between a value and the unit to show it in (`100 USD to EUR`)
+const UNIT_CONVERSION: &str = " to ";
+/// Most trailing characters of the input that are typeset apart while
+/// they do not parse yet, such as `×` waiting for its operand
+const MAX_PENDING: usize = 8;
 
 pub struct Calculator {
     pub expression: String,
@@ -6,14 +25,67 @@ pub struct Calculator {
     pub history: Vec<HistoryEntry>,
     pub last_result: Option<f64>,
     pub angle_mode: AngleMode,
+    pub percent_mode: PercentMode,
+    /// How numeric results are shown
+    pub display_format: DisplayFormat,
+    /// Decimal separator and digit grouping of the display
+    pub locale: Locale,
+    /// Rounding of shown and stored results
+    pub rounding: Rounding,
     pub open_parens: i32,
+    pub stats: DataSet,
+    pub regression_model: Model,
+    /// Value of the variable `x` (set from solver results)
+    pub x: f64,
+    pub rates: RateTable,
     result_shown: bool,
+    /// Error estimate of numerical derivatives and integrals in the last result
+    error_estimate: Option<f64>,
+    /// Last result if it is a matrix or vector
+    matrix_result: Option<Matrix>,
+    /// Whether the last result was converted with currency rates
+    currency_result: bool,
+    /// Cursor position in the expression, in characters
+    cursor: usize,
+    /// Other end of the selection, if text is selected
+    anchor: Option<usize>,
+    undo: Vec<EditState>,
+    redo: Vec<EditState>,
+}
+
+/// Result of an evaluated expression, before it is shown
+enum Outcome {
+    /// Number and the error estimate of numerical derivatives and integrals
+    Number(f64, Option<f64>),
+    /// Value with units, a date or a duration, and whether it was converted
+    /// with currency rates
+    Quantity(String, bool),
+    /// Expression such as a symbolic derivative
+    Symbolic(String),
+    Matrix(Matrix),
+}
+
+/// Input state restored by undo and redo
+#[derive(Clone)]
+struct EditState {
+    expression: String,
+    display: String,
+    open_parens: i32,
+    result_shown: bool,
+    cursor: usize,
+    last_result: Option<f64>,
+    error_estimate: Option<f64>,
+    matrix_result: Option<Matrix>,
+    currency_result: bool,
 }
 
 #[derive(Clone)]
 pub struct HistoryEntry {
     pub expression: String,
+    /// Result in the display format
     pub result: String,
+    /// Result at full precision, inserted when the entry is reused
+    pub value: String,
 }
 
 #[derive(Clone, Copy, PartialEq, Debug)]
@@ -22,6 +94,16 @@ pub enum AngleMode {
     Degrees,
 }
 
+/// Meaning of the percent key
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PercentMode {
+    /// `b%` is b/100
+    Plain,
+    /// Office calculator rules: `a+b%` is a·(1+b/100), `a−b%` is
+    /// a·(1−b/100), `a×b%` is a·b/100 and `a÷b%` is a/(b/100)
+    Business,
+}
+
 impl Default for Calculator {
     fn default() -> Self {
         Self {
@@ -30,8 +112,23 @@ impl Default for Calculator {
             history: Vec::new(),
             last_result: None,
             angle_mode: AngleMode::Degrees,
+            percent_mode: PercentMode::Plain,
+            display_format: DisplayFormat::default(),
+            locale: Locale::default(),
+            rounding: Rounding::default(),
             open_parens: 0,
+            stats: DataSet::default(),
+            regression_model: Model::default(),
+            x: 0.0,
+            rates: RateTable::default(),
             result_shown: false,
+            error_estimate: None,
+            matrix_result: None,
+            currency_result: false,
+            cursor: 0,
+            anchor: None,
+            undo: Vec::new(),
+            redo: Vec::new(),
         }
     }
 }
@@ -39,6 +136,310 @@ impl Default for Calculator {
 impl Calculator {
     /// Input a digit (0-9)
     pub fn input_digit(&mut self, digit: &str) {
+        self.edit(|calc| calc.append_digit(digit));
+    }
+
+    /// Input decimal point with validation
+    pub fn input_decimal(&mut self) {
+        self.edit(Self::append_decimal);
+    }
+
+    /// Input a `.` or `,` key: the decimal separator of the locale enters
+    /// a decimal point, and `,` with a decimal point separates arguments
+    /// like `;`. A `.` with a decimal comma is ignored.
+    pub fn input_decimal_key(&mut self, key: char) {
+        if key == self.locale.decimal() {
+            self.input_decimal();
+        } else if key == ',' {
+            self.input_separator();
+        }
+    }
+
+    /// Input operator with validation (prevents consecutive operators)
+    pub fn input_operator(&mut self, op: &str) {
+        self.edit(|calc| calc.append_operator(op));
+    }
+
+    /// Input function (sin, cos, etc.)
+    pub fn input_function(&mut self, func: &str) {
+        self.edit(|calc| calc.append_function(func));
+    }
+
+    /// Input a complete function call, e.g. `normcdf(1.96,0,1)`
+    pub fn input_call(&mut self, func: &str, args: &[&str]) {
+        self.edit(|calc| calc.append_call(func, args));
+    }
+
+    /// Input constant or named value (π, e, x, mean)
+    pub fn input_constant(&mut self, constant: &str) {
+        self.edit(|calc| calc.append_constant(constant));
+    }
+
+    /// Input a unit such as `EUR` or `h`, after a number (`100 USD`) or
+    /// another unit (`EUR·h`)
+    pub fn input_unit(&mut self, unit: &str) {
+        self.edit(|calc| calc.append_unit(unit));
+    }
+
+    /// Input ` to ` before the unit a value is converted to
+    pub fn input_conversion(&mut self) {
+        self.edit(Self::append_conversion);
+    }
+
+    /// Input a typed name other than a function: `to`, a unit (any name
+    /// after `to`, such as `hms`) or else a value such as `π` or `t`
+    pub fn input_name(&mut self, name: &str) {
+        if name == UNIT_CONVERSION.trim() {
+            self.input_conversion();
+        } else if self.converting() || units::is_unit(name, &self.rates) {
+            self.input_unit(name);
+        } else {
+            self.input_constant(name);
+        }
+    }
+
+    /// Whether the input ends with ` to `, waiting for the target unit
+    pub fn converting(&self) -> bool {
+        self.expression.ends_with(UNIT_CONVERSION)
+    }
+
+    /// Input opening parenthesis
+    pub fn input_open_paren(&mut self) {
+        self.edit(Self::append_open_paren);
+    }
+
+    /// Input closing parenthesis (only if there are open ones)
+    pub fn input_close_paren(&mut self) {
+        self.edit(Self::append_close_paren);
+    }
+
+    /// Input argument separator (only inside parentheses)
+    pub fn input_separator(&mut self) {
+        self.edit(Self::append_separator);
+    }
+
+    /// Input square (^2)
+    pub fn input_square(&mut self) {
+        self.edit(Self::append_square);
+    }
+
+    /// Input power (^)
+    pub fn input_power(&mut self) {
+        self.edit(Self::append_power);
+    }
+
+    /// Input percent (/100, or a percentage of the value before the
+    /// operator in business mode)
+    pub fn input_percent(&mut self) {
+        self.edit(Self::append_percent);
+    }
+
+    /// Toggle sign of the number before the cursor
+    pub fn toggle_sign(&mut self) {
+        self.edit(Self::append_sign);
+    }
+
+    /// Paste text such as `2*pi/3` or `$1,234.50 * 2`, normalized to the
+    /// calculator's symbols. Text starting with an operator continues from
+    /// a shown result; other text replaces it.
+    pub fn paste(&mut self, text: &str) {
+        self.edit(|calc| calc.append_paste(text));
+    }
+
+    /// Use result from history
+    pub fn use_history(&mut self, result: &str) {
+        self.edit(|calc| calc.append_history(result));
+    }
+
+    /// Clear everything
+    pub fn clear(&mut self) {
+        self.record(Self::clear_input);
+    }
+
+    /// Delete the selection or the character before the cursor (backspace)
+    pub fn clear_entry(&mut self) {
+        if self.selection().is_some() {
+            self.record(Self::remove_selection);
+        } else if self.cursor > 0 {
+            self.edit(Self::remove_last);
+        }
+    }
+
+    /// Delete the selection or the character after the cursor
+    pub fn delete_forward(&mut self) {
+        if self.selection().is_some() {
+            self.record(Self::remove_selection);
+        } else if self.cursor < self.expression.chars().count() {
+            self.move_cursor(1, false);
+            self.edit(Self::remove_last);
+        }
+    }
+
+    /// Cursor position in the expression, in characters
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Selected range of the expression, in characters
+    pub fn selection(&self) -> Option<(usize, usize)> {
+        let anchor = self.anchor.filter(|&anchor| anchor != self.cursor)?;
+        Some((anchor.min(self.cursor), anchor.max(self.cursor)))
+    }
+
+    /// Move the cursor by `delta` characters, keeping combining marks (as
+    /// in x̂) with their base. With `select` the selection is extended.
+    pub fn move_cursor(&mut self, delta: isize, select: bool) {
+        let chars: Vec<char> = self.expression.chars().collect();
+        let mut cursor = self.cursor.min(chars.len());
+        for _ in 0..delta.unsigned_abs() {
+            if delta < 0 {
+                cursor = cursor.saturating_sub(1);
+                while cursor > 0 && is_combining(chars[cursor]) {
+                    cursor -= 1;
+                }
+            } else {
+                cursor = (cursor + 1).min(chars.len());
+                while cursor < chars.len() && is_combining(chars[cursor]) {
+                    cursor += 1;
+                }
+            }
+        }
+        self.set_cursor(cursor, select);
+    }
+
+    /// Place the cursor, e.g. where the display was clicked. Moving into a
+    /// shown result makes it editable.
+    pub fn set_cursor(&mut self, cursor: usize, select: bool) {
+        let chars: Vec<char> = self.expression.chars().collect();
+        let mut cursor = cursor.min(chars.len());
+        while cursor < chars.len() && is_combining(chars[cursor]) {
+            cursor += 1;
+        }
+        if select {
+            self.anchor.get_or_insert(self.cursor);
+        } else {
+            self.anchor = None;
+        }
+        self.cursor = cursor;
+        if self.cursor < chars.len() || self.selection().is_some() || self.result_shown {
+            self.show_expression();
+        }
+    }
+
+    /// Select the whole expression
+    pub fn select_all(&mut self) {
+        self.set_cursor(0, false);
+        self.set_cursor(usize::MAX, true);
+    }
+
+    /// Undo the last input action
+    pub fn undo(&mut self) {
+        if let Some(state) = self.undo.pop() {
+            self.redo.push(self.edit_state());
+            self.restore(state);
+        }
+    }
+
+    /// Redo the last undone input action
+    pub fn redo(&mut self) {
+        if let Some(state) = self.redo.pop() {
+            self.undo.push(self.edit_state());
+            self.restore(state);
+        }
+    }
+
+    /// Apply an input action at the cursor, replacing the selection. The
+    /// actions append to the expression, so they run on the text before
+    /// the cursor and the rest is put back after them.
+    fn edit(&mut self, action: impl FnOnce(&mut Self)) {
+        self.record(|calc| {
+            calc.remove_selection();
+            let split = char_to_byte_index(&calc.expression, calc.cursor);
+            if split == calc.expression.len() {
+                action(calc);
+                calc.cursor = calc.expression.chars().count();
+                return;
+            }
+            let tail = calc.expression.split_off(split);
+            calc.show_expression();
+            action(calc);
+            calc.cursor = calc.expression.chars().count();
+            calc.expression.push_str(&tail);
+            calc.show_expression();
+        });
+    }
+
+    /// Run an action, recording the previous input for undo if it changed
+    fn record<T>(&mut self, action: impl FnOnce(&mut Self) -> T) -> T {
+        let before = self.edit_state();
+        let result = action(self);
+        let changed = self.expression != before.expression
+            || self.display != before.display
+            || self.result_shown != before.result_shown;
+        if changed {
+            self.undo.push(before);
+            if self.undo.len() > 100 {
+                self.undo.remove(0);
+            }
+            self.redo.clear();
+        }
+        result
+    }
+
+    fn edit_state(&self) -> EditState {
+        EditState {
+            expression: self.expression.clone(),
+            display: self.display.clone(),
+            open_parens: self.open_parens,
+            result_shown: self.result_shown,
+            cursor: self.cursor,
+            last_result: self.last_result,
+            error_estimate: self.error_estimate,
+            matrix_result: self.matrix_result.clone(),
+            currency_result: self.currency_result,
+        }
+    }
+
+    fn restore(&mut self, state: EditState) {
+        self.expression = state.expression;
+        self.display = state.display;
+        self.open_parens = state.open_parens;
+        self.result_shown = state.result_shown;
+        self.cursor = state.cursor;
+        self.last_result = state.last_result;
+        self.error_estimate = state.error_estimate;
+        self.matrix_result = state.matrix_result;
+        self.currency_result = state.currency_result;
+        self.anchor = None;
+    }
+
+    /// Delete the selected text, if any
+    fn remove_selection(&mut self) {
+        if let Some((start, end)) = self.selection() {
+            let range = char_to_byte_index(&self.expression, start)
+                ..char_to_byte_index(&self.expression, end);
+            self.expression.replace_range(range, "");
+            self.cursor = start;
+            self.show_expression();
+        }
+        self.anchor = None;
+    }
+
+    /// Show the expression as typed, so it can be edited in the middle
+    fn show_expression(&mut self) {
+        self.display = if self.expression.is_empty() {
+            String::from("0")
+        } else {
+            self.expression.clone()
+        };
+        self.open_parens = unclosed_parens(&self.expression);
+        self.result_shown = false;
+    }
+}
+
+impl Calculator {
+    /// Input a digit (0-9)
+    fn append_digit(&mut self, digit: &str) {
         if self.result_shown {
             self.expression.clear();
             self.display = String::from("0");
@@ -54,7 +455,7 @@ impl Calculator {
     }
 
     /// Input decimal point with validation
-    pub fn input_decimal(&mut self) {
+    fn append_decimal(&mut self) {
         if self.result_shown {
             self.expression.clear();
             self.display = String::from("0");
@@ -64,12 +465,12 @@ impl Calculator {
         // Find the last number in expression and check if it has a decimal
         let last_num = self
             .expression
-            .rsplit(|c: char| "+-×÷()".contains(c))
+            .rsplit(|c: char| "+-×÷(),".contains(c))
             .next()
             .unwrap_or("");
 
         if !last_num.contains('.') {
-            if self.display == "0" || self.display.ends_with(|c: char| "+-×÷(".contains(c)) {
+            if self.display == "0" || self.display.ends_with(|c: char| "+-×÷(,".contains(c)) {
                 self.display.push_str("0.");
                 self.expression.push_str("0.");
             } else {
@@ -80,7 +481,7 @@ impl Calculator {
     }
 
     /// Input operator with validation (prevents consecutive operators)
-    pub fn input_operator(&mut self, op: &str) {
+    fn append_operator(&mut self, op: &str) {
         self.result_shown = false;
 
         if self.expression.is_empty() {
@@ -111,11 +512,11 @@ impl Calculator {
     }
 
     /// Input function (sin, cos, etc.)
-    pub fn input_function(&mut self, func: &str) {
+    fn append_function(&mut self, func: &str) {
         // Add implicit multiplication if needed
         if !self.expression.is_empty() {
             let last = self.expression.chars().last().unwrap();
-            if last.is_ascii_digit() || last == ')' || last == 'π' || last == 'e' {
+            if ends_operand(last) {
                 self.expression.push('×');
                 self.display.push('×');
             }
@@ -128,8 +529,25 @@ impl Calculator {
         self.open_parens += 1;
     }
 
-    /// Input constant (π, e)
-    pub fn input_constant(&mut self, constant: &str) {
+    /// Input a complete function call, e.g. `normcdf(1.96,0,1)`
+    fn append_call(&mut self, func: &str, args: &[&str]) {
+        if self.result_shown {
+            self.expression.clear();
+            self.display = String::from("0");
+            self.result_shown = false;
+        }
+
+        self.append_function(func);
+        let args = args.join(",").replace('-', "−");
+        self.expression.push_str(&args);
+        self.expression.push(')');
+        self.display.push_str(&args);
+        self.display.push(')');
+        self.open_parens -= 1;
+    }
+
+    /// Input constant or named value (π, e, x, mean)
+    fn append_constant(&mut self, constant: &str) {
         if self.result_shown {
             self.expression.clear();
             self.display = String::from("0");
@@ -139,7 +557,7 @@ impl Calculator {
         // Add implicit multiplication if needed
         if !self.expression.is_empty() {
             let last = self.expression.chars().last().unwrap();
-            if last.is_ascii_digit() || last == ')' || last == 'π' || last == 'e' {
+            if ends_operand(last) {
                 self.expression.push('×');
                 self.display.push('×');
             }
@@ -149,12 +567,39 @@ impl Calculator {
         self.display.push_str(constant);
     }
 
+    /// Input a unit such as `EUR` or `h`, after a number (`100 USD`) or
+    /// another unit (`EUR·h`)
+    fn append_unit(&mut self, unit: &str) {
+        self.result_shown = false;
+        if self.expression.is_empty() {
+            self.display.clear();
+        }
+        let separator = match self.expression.chars().last() {
+            Some(c) if c.is_ascii_digit() || c == '.' => " ",
+            Some(c) if ends_operand(c) => "·",
+            _ => "",
+        };
+        for text in [separator, unit] {
+            self.expression.push_str(text);
+            self.display.push_str(text);
+        }
+    }
+
+    /// Input ` to ` before the unit a value is converted to
+    fn append_conversion(&mut self) {
+        if !self.expression.is_empty() && !self.expression.contains(UNIT_CONVERSION) {
+            self.result_shown = false;
+            self.expression.push_str(UNIT_CONVERSION);
+            self.display.push_str(UNIT_CONVERSION);
+        }
+    }
+
     /// Input opening parenthesis
-    pub fn input_open_paren(&mut self) {
+    fn append_open_paren(&mut self) {
         // Add implicit multiplication if needed
         if !self.expression.is_empty() {
             let last = self.expression.chars().last().unwrap();
-            if last.is_ascii_digit() || last == ')' || last == 'π' || last == 'e' {
+            if ends_operand(last) {
                 self.expression.push('×');
                 self.display.push('×');
             }
@@ -166,11 +611,11 @@ impl Calculator {
     }
 
     /// Input closing parenthesis (only if there are open ones)
-    pub fn input_close_paren(&mut self) {
+    fn append_close_paren(&mut self) {
         if self.open_parens > 0 {
             let last = self.expression.chars().last().unwrap_or('(');
             // Don't close empty parens or right after operator
-            if last != '(' && !"+-×÷".contains(last) {
+            if last != '(' && !"+-×÷,".contains(last) {
                 self.expression.push(')');
                 self.display.push(')');
                 self.open_parens -= 1;
@@ -178,41 +623,53 @@ impl Calculator {
         }
     }
 
-    /// Input square (^2)
-    pub fn input_square(&mut self) {
+    /// Input argument separator (only inside parentheses)
+    fn append_separator(&mut self) {
+        if self.open_parens > 0 {
+            let last = self.expression.chars().last().unwrap_or('(');
+            if last != '(' && !"+-×÷,".contains(last) {
+                self.expression.push(',');
+                self.display.push(',');
+            }
+        }
+    }
+
+    /// Input square (²)
+    fn append_square(&mut self) {
         if !self.expression.is_empty() {
             let last = self.expression.chars().last().unwrap();
-            if last.is_ascii_digit() || last == ')' || last == 'π' || last == 'e' {
-                self.expression.push_str("^2");
+            if ends_operand(last) {
+                self.expression.push('²');
                 self.display.push('²');
             }
         }
     }
 
     /// Input power (^)
-    pub fn input_power(&mut self) {
+    fn append_power(&mut self) {
         if !self.expression.is_empty() {
             let last = self.expression.chars().last().unwrap();
-            if last.is_ascii_digit() || last == ')' || last == 'π' || last == 'e' {
+            if ends_operand(last) {
                 self.expression.push('^');
                 self.display.push('^');
             }
         }
     }
 
-    /// Input percent (/100)
-    pub fn input_percent(&mut self) {
+    /// Input percent, kept as typed and applied with the percent mode when
+    /// the expression is evaluated
+    fn append_percent(&mut self) {
         if !self.expression.is_empty() {
             let last = self.expression.chars().last().unwrap();
-            if last.is_ascii_digit() || last == ')' {
-                self.expression.push_str("/100");
+            if last.is_ascii_digit() || last == ')' || last == '²' {
+                self.expression.push('%');
                 self.display.push('%');
             }
         }
     }
 
     /// Toggle sign of current number
-    pub fn toggle_sign(&mut self) {
+    fn append_sign(&mut self) {
         if self.expression.is_empty() || self.display == "0" {
             return;
         }
@@ -256,16 +713,17 @@ impl Calculator {
         self.display = self.expression.clone();
     }
 
-    /// Clear everything
-    pub fn clear(&mut self) {
+    fn clear_input(&mut self) {
         self.expression.clear();
         self.display = String::from("0");
         self.open_parens = 0;
         self.result_shown = false;
+        self.cursor = 0;
+        self.anchor = None;
     }
 
     /// Clear last entry (backspace) - handles multi-byte unicode
-    pub fn clear_entry(&mut self) {
+    fn remove_last(&mut self) {
         self.result_shown = false;
 
         if self.expression.is_empty() {
@@ -286,6 +744,11 @@ impl Calculator {
         let new_len = self.expression.len() - last_char.len_utf8();
         self.expression.truncate(new_len);
 
+        // A combining mark (as in x̂) goes together with its base character
+        if is_combining(last_char) {
+            self.expression.pop();
+        }
+
         // Update display
         if self.expression.is_empty() {
             self.display = String::from("0");
@@ -294,57 +757,291 @@ impl Calculator {
         }
     }
 
-    /// Calculate result
-    pub fn calculate(&mut self) -> Result<f64, String> {
+    /// Calculate the result. A symbolic derivative such as `d/dx(x²,x)`
+    /// gives an expression (`2x`) instead of a number, and matrix
+    /// expressions give a matrix.
+    pub fn evaluate(&mut self) -> Result<(), String> {
+        self.record(Self::evaluate_input)
+    }
+
+    fn evaluate_input(&mut self) -> Result<(), String> {
         if self.expression.is_empty() {
-            return Ok(0.0);
+            return Ok(());
+        }
+        self.last_result = None;
+        self.error_estimate = None;
+        match self.outcome()? {
+            Outcome::Number(result, error_estimate) => {
+                let result = self.rounding.store(result, self.display_format);
+                self.last_result = Some(result);
+                self.error_estimate = error_estimate;
+                self.show_number(result);
+            }
+            Outcome::Quantity(formatted, currency) => {
+                self.show_result(formatted);
+                self.currency_result = currency;
+            }
+            Outcome::Symbolic(result) => self.show_result(result),
+            Outcome::Matrix(m) => {
+                self.show_result(m.to_string());
+                self.matrix_result = Some(m);
+            }
+        }
+        Ok(())
+    }
+
+    /// Result of the expression as typed, with open parentheses closed, or
+    /// None while it is incomplete or has no valid result. Nothing is shown
+    /// or added to the history. Integrals, sums and products are too slow
+    /// to evaluate on every keystroke and are not previewed.
+    pub fn preview(&self) -> Option<String> {
+        if self.result_shown || self.expression.is_empty() || uses_costly_calculus(&self.expression)
+        {
+            return None;
+        }
+        let preview = match self.outcome().ok()? {
+            Outcome::Number(result, _) => {
+                let result = self.rounding.store(result, self.display_format);
+                self.display_format.format(result, self.rounding.mode)
+            }
+            Outcome::Quantity(formatted, _) | Outcome::Symbolic(formatted) => formatted,
+            Outcome::Matrix(m) => m.to_string(),
+        };
+        // A plain number is its own result
+        Some(preview).filter(|preview| *preview != self.display)
+    }
+
+    /// Input as a tree for typeset display, with open parentheses closed.
+    /// Trailing text that does not parse yet, such as an operator waiting
+    /// for its operand, is kept apart. None for input that is shown as
+    /// typed: results, percentages, units, dates and conversions.
+    pub fn typeset_input(&self) -> Option<Typeset> {
+        if self.result_shown
+            || self.display.contains('%')
+            || self.expression.contains(UNIT_CONVERSION)
+        {
+            return None;
+        }
+        let chars: Vec<char> = self.expression.chars().collect();
+        let typeset = |end: usize| {
+            let head: String = chars[..end].iter().collect();
+            let text = preprocess(&head, unclosed_parens(&head));
+            let expr: Expr = text.parse().ok()?;
+            Some(Typeset {
+                numbers: expr::number_literals(&text),
+                rest: chars[end..].iter().collect(),
+                expr,
+            })
+        };
+        // The whole input usually parses; otherwise only a short tail is
+        // cut off, so that every frame parses a bounded number of times
+        typeset(chars.len())
+            .or_else(|| {
+                (chars.len().saturating_sub(MAX_PENDING).max(1)..chars.len())
+                    .rev()
+                    .find_map(typeset)
+            })
+            .filter(|input| !units::uses_units(&input.expr, &self.rates))
+    }
+
+    /// Evaluate the expression without showing the result
+    fn outcome(&self) -> Result<Outcome, String> {
+        if let Some((value, target)) = self.expression.split_once(UNIT_CONVERSION) {
+            if target.trim().is_empty() {
+                return Err("Incomplete".to_string());
+            }
+            let expr: Expr = self.preprocess(value, 0).parse()?;
+            let target_expr: Expr = self.preprocess(target, self.open_parens).parse()?;
+            return self.quantity(&expr, Some((&target_expr, target)));
+        }
+        let expr = self.parse_expression()?;
+        if units::uses_units(&expr, &self.rates) {
+            return self.quantity(&expr, None);
+        }
+        if matrix::uses_matrices(&expr) {
+            return match matrix::evaluate(&expr, &self.context())? {
+                Value::Number(result) => {
+                    check_finite(result)?;
+                    Ok(Outcome::Number(result, None))
+                }
+                Value::Matrix(m) if m.is_finite() => Ok(Outcome::Matrix(m)),
+                Value::Matrix(_) => Err("Undefined".to_string()),
+            };
         }
+        expr.check(&self.context())?;
+        let degrees = self.angle_mode == AngleMode::Degrees;
+        if let Some(result) = symbolic::expand(&expr, degrees)? {
+            return Ok(Outcome::Symbolic(result.to_string()));
+        }
+        let (result, error_estimate) = expr.eval_with_error(&self.context())?;
+        check_finite(result)?;
+        Ok(Outcome::Number(result, error_estimate))
+    }
+
+    /// Value of an expression with units, such as `400 EUR`, a date or a
+    /// duration
+    fn quantity(&self, expr: &Expr, target: Option<(&Expr, &str)>) -> Result<Outcome, String> {
+        let formatted = units::convert(expr, target, &self.context(), &self.rates)?;
+        let currency = formatted
+            .split(|c: char| !c.is_ascii_alphabetic())
+            .any(currency::is_code);
+        Ok(Outcome::Quantity(formatted, currency))
+    }
 
+    fn parse_expression(&self) -> Result<Expr, String> {
         // Check for invalid ending
-        let last = self.expression.chars().last().unwrap();
+        let last = self.expression.chars().last().unwrap_or('(');
         if "+-×÷(".contains(last) {
             return Err("Incomplete".to_string());
         }
+        self.preprocess_expression().parse()
+    }
 
-        let expr = self.preprocess_expression();
+    /// Show a number in the display format; the expression keeps it at
+    /// full precision
+    fn show_number(&mut self, value: f64) {
+        let shown = self.display_format.format(value, self.rounding.mode);
+        self.show_formatted(value_text(value), shown);
+    }
 
-        match std::panic::catch_unwind(|| meval::eval_str(&expr)) {
-            Ok(eval_result) => match eval_result {
-                Ok(result) => {
-                    if result.is_nan() {
-                        return Err("Undefined".to_string());
-                    }
-                    if result.is_infinite() {
-                        return Err("Infinity".to_string());
-                    }
+    /// Record the calculation in the history and show its result
+    fn show_result(&mut self, formatted: String) {
+        self.show_formatted(formatted.clone(), formatted);
+    }
+
+    /// Record the calculation in the history and show `shown` for a result
+    /// whose expression text is `value`
+    fn show_formatted(&mut self, value: String, shown: String) {
+        self.history.push(HistoryEntry {
+            expression: self.display.clone(),
+            result: shown.clone(),
+            value: value.clone(),
+        });
+
+        if self.history.len() > 100 {
+            self.history.remove(0);
+        }
+
+        self.expression = value;
+        self.display = shown;
+        self.open_parens = 0;
+        self.result_shown = true;
+        self.cursor = self.expression.chars().count();
+        self.anchor = None;
+        self.matrix_result = None;
+        self.currency_result = false;
+    }
 
-                    let formatted = format_result(result);
+    /// Whether the display shows a result rather than the input
+    pub fn result_shown(&self) -> bool {
+        self.result_shown
+    }
 
-                    self.history.push(HistoryEntry {
-                        expression: self.display.clone(),
-                        result: formatted.clone(),
-                    });
+    /// Error estimate of the shown result, if it came from a numerical
+    /// derivative or integral
+    pub fn error_estimate(&self) -> Option<f64> {
+        self.error_estimate.filter(|_| self.result_shown)
+    }
 
-                    if self.history.len() > 100 {
-                        self.history.remove(0);
-                    }
+    /// Shown result if it is a matrix or vector
+    pub fn matrix_result(&self) -> Option<&Matrix> {
+        self.matrix_result.as_ref().filter(|_| self.result_shown)
+    }
 
-                    self.last_result = Some(result);
-                    self.expression = formatted.clone();
-                    self.display = formatted;
-                    self.open_parens = 0;
-                    self.result_shown = true;
+    /// Date of the currency rates if the shown result was converted with them
+    pub fn rates_as_of(&self) -> Option<&str> {
+        Some(self.rates.as_of.as_str()).filter(|_| self.currency_result && self.result_shown)
+    }
 
-                    Ok(result)
-                }
-                Err(e) => Err(simplify_error(&e.to_string())),
-            },
-            Err(_) => Err("Error".to_string()),
+    /// Exact form of the shown result, such as `2√2` or `π/3`
+    pub fn exact_result(&self) -> Option<String> {
+        self.last_result
+            .filter(|_| self.result_shown)
+            .and_then(exact::exact_form)
+    }
+
+    /// Switch the shown result between its exact and decimal form (S⇔D)
+    pub fn toggle_exact_form(&mut self) {
+        self.record(Self::switch_exact_form);
+    }
+
+    fn switch_exact_form(&mut self) {
+        let (Some(value), Some(exact)) = (self.last_result, self.exact_result()) else {
+            return;
+        };
+        if self.display == exact {
+            self.expression = value_text(value);
+            self.display = self.display_format.format(value, self.rounding.mode);
+        } else {
+            self.expression = exact.clone();
+            self.display = exact;
         }
+        self.cursor = self.expression.chars().count();
+    }
+
+    /// Change the display format, reformatting a shown numeric result
+    pub fn set_display_format(&mut self, format: DisplayFormat) {
+        self.display_format = format;
+        self.reformat_result();
+    }
+
+    /// Change the rounding, reformatting a shown numeric result. Stored
+    /// values stay as they are until the next calculation.
+    pub fn set_rounding(&mut self, rounding: Rounding) {
+        self.rounding = rounding;
+        self.reformat_result();
+    }
+
+    fn reformat_result(&mut self) {
+        if let Some(value) = self.last_result.filter(|_| self.result_shown) {
+            if self.expression == value_text(value) {
+                self.display = self.display_format.format(value, self.rounding.mode);
+            }
+        }
+    }
+
+    /// Text to copy: the selection, the shown result or the current input, or with
+    /// `with_expression` the last calculation as `expression = result`.
+    /// Results are copied at full precision without digit grouping.
+    pub fn copy_text(&self, with_expression: bool) -> Option<String> {
+        if let Some((start, end)) = self.selection() {
+            return Some(
+                self.expression
+                    .chars()
+                    .skip(start)
+                    .take(end - start)
+                    .collect(),
+            );
+        }
+        if with_expression && self.result_shown {
+            if let Some(entry) = self.history.last() {
+                return Some(format!("{} = {}", entry.expression, entry.value));
+            }
+        }
+        Some(self.expression.clone()).filter(|text| !text.is_empty())
+    }
+
+    /// Paste text such as `2*pi/3` or `$1,234.50 * 2`, normalized to the
+    /// calculator's symbols. Text starting with an operator continues from
+    /// a shown result; other text replaces it.
+    fn append_paste(&mut self, text: &str) {
+        let text = normalize_paste(text, self.locale);
+        if text.is_empty() {
+            return;
+        }
+        let continues = text.starts_with(|c: char| "+×÷^%".contains(c));
+        if (self.result_shown && !continues) || self.expression.is_empty() {
+            self.expression.clear();
+            self.display.clear();
+        }
+        self.result_shown = false;
+        self.expression.push_str(&text);
+        self.display.push_str(&text);
+        self.open_parens = unclosed_parens(&self.expression);
     }
 
     /// Use result from history
-    pub fn use_history(&mut self, result: &str) {
+    fn append_history(&mut self, result: &str) {
         if self.display == "0" {
             self.expression = result.to_string();
             self.display = result.to_string();
@@ -367,30 +1064,115 @@ impl Calculator {
             .unwrap_or(' ')
     }
 
-    fn preprocess_expression(&self) -> String {
-        let mut expr = self.expression.clone();
+    /// Fit the selected regression model to the paired data
+    pub fn regression(&self) -> Result<Fit, String> {
+        let (xs, ys, weights) = self.stats.pairs();
+        regression::fit(self.regression_model, &xs, &ys, &weights)
+    }
 
-        // Replace display symbols with math symbols
-        expr = expr.replace("×", "*");
-        expr = expr.replace("÷", "/");
-        expr = expr.replace("−", "-");
-        expr = expr.replace("π", "pi");
+    /// Evaluation context: built-ins plus statistics, distribution and
+    /// financial functions, the named summary values of the current data set and
+    /// regression predictions
+    pub fn context(&self) -> Context {
+        let mut ctx = Context::new();
+        if self.angle_mode == AngleMode::Degrees {
+            ctx.use_degrees();
+        }
+        ctx.var("x", self.x);
+        ctx.rounding(self.rounding.mode);
 
-        // Auto-close parentheses
-        for _ in 0..self.open_parens {
-            expr.push(')');
+        for func in distributions::FUNCTIONS.iter().chain(&finance::FUNCTIONS) {
+            ctx.funcn(func.name, func.eval, func.required..func.params.len() + 1);
         }
+        ctx.funcn("npv", |xs| finance::npv(xs[0], &xs[1..]), 2..);
+        ctx.funcn("irr", finance::irr, 2..);
 
-        // Add implicit multiplication for constants
-        expr = add_implicit_multiplication(&expr);
+        for name in SUMMARY_NAMES {
+            ctx.funcn(name, move |xs| stats::summary_value(name, xs), 1..);
+        }
 
-        // Convert degrees to radians for trig functions
-        if self.angle_mode == AngleMode::Degrees {
-            expr = convert_trig_to_radians(&expr);
+        match self.stats.summary() {
+            Some(summary) => {
+                for (name, value) in summary.named_values() {
+                    ctx.var(name, value);
+                }
+            }
+            None => {
+                for name in SUMMARY_NAMES {
+                    ctx.var(name, f64::NAN);
+                }
+            }
         }
 
-        expr
-    }
+        match self.regression() {
+            Ok(fit) => {
+                ctx.func("yhat", move |x| fit.predict(x));
+                ctx.func("xhat", move |y| fit.estimate_x(y));
+            }
+            Err(_) => {
+                ctx.func("yhat", |_| f64::NAN);
+                ctx.func("xhat", |_| f64::NAN);
+            }
+        }
+
+        ctx
+    }
+
+    fn preprocess_expression(&self) -> String {
+        self.preprocess(&self.expression, self.open_parens)
+    }
+
+    /// Turn display text into parser text, with percentages in the percent
+    /// mode
+    fn preprocess(&self, text: &str, open_parens: i32) -> String {
+        preprocess(&apply_percent(text, self.percent_mode), open_parens)
+    }
+
+    /// Solve an equation in `x` such as `x²−5x+6=0` (an expression without
+    /// `=` is solved for zero) over `[lo, hi]`
+    pub fn solve_equation(
+        &self,
+        equation: &str,
+        lo: f64,
+        hi: f64,
+        guess: Option<f64>,
+    ) -> Result<solver::Report, String> {
+        let (lhs, rhs) = match equation.split_once('=') {
+            Some((lhs, rhs)) => (lhs, rhs),
+            None => (equation, "0"),
+        };
+        if rhs.contains('=') {
+            return Err("Too many =".to_string());
+        }
+        if lhs.trim().is_empty() || rhs.trim().is_empty() {
+            return Err("Incomplete".to_string());
+        }
+
+        let lhs = self.bind_x(lhs)?;
+        let rhs = self.bind_x(rhs)?;
+        solver::solve(|x| lhs(x) - rhs(x), lo, hi, guess)
+    }
+
+    /// Compile an expression into a function of the variable `x`
+    pub fn bind_x(&self, text: &str) -> Result<Box<dyn Fn(f64) -> f64>, String> {
+        self.bind(text, "x")
+    }
+
+    /// Compile an expression into a function of the variable `var`
+    /// (`theta` for θ)
+    pub fn bind(&self, text: &str, var: &str) -> Result<Box<dyn Fn(f64) -> f64>, String> {
+        let expr: Expr = self.preprocess(text.trim(), 0).parse()?;
+        let f = expr.bind(self.context(), var)?;
+        Ok(Box::new(f))
+    }
+
+    /// Value of a constant expression such as `2π`, without touching the
+    /// display or the history
+    pub fn evaluate_text(&self, text: &str) -> Result<f64, String> {
+        let expr: Expr = self.preprocess(text.trim(), 0).parse()?;
+        let (value, _) = expr.eval_with_error(&self.context())?;
+        Ok(value)
+    }
 
     pub fn toggle_angle_mode(&mut self) {
         self.angle_mode = match self.angle_mode {
@@ -399,69 +1181,268 @@ impl Calculator {
         };
     }
 
+    pub fn toggle_percent_mode(&mut self) {
+        self.percent_mode = match self.percent_mode {
+            PercentMode::Plain => PercentMode::Business,
+            PercentMode::Business => PercentMode::Plain,
+        };
+    }
+
     pub fn get_open_parens(&self) -> i32 {
         self.open_parens
     }
 }
 
-fn convert_trig_to_radians(expr: &str) -> String {
-    let mut result = expr.to_string();
-    let deg_to_rad = PI / 180.0;
+/// Whether a character can end an operand (number, `)`, constant, `x`,
+/// unit, square or percent)
+fn ends_operand(c: char) -> bool {
+    c.is_ascii_digit() || c.is_ascii_alphabetic() || ")]π²%".contains(c)
+}
 
-    for func in ["sin", "cos", "tan"] {
-        let pattern = format!("{}(", func);
-        let mut new_result = String::new();
-        let mut remaining = result.as_str();
+/// Whether `c` is a combining mark, as in x̂
+fn is_combining(c: char) -> bool {
+    ('\u{0300}'..='\u{036F}').contains(&c)
+}
 
-        while let Some(pos) = remaining.find(&pattern) {
-            new_result.push_str(&remaining[..pos]);
-            remaining = &remaining[pos..];
+/// Number of opening parentheses in `text` that are not closed
+fn unclosed_parens(text: &str) -> i32 {
+    let opened = text.matches('(').count() as i32;
+    let closed = text.matches(')').count() as i32;
+    (opened - closed).max(0)
+}
 
-            if let Some(end) = find_closing_paren(remaining, func.len()) {
-                let inner = &remaining[func.len() + 1..end];
-                new_result.push_str(&format!("{}(({})*{})", func, inner, deg_to_rad));
-                remaining = &remaining[end + 1..];
-            } else {
-                new_result.push_str(&remaining[..pattern.len()]);
-                remaining = &remaining[pattern.len()..];
+/// Whether `text` has an integral, sum or product, whose evaluation can
+/// take up to millions of function evaluations
+fn uses_costly_calculus(text: &str) -> bool {
+    let expr = preprocess(text, 0);
+    ["integral(", "summation(", "product("]
+        .iter()
+        .any(|name| expr.contains(name))
+}
+
+/// Replace each `%` after an operand (not the one of `Δ%`) by its meaning
+/// in `mode`, as if the text before it had just been typed
+fn apply_percent(text: &str, mode: PercentMode) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut result = String::with_capacity(text.len());
+    for (i, &c) in chars.iter().enumerate() {
+        if c != '%' || i == 0 || chars[i - 1] == 'Δ' {
+            result.push(c);
+            continue;
+        }
+        result = match mode {
+            PercentMode::Plain => format!("{}/100", result),
+            PercentMode::Business => business_percent(&result),
+        };
+        // An operand right after the percentage is multiplied by it
+        if chars
+            .get(i + 1)
+            .is_some_and(|&next| next.is_alphanumeric() || "(.√".contains(next))
+        {
+            result.push('×');
+        }
+    }
+    result
+}
+
+/// Rewrite the operand `b` at the end of `expression` with office
+/// calculator percent rules: `a+b` becomes `(a)×(100+b)÷100`, `a×b` becomes
+/// `a×(b÷100)`; `a` is everything before the operator within the innermost
+/// open group
+fn business_percent(expression: &str) -> String {
+    let mut depth = 0;
+    let mut operator: Option<(usize, char)> = None;
+    let mut group_start = 0;
+    for (i, c) in expression.char_indices().rev() {
+        match c {
+            ')' | ']' => depth += 1,
+            '(' | '[' if depth > 0 => depth -= 1,
+            '(' | '[' | ',' | ';' if depth == 0 => {
+                group_start = i + c.len_utf8();
+                break;
+            }
+            '+' | '-' | '−' | '×' | '÷' | '^' if depth == 0 && operator.is_none() => {
+                operator = Some((i, c));
             }
+            _ => {}
         }
-        new_result.push_str(remaining);
-        result = new_result;
     }
 
-    result
+    let Some((i, op)) = operator else {
+        return format!("{}/100", expression);
+    };
+    let end = i + op.len_utf8();
+    let (before, b) = (&expression[..i], &expression[end..]);
+    // A sign without an operand before it is not an operator
+    let binary = before[group_start..]
+        .chars()
+        .last()
+        .is_some_and(ends_operand);
+    match op {
+        '+' | '-' | '−' if binary => format!(
+            "{}({})×(100{}{})÷100",
+            &before[..group_start],
+            &before[group_start..],
+            op,
+            b
+        ),
+        '×' | '÷' => format!("{}({}÷100)", &expression[..end], b),
+        _ => format!("{}/100", expression),
+    }
 }
 
-fn find_closing_paren(s: &str, func_len: usize) -> Option<usize> {
-    let bytes = s.as_bytes();
-    let start = func_len + 1;
+fn check_finite(result: f64) -> Result<(), String> {
+    if result.is_nan() {
+        return Err("Undefined".to_string());
+    }
+    if result.is_infinite() {
+        return Err("Infinity".to_string());
+    }
+    Ok(())
+}
 
-    if start >= bytes.len() || bytes[func_len] != b'(' {
-        return None;
+/// Turn display text into a string the expression parser understands
+fn preprocess(text: &str, open_parens: i32) -> String {
+    // Date and duration literals first, while `-` and `:` are unchanged
+    let mut expr = dates::replace_literals(&replace_derivatives(text));
+
+    // Replace display symbols with math symbols
+    expr = expr.replace("Δ%", "pchange");
+    expr = expr.replace("∫", "integral");
+    expr = expr.replace("Σ", "summation");
+    expr = expr.replace("Π", "product");
+    expr = expr.replace("×", "*");
+    expr = expr.replace("·", "*");
+    expr = expr.replace("÷", "/");
+    expr = expr.replace("−", "-");
+    expr = expr.replace("π", "pi");
+    // Parenthesized so that it stays apart from neighbouring names (πθ)
+    expr = expr.replace("θ", "(theta)");
+    expr = expr.replace("ŷ", "yhat");
+    expr = expr.replace("x̂", "xhat");
+    expr = expr.replace("²", "^2");
+    expr = expr.replace("³", "^3");
+    expr.retain(|c| !c.is_whitespace());
+
+    // Auto-close parentheses
+    for _ in 0..open_parens {
+        expr.push(')');
     }
 
-    let mut depth = 1;
-    for (i, &byte) in bytes.iter().enumerate().skip(start) {
-        match byte {
-            b'(' => depth += 1,
-            b')' => {
-                depth -= 1;
-                if depth == 0 {
-                    return Some(i);
+    // Add implicit multiplication for constants
+    add_implicit_multiplication(&expr)
+}
+
+/// Calculator text of pasted text: ASCII operators become display symbols
+/// (`*` → `×`, `/` → `÷`, `-` → `−`, `**` → `^`, `;` → `,`), `pi` becomes `π` and
+/// `log(` becomes `log10(`. Currency symbols, digit grouping and anything
+/// from `=` on are dropped; date literals, `d/dx(` and `%` (a percentage in
+/// the percent mode) are kept.
+fn normalize_paste(text: &str, locale: Locale) -> String {
+    let text = text.split('=').next().unwrap_or("");
+    let text: String = text
+        .chars()
+        .filter(|c| !"$€£¥".contains(*c))
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let chars: Vec<char> = locale.delocalize(&text).chars().collect();
+
+    let mut result = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let after_name = result.ends_with(|p: char| p.is_alphanumeric() || p == '_');
+        if c.is_ascii_digit() && !after_name {
+            if let Some(len) = dates::literal_len(&chars[i..]) {
+                result.extend(&chars[i..i + len]);
+                i += len;
+                continue;
+            }
+        }
+        if (c.is_ascii_alphabetic() || c == '_') && !after_name {
+            let len = chars[i..]
+                .iter()
+                .take_while(|c| c.is_ascii_alphanumeric() || **c == '_')
+                .count();
+            let name: String = chars[i..i + len].iter().collect();
+            let next = chars.get(i + len);
+            let derivative = name == "d" && next == Some(&'/') && chars.get(i + 2) == Some(&'d');
+            match name.as_str() {
+                "pi" | "Pi" | "PI" => result.push('π'),
+                "theta" => result.push('θ'),
+                "log" if next == Some(&'(') => result.push_str("log10"),
+                _ if derivative => {
+                    result.push_str("d/");
+                    i += 2;
+                    continue;
                 }
+                _ => result.push_str(&name),
             }
-            _ => {}
+            i += len;
+            continue;
+        }
+        match c {
+            '*' if chars.get(i + 1) == Some(&'*') => {
+                result.push('^');
+                i += 1;
+            }
+            '*' => result.push('×'),
+            '/' => result.push('÷'),
+            '-' | '–' => result.push('−'),
+            // The argument separator key
+            ';' => result.push(','),
+            _ => result.push(c),
+        }
+        i += 1;
+    }
+    result
+}
+
+/// Replace `d/dx(` (with any variable name) by `deriv(`
+fn replace_derivatives(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut remaining = text;
+
+    while let Some(pos) = remaining.find("d/d") {
+        result.push_str(&remaining[..pos]);
+        let after = &remaining[pos + 3..];
+        let name_len = after
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(after.len());
+        if name_len > 0 && after[name_len..].starts_with('(') {
+            result.push_str("deriv");
+            remaining = &after[name_len..];
+        } else {
+            result.push_str("d/d");
+            remaining = after;
         }
     }
-    None
+    result.push_str(remaining);
+    result
 }
 
 fn add_implicit_multiplication(expr: &str) -> String {
     let mut result = String::with_capacity(expr.len() * 2);
     let chars: Vec<char> = expr.chars().collect();
     let functions = [
-        "sin", "cos", "tan", "asin", "acos", "atan", "log10", "ln", "sqrt", "abs", "exp", "pi",
+        "sin",
+        "cos",
+        "tan",
+        "asin",
+        "acos",
+        "atan",
+        "log10",
+        "ln",
+        "sqrt",
+        "abs",
+        "exp",
+        "pi",
+        "deriv",
+        "integral",
+        "summation",
+        "product",
     ];
 
     let mut i = 0;
@@ -489,10 +1470,25 @@ fn add_implicit_multiplication(expr: &str) -> String {
             continue;
         }
 
-        // Handle ( after digit or )
+        // Variable x, e.g. 5x, 2(x+1)x, x(x−1)
+        if c == 'x' && is_standalone_x(&chars, i) {
+            if let Some(last) = result.chars().last() {
+                if (last.is_ascii_digit() && !ends_with_identifier(&result)) || last == ')' {
+                    result.push('*');
+                }
+            }
+            result.push('x');
+            if matches!(chars.get(i + 1), Some(next) if *next == '(' || next.is_ascii_digit()) {
+                result.push('*');
+            }
+            i += 1;
+            continue;
+        }
+
+        // Handle ( after a number or ) (but not after names like chi2)
         if c == '(' && !result.is_empty() {
             let last = result.chars().last().unwrap();
-            if last.is_ascii_digit() || last == ')' {
+            if (last.is_ascii_digit() && !ends_with_identifier(&result)) || last == ')' {
                 result.push('*');
             }
         }
@@ -512,6 +1508,24 @@ fn add_implicit_multiplication(expr: &str) -> String {
     result
 }
 
+/// Whether the `x` at `i` is the variable rather than part of a name
+fn is_standalone_x(chars: &[char], i: usize) -> bool {
+    let is_name_char = |c: &char| c.is_ascii_alphabetic() || *c == '_';
+    let prev_ok = i == 0 || !is_name_char(&chars[i - 1]);
+    let next_ok = chars.get(i + 1).is_none_or(|c| !is_name_char(c));
+    prev_ok && next_ok
+}
+
+/// Whether the trailing alphanumeric run of `s` is a name (starts with a
+/// letter) rather than a number such as `12` or `1.5e12`
+fn ends_with_identifier(s: &str) -> bool {
+    s.chars()
+        .rev()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '.')
+        .last()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+}
+
 fn char_to_byte_index(s: &str, char_idx: usize) -> usize {
     s.char_indices()
         .nth(char_idx)
@@ -519,7 +1533,19 @@ fn char_to_byte_index(s: &str, char_idx: usize) -> usize {
         .unwrap_or(s.len())
 }
 
-fn format_result(value: f64) -> String {
+/// Expression text of a stored value that reads back as the same number:
+/// the shortest digits, with an exponent for very large or small values
+pub fn value_text(value: f64) -> String {
+    if value == 0.0 {
+        String::from("0")
+    } else if (1e-6..1e15).contains(&value.abs()) {
+        format!("{}", value)
+    } else {
+        format!("{:e}", value)
+    }
+}
+
+pub fn format_result(value: f64) -> String {
     if value.fract() == 0.0 && value.abs() < 1e12 {
         format!("{}", value as i64)
     } else if value.abs() < 1e-8 || value.abs() >= 1e12 {
@@ -533,20 +1559,18 @@ fn format_result(value: f64) -> String {
     }
 }
 
-fn simplify_error(err: &str) -> String {
-    if err.contains("parse") || err.contains("Parse") {
-        "Syntax error".to_string()
-    } else if err.contains("parenthesis") {
-        "Missing )".to_string()
-    } else {
-        "Error".to_string()
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    impl Calculator {
+        /// Calculate and return a numeric result
+        fn calculate(&mut self) -> Result<f64, String> {
+            self.evaluate()?;
+            Ok(self.last_result.unwrap_or(0.0))
+        }
+    }
+
     #[test]
     fn test_default_state() {
         let calc = Calculator::default();
@@ -687,6 +1711,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::field_reassign_with_default)]
     fn test_sin_degrees() {
         let mut calc = Calculator::default();
         calc.angle_mode = AngleMode::Degrees;
@@ -699,6 +1724,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::field_reassign_with_default)]
     fn test_cos_degrees() {
         let mut calc = Calculator::default();
         calc.angle_mode = AngleMode::Degrees;
@@ -710,6 +1736,20 @@ mod tests {
         assert!((result - 0.5).abs() < 1e-10);
     }
 
+    #[test]
+    fn test_inverse_trig_degrees() {
+        let mut calc = Calculator::default();
+        for (text, degrees) in [("asin(0.5)", 30.0), ("acos(0.5)", 60.0), ("atan(1)", 45.0)] {
+            calc.expression = text.to_string();
+            assert_eq!(calc.calculate(), Ok(degrees), "{text}");
+        }
+        calc.expression = "atan2(1,−1)".to_string();
+        assert_eq!(calc.calculate(), Ok(135.0));
+        calc.toggle_angle_mode();
+        calc.expression = "asin(1)".to_string();
+        assert!((calc.calculate().unwrap() - std::f64::consts::FRAC_PI_2).abs() < 1e-12);
+    }
+
     #[test]
     fn test_clear() {
         let mut calc = Calculator::default();
@@ -755,6 +1795,38 @@ mod tests {
         calc.input_percent();
         let result = calc.calculate().unwrap();
         assert_eq!(result, 0.5);
+
+        let percent = |keys: &str, mode: PercentMode| {
+            let mut calc = Calculator {
+                percent_mode: mode,
+                ..Default::default()
+            };
+            for key in keys.chars() {
+                match key {
+                    '%' => calc.input_percent(),
+                    '(' => calc.input_open_paren(),
+                    ')' => calc.input_close_paren(),
+                    '+' | '−' | '×' | '÷' => calc.input_operator(&key.to_string()),
+                    digit => calc.input_digit(&digit.to_string()),
+                }
+            }
+            assert_eq!(calc.display, keys);
+            calc.calculate().unwrap()
+        };
+        let business = |keys| percent(keys, PercentMode::Business);
+        assert_eq!(business("200+10%"), 220.0);
+        assert_eq!(business("200−10%"), 180.0);
+        assert_eq!(business("200×10%"), 20.0);
+        assert_eq!(business("200÷10%"), 2000.0);
+        // The percentage is of everything before the operator
+        assert_eq!(business("2×50+10%"), 110.0);
+        assert_eq!(business("5+(200+10%)"), 225.0);
+        assert_eq!(business("−50%"), -0.5);
+        assert_eq!(percent("200+10%", PercentMode::Plain), 200.1);
+
+        // Percentage change
+        assert_eq!(eval("Δ%(80,100)"), 25.0);
+        assert_eq!(eval("Δ%(200,150)"), -25.0);
     }
 
     #[test]
@@ -800,6 +1872,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn test_format_result_decimal() {
         assert_eq!(format_result(3.14), "3.14");
         assert_eq!(format_result(2.5), "2.5");
@@ -848,4 +1921,851 @@ mod tests {
         let result = calc.calculate().unwrap();
         assert!((result - 1.0).abs() < 1e-10);
     }
+
+    #[test]
+    fn test_list_functions() {
+        let mut calc = Calculator::default();
+        calc.expression = "mean(3,5,8,13)".to_string();
+        calc.display = calc.expression.clone();
+        assert_eq!(calc.calculate().unwrap(), 7.25);
+
+        calc.expression = "median(3,5,8,13)+max(1,2)".to_string();
+        calc.display = calc.expression.clone();
+        assert_eq!(calc.calculate().unwrap(), 8.5);
+    }
+
+    #[test]
+    fn test_input_separator() {
+        let mut calc = Calculator::default();
+        calc.input_separator();
+        assert!(calc.expression.is_empty());
+
+        calc.input_function("mean");
+        calc.input_digit("2");
+        calc.input_separator();
+        calc.input_separator();
+        calc.input_digit("4");
+        calc.input_decimal();
+        calc.input_digit("5");
+        calc.input_close_paren();
+        assert_eq!(calc.expression, "mean(2,4.5)");
+        assert_eq!(calc.calculate().unwrap(), 3.25);
+    }
+
+    #[test]
+    fn test_stats_named_values() {
+        let mut calc = Calculator::default();
+        calc.input_constant("mean");
+        assert!(calc.calculate().is_err());
+
+        calc.stats.push(2.0, 3);
+        calc.stats.push(6.0, 1);
+        calc.clear();
+        calc.input_digit("2");
+        calc.input_constant("count");
+        calc.input_operator("+");
+        calc.input_constant("mean");
+        assert_eq!(calc.calculate().unwrap(), 11.0);
+    }
+
+    #[test]
+    fn test_regression_predictions() {
+        let mut calc = Calculator::default();
+        for (x, y) in [(1.0, 3.0), (2.0, 5.0), (3.0, 7.0)] {
+            calc.stats.push_pair(x, y, 1);
+        }
+        calc.input_function("ŷ");
+        calc.input_digit("4");
+        calc.input_close_paren();
+        assert!((calc.calculate().unwrap() - 9.0).abs() < 1e-10);
+
+        calc.clear();
+        calc.input_function("x̂");
+        calc.input_digit("1");
+        calc.input_digit("1");
+        calc.input_close_paren();
+        assert!((calc.calculate().unwrap() - 5.0).abs() < 1e-10);
+
+        calc.regression_model = Model::Power;
+        calc.stats.clear();
+        calc.stats.push_pair(1.0, 2.0, 1);
+        calc.stats.push_pair(4.0, 4.0, 1);
+        calc.clear();
+        calc.input_function("ŷ");
+        calc.input_digit("9");
+        calc.input_close_paren();
+        assert!((calc.calculate().unwrap() - 6.0).abs() < 1e-10);
+    }
+
+    #[test]
+    fn test_clear_entry_combining_mark() {
+        let mut calc = Calculator::default();
+        calc.input_digit("2");
+        calc.input_function("x̂");
+        calc.clear_entry();
+        assert_eq!(calc.expression, "2×x̂");
+        calc.clear_entry();
+        assert_eq!(calc.expression, "2×");
+        assert_eq!(calc.open_parens, 0);
+    }
+
+    fn eval(expr: &str) -> f64 {
+        let mut calc = Calculator::default();
+        calc.expression = expr.to_string();
+        calc.display = calc.expression.clone();
+        calc.calculate().unwrap()
+    }
+
+    fn assert_close(expr: &str, expected: f64, tolerance: f64) {
+        let result = eval(expr);
+        assert!(
+            (result - expected).abs() <= tolerance,
+            "{} = {}, expected {}",
+            expr,
+            result,
+            expected
+        );
+    }
+
+    // Reference values computed with mpmath at 30 significant digits
+
+    #[test]
+    fn test_normal_distribution() {
+        assert_close("normpdf(0)", 0.398_942_280_401_432_7, 1e-15);
+        assert_close("normcdf(1.96)", 0.975_002_104_851_779_5, 1e-14);
+        assert_close("normcdf(−3)", 0.001_349_898_031_630_094_6, 1e-15);
+        assert_close("normcdf(110,100,15)", 0.747_507_462_453_077_1, 1e-14);
+        assert_close("invnorm(0.975)", 1.959_963_984_540_054_3, 1e-12);
+        assert_close("invnorm(0.001)", -3.090_232_306_167_813_6, 1e-12);
+        assert_close("invnorm(0.5,100,15)", 100.0, 1e-12);
+    }
+
+    #[test]
+    fn test_binomial_distribution() {
+        assert_close("binompdf(3,10,0.5)", 0.1171875, 1e-14);
+        assert_close("binomcdf(3,10,0.5)", 0.171875, 1e-14);
+        assert_close("binomcdf(10,10,0.3)", 1.0, 0.0);
+        assert_close("invbinom(0.171875,10,0.5)", 3.0, 0.0);
+        assert_close("invbinom(0.5,10,0.5)", 5.0, 0.0);
+        assert_close("invbinom(0,10,0.5)", 0.0, 0.0);
+        assert_close("invbinom(1,10,0.5)", 10.0, 0.0);
+        assert_close("invbinom(0.99,1e7,0.5)", 5_003_678.0, 0.0);
+    }
+
+    #[test]
+    fn test_poisson_distribution() {
+        assert_close("poisspdf(2,3)", 0.224_041_807_655_387_75, 1e-15);
+        assert_close("poisscdf(2,3)", 0.423_190_081_126_843_53, 1e-14);
+        assert_close("poisscdf(999999,1e6)", 0.499_867_019_239_127_4, 1e-8);
+        assert_close("invpoiss(0.5,3)", 3.0, 0.0);
+        assert_close("invpoiss(0,3)", 0.0, 0.0);
+        assert_close("invpoiss(0.5,1e6)", 1_000_000.0, 0.0);
+    }
+
+    #[test]
+    fn test_t_distribution() {
+        assert_close("tpdf(1,3)", 0.206_748_335_783_172_03, 1e-14);
+        assert_close("tcdf(2,5)", 0.949_030_260_585_070_8, 1e-13);
+        assert_close("tcdf(−1.5,10)", 0.082_253_663_222_720_1, 1e-13);
+        assert_close("invt(0.975,10)", 2.228_138_851_986_275, 1e-10);
+    }
+
+    #[test]
+    fn test_chi_square_distribution() {
+        assert_close("chi2pdf(2,3)", 0.207_553_748_710_297_36, 1e-14);
+        assert_close("chi2cdf(3.84,1)", 0.949_956_478_751_294_9, 1e-13);
+        assert_close("chi2cdf(10,4)", 0.959_572_318_005_487_1, 1e-13);
+        assert_close("invchi2(0.95,1)", 3.841_458_820_694_126, 1e-10);
+        assert_close("invchi2(0.95,2)", 5.991_464_547_107_982, 1e-10);
+    }
+
+    #[test]
+    fn test_exponential_distribution() {
+        assert_close("exppdf(1,0.5)", 0.5 * (-0.5f64).exp(), 1e-15);
+        assert_close("expcdf(1,0.5)", 0.393_469_340_287_366_6, 1e-15);
+        assert_close("invexp(0.393469340287366576,0.5)", 1.0, 1e-12);
+    }
+
+    #[test]
+    fn test_distribution_domain_errors() {
+        let mut calc = Calculator::default();
+        calc.expression = "normcdf(1,0,−1)".to_string();
+        calc.display = calc.expression.clone();
+        assert!(calc.calculate().is_err());
+
+        calc.expression = "binompdf(1,10,1.5)".to_string();
+        assert!(calc.calculate().is_err());
+    }
+
+    #[test]
+    fn test_input_call() {
+        let mut calc = Calculator::default();
+        calc.input_digit("2");
+        calc.input_call("normcdf", &["-1", "0", "1"]);
+        assert_eq!(calc.expression, "2×normcdf(−1,0,1)");
+        assert_eq!(calc.open_parens, 0);
+        assert_close(
+            &calc.expression.clone(),
+            2.0 * 0.158_655_253_931_457_05,
+            1e-14,
+        );
+    }
+
+    #[test]
+    fn test_variable_x() {
+        let mut calc = Calculator {
+            x: 4.0,
+            ..Default::default()
+        };
+        calc.input_digit("3");
+        calc.input_constant("x");
+        calc.input_operator("+");
+        calc.input_constant("x");
+        calc.input_square();
+        assert_eq!(calc.expression, "3×x+x²");
+        assert_eq!(calc.calculate().unwrap(), 28.0);
+    }
+
+    #[test]
+    fn test_implicit_multiplication_with_x() {
+        let expr = preprocess("2x+3(x−1)x+exp(x)+max(x,1)", 0);
+        assert_eq!(expr, "2*x+3*(x-1)*x+exp(x)+max(x,1)");
+    }
+
+    #[test]
+    fn test_solve_equation() {
+        let calc = Calculator::default();
+        let report = calc
+            .solve_equation("x² − 5x + 6 = 0", -10.0, 10.0, None)
+            .unwrap();
+        assert_eq!(report.roots, vec![2.0, 3.0]);
+
+        // Expression on both sides
+        let report = calc
+            .solve_equation("x^3 = 2x+4", -10.0, 10.0, None)
+            .unwrap();
+        assert_eq!(report.roots, vec![2.0]);
+
+        // Without '=' the expression is solved for zero
+        let report = calc.solve_equation("sin(x)−0.5", 0.0, 180.0, None).unwrap();
+        assert_eq!(report.roots.len(), 2);
+        assert!((report.roots[0] - 30.0).abs() < 1e-9);
+        assert!((report.roots[1] - 150.0).abs() < 1e-9);
+
+        assert!(calc.solve_equation("x+=1", -1.0, 1.0, None).is_err());
+        assert!(calc.solve_equation("x=1=2", -1.0, 1.0, None).is_err());
+        assert!(calc.solve_equation("y=1", -1.0, 1.0, None).is_err());
+    }
+
+    #[test]
+    fn test_calculus() {
+        // Trigonometric functions in the body follow the angle mode
+        let mut calc = Calculator {
+            angle_mode: AngleMode::Radians,
+            ..Default::default()
+        };
+        calc.expression = "d/dx(x³,x,2)".to_string();
+        calc.display = calc.expression.clone();
+        assert!((calc.calculate().unwrap() - 12.0).abs() < 1e-9);
+        assert!(calc.error_estimate().is_some());
+
+        calc.expression = "∫(sin(t),t,0,π)".to_string();
+        calc.display = calc.expression.clone();
+        assert!((calc.calculate().unwrap() - 2.0).abs() < 1e-12);
+
+        assert_eq!(eval("Σ(k²,k,1,10)"), 385.0);
+        assert_eq!(eval("Π(k,k,1,5)"), 120.0);
+        assert_eq!(eval("2Σ(k,k,1,4)"), 20.0);
+        // x stays the calculator variable outside the operation
+        let mut calc = Calculator {
+            x: 3.0,
+            ..Default::default()
+        };
+        calc.expression = "Σ(x·k,k,1,3)+x".replace('·', "×");
+        calc.display = calc.expression.clone();
+        assert_eq!(calc.calculate(), Ok(21.0));
+        assert_eq!(calc.error_estimate(), None);
+    }
+
+    #[test]
+    fn test_symbolic_derivative() {
+        let mut calc = Calculator {
+            angle_mode: AngleMode::Radians,
+            ..Default::default()
+        };
+        calc.expression = "d/dx(x²×sin(x),x)".to_string();
+        calc.display = calc.expression.clone();
+        assert_eq!(calc.evaluate(), Ok(()));
+        assert_eq!(calc.display, "2x·sin(x)+x²·cos(x)");
+        assert_eq!(calc.last_result, None);
+
+        // The result can be evaluated again
+        calc.x = 0.0;
+        assert_eq!(calc.calculate(), Ok(0.0));
+
+        calc.expression = "diff(x³−2x,x)".to_string();
+        calc.display = calc.expression.clone();
+        assert_eq!(calc.evaluate(), Ok(()));
+        assert_eq!(calc.display, "3x²−2");
+
+        // A numeric derivative still gives a number
+        calc.expression = "d/dx(x³,x,2)".to_string();
+        assert_eq!(calc.evaluate(), Ok(()));
+        assert_eq!(calc.display, "12");
+
+        // In degrees, the default angle mode, the chain rule brings in π/180
+        calc.angle_mode = AngleMode::Degrees;
+        calc.expression = "diff(x²·sin(x),x)".to_string();
+        calc.display = calc.expression.clone();
+        assert_eq!(calc.evaluate(), Ok(()));
+        assert_eq!(calc.display, "2x·sin(x)+x²·π/180·cos(x)");
+    }
+
+    #[test]
+    fn test_exact_form() {
+        let mut calc = Calculator {
+            expression: "sin(60)".to_string(),
+            ..Default::default()
+        };
+        calc.calculate().unwrap();
+        assert_eq!(calc.display, "0.8660254038");
+        calc.toggle_exact_form();
+        assert_eq!(calc.display, "√3/2");
+        // The exact form evaluates to the same value
+        assert!((calc.calculate().unwrap() - 0.75f64.sqrt()).abs() < 1e-15);
+        calc.toggle_exact_form();
+        calc.toggle_exact_form();
+        assert_eq!(calc.display, "0.8660254038");
+
+        calc.expression = "sqrt(8)".to_string();
+        calc.calculate().unwrap();
+        calc.toggle_exact_form();
+        assert_eq!(calc.display, "2√2");
+
+        // Integers have no other form
+        calc.expression = "2+2".to_string();
+        calc.calculate().unwrap();
+        assert_eq!(calc.exact_result(), None);
+        calc.toggle_exact_form();
+        assert_eq!(calc.display, "4");
+    }
+
+    #[test]
+    fn test_matrix_expression() {
+        let mut calc = Calculator {
+            expression: "[[1,2],[3,4]]×[1,−1]".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(calc.evaluate(), Ok(()));
+        assert_eq!(calc.display, "[-1,-1]");
+        assert_eq!(calc.matrix_result().map(Matrix::rows), Some(2));
+        assert_eq!(calc.last_result, None);
+
+        // The result can be used in the next calculation
+        calc.input_operator("×");
+        calc.use_history("2");
+        assert_eq!(calc.evaluate(), Ok(()));
+        assert_eq!(calc.display, "[-2,-2]");
+
+        calc.expression = "det([[2,1],[1,3]])+1".to_string();
+        assert_eq!(calc.evaluate(), Ok(()));
+        assert_eq!(calc.display, "6");
+        assert_eq!(calc.matrix_result(), None);
+        assert_eq!(calc.last_result, Some(6.0));
+    }
+
+    #[test]
+    fn test_financial_functions() {
+        assert_close("pmt(0.05/12,360,300000)", -1610.464869, 1e-5);
+        assert_close("fv(0.06,10,−100,0,1)", 1397.164264, 1e-5);
+        assert_close("npv(0.1,−1000,300,400,500)", -21.036814, 1e-5);
+        assert_close("irr(−1000,300,400,500)", 0.0889633947, 1e-8);
+        assert_close("ddb(10000,1000,5,2)", 2400.0, 1e-9);
+        assert!(Calculator {
+            expression: "pmt(0.05)".to_string(),
+            ..Default::default()
+        }
+        .calculate()
+        .is_err());
+    }
+
+    #[test]
+    fn test_display_format() {
+        let mut calc = Calculator {
+            display_format: DisplayFormat::Fix(2),
+            ..Default::default()
+        };
+        calc.expression = "2÷3".to_string();
+        calc.display = "2÷3".to_string();
+        assert_eq!(calc.evaluate(), Ok(()));
+        assert_eq!(calc.display, "0.67");
+        // The value keeps full precision for further calculation
+        assert_eq!(calc.expression, "0.666666666666667");
+        let entry = calc.history.last().unwrap();
+        assert_eq!(
+            (entry.result.as_str(), entry.value.as_str()),
+            ("0.67", "0.666666666666667")
+        );
+
+        calc.set_display_format(DisplayFormat::Eng(1, true));
+        assert_eq!(calc.display, "666.7m");
+        calc.set_display_format(DisplayFormat::Auto);
+        assert_eq!(calc.display, "0.6666666667");
+    }
+
+    #[test]
+    fn test_rounding() {
+        use crate::format::RoundingMode;
+
+        let mut calc = Calculator::default();
+        let result = |calc: &mut Calculator, text: &str| {
+            calc.expression = text.to_string();
+            calc.calculate().unwrap()
+        };
+        assert_eq!(result(&mut calc, "round(2.675,2)"), 2.68);
+        assert_eq!(result(&mut calc, "round(−2.5)"), -3.0);
+        assert_eq!(result(&mut calc, "round(1234.5,−2)"), 1200.0);
+        assert_eq!(result(&mut calc, "trunc(−2.7)"), -2.0);
+        assert_eq!(result(&mut calc, "frac(2.75)"), 0.75);
+        assert_eq!(result(&mut calc, "floor(−2.5)+ceil(2.1)"), 0.0);
+        calc.expression = "round(2.5,0.5)".to_string();
+        assert!(calc.calculate().is_err());
+        // Working precision hides binary noise
+        assert_eq!(result(&mut calc, "0.1+0.2"), 0.3);
+
+        calc.set_rounding(Rounding {
+            mode: RoundingMode::HalfEven,
+            ..Rounding::default()
+        });
+        assert_eq!(result(&mut calc, "round(2.665,2)+round(2.5)"), 4.66);
+
+        // Results stored as shown
+        calc.display_format = DisplayFormat::Fix(2);
+        calc.set_rounding(Rounding {
+            stored: true,
+            ..calc.rounding
+        });
+        assert_eq!(result(&mut calc, "2÷3"), 0.67);
+        assert_eq!(calc.expression, "0.67");
+    }
+
+    #[test]
+    fn test_decimal_key() {
+        let keys = |locale: Locale, keys: &str| {
+            let mut calc = Calculator {
+                locale,
+                ..Default::default()
+            };
+            for key in keys.chars() {
+                match key {
+                    '.' | ',' => calc.input_decimal_key(key),
+                    '(' => calc.input_function("max"),
+                    ')' => calc.input_close_paren(),
+                    digit => calc.input_digit(&digit.to_string()),
+                }
+            }
+            calc.expression
+        };
+        let point = Locale::default();
+        assert_eq!(keys(point, "1.5"), "1.5");
+        assert_eq!(keys(point, "1,5"), "15");
+        assert_eq!(keys(point, "(1,2.5)"), "max(1,2.5)");
+        let comma = Locale {
+            decimal_comma: true,
+            grouping: true,
+        };
+        assert_eq!(keys(comma, "1,5"), "1.5");
+        assert_eq!(keys(comma, "1.5"), "15");
+        assert_eq!(keys(comma, "(1,2)"), "max(1.2)");
+    }
+
+    #[test]
+    fn test_reused_result_precision() {
+        let mut calc = Calculator {
+            rounding: Rounding {
+                precision: 17,
+                ..Rounding::default()
+            },
+            ..Default::default()
+        };
+        calc.expression = "1÷3".to_string();
+        assert!(calc.evaluate().is_ok());
+        calc.input_operator("×");
+        calc.input_digit("3");
+        assert_eq!(calc.calculate(), Ok(1.0));
+
+        calc.expression = "1234567890123+1".to_string();
+        assert!(calc.evaluate().is_ok());
+        calc.input_operator("+");
+        calc.input_digit("1");
+        assert_eq!(calc.calculate(), Ok(1234567890125.0));
+        assert_eq!(calc.expression, "1234567890125");
+
+        assert_eq!(value_text(1.5e-300), "1.5e-300");
+        assert_eq!(value_text(-0.0), "0");
+    }
+
+    #[test]
+    fn test_paste() {
+        assert_eq!(
+            normalize_paste("2*pi/3 - 1", Locale::default()),
+            "2×π÷3 − 1"
+        );
+        assert_eq!(
+            normalize_paste("$1,234,567.50**2", Locale::default()),
+            "1234567.50^2"
+        );
+        assert_eq!(
+            normalize_paste("log(100)+sqrt(2)", Locale::default()),
+            "log10(100)+sqrt(2)"
+        );
+        assert_eq!(
+            normalize_paste("d/dx(x^2,x,3)", Locale::default()),
+            "d/dx(x^2,x,3)"
+        );
+        assert_eq!(
+            normalize_paste("2026-03-01 - 7 days", Locale::default()),
+            "2026-03-01 − 7 days"
+        );
+        assert_eq!(normalize_paste("3 + 4 = 7", Locale::default()), "3 + 4");
+        let comma = Locale {
+            decimal_comma: true,
+            grouping: true,
+        };
+        assert_eq!(normalize_paste("1.234,5 €", comma), "1234.5");
+
+        let mut calc = Calculator::default();
+        calc.paste("max(1;2*(3");
+        assert_eq!(calc.display, "max(1,2×(3");
+        assert_eq!(calc.get_open_parens(), 2);
+
+        calc.clear();
+        calc.paste("(1+2)*(3");
+        assert_eq!(calc.get_open_parens(), 1);
+        assert_eq!(calc.evaluate(), Ok(()));
+        assert_eq!(calc.display, "9");
+        assert_eq!(calc.copy_text(false), Some("9".to_string()));
+        assert_eq!(calc.copy_text(true), Some("(1+2)×(3 = 9".to_string()));
+        // An operator continues from the result, a number replaces it
+        calc.paste("*2");
+        assert_eq!(calc.display, "9×2");
+        calc.result_shown = true;
+        calc.paste("5");
+        assert_eq!(calc.display, "5");
+
+        // A pasted percent follows the percent mode like the % key
+        let pasted = |text: &str, mode: PercentMode| {
+            let mut calc = Calculator {
+                percent_mode: mode,
+                ..Default::default()
+            };
+            calc.paste(text);
+            assert_eq!(calc.display, text.replace('*', "×"));
+            calc.calculate()
+        };
+        assert_eq!(pasted("10%", PercentMode::Plain), Ok(0.1));
+        assert_eq!(pasted("200*15%", PercentMode::Plain), Ok(30.0));
+        assert_eq!(pasted("200+10%", PercentMode::Plain), Ok(200.1));
+        assert_eq!(pasted("200+10%", PercentMode::Business), Ok(220.0));
+        assert_eq!(pasted("200*15%", PercentMode::Business), Ok(30.0));
+    }
+
+    #[test]
+    fn test_cursor_editing() {
+        let mut calc = Calculator::default();
+        for digit in ["1", "2", "3"] {
+            calc.input_digit(digit);
+        }
+        calc.move_cursor(-1, false);
+        calc.input_operator("+");
+        assert_eq!(calc.display, "12+3");
+        assert_eq!(calc.cursor(), 3);
+
+        // Functions and parentheses inserted before the cursor
+        calc.set_cursor(0, false);
+        calc.input_function("sqrt");
+        assert_eq!(calc.expression, "sqrt(12+3");
+        assert_eq!(calc.get_open_parens(), 1);
+        calc.set_cursor(7, false);
+        calc.input_close_paren();
+        assert_eq!(calc.expression, "sqrt(12)+3");
+        assert_eq!(calc.get_open_parens(), 0);
+
+        // Backspace deletes before the cursor, Delete after it
+        calc.move_cursor(-1, false);
+        calc.clear_entry();
+        calc.delete_forward();
+        assert_eq!(calc.expression, "sqrt(1+3");
+        assert_eq!(calc.cursor(), 6);
+
+        // A selection is replaced by input and removed by backspace
+        calc.move_cursor(-1, true);
+        assert_eq!(calc.selection(), Some((5, 6)));
+        assert_eq!(calc.copy_text(false), Some("1".to_string()));
+        calc.input_digit("9");
+        assert_eq!(calc.expression, "sqrt(9+3");
+        calc.select_all();
+        calc.clear_entry();
+        assert_eq!(calc.display, "0");
+
+        // Combining marks stay with their base
+        calc.input_constant("x̂(2)");
+        calc.set_cursor(1, false);
+        assert_eq!(calc.cursor(), 2);
+        calc.move_cursor(-1, false);
+        assert_eq!(calc.cursor(), 0);
+
+        // Moving into a result makes it editable
+        calc.clear();
+        calc.paste("6/4");
+        assert_eq!(calc.evaluate(), Ok(()));
+        calc.set_cursor(0, false);
+        calc.input_operator("−");
+        assert_eq!(calc.display, "−1.5");
+        assert!(!calc.result_shown());
+
+        // The typed form is edited, not its rewrite for evaluation
+        calc = Calculator {
+            percent_mode: PercentMode::Business,
+            ..Default::default()
+        };
+        for digit in ["2", "0", "0"] {
+            calc.input_digit(digit);
+        }
+        calc.input_operator("+");
+        calc.input_digit("5");
+        calc.input_square();
+        calc.input_percent();
+        calc.move_cursor(-2, false);
+        calc.clear_entry();
+        calc.input_digit("4");
+        assert_eq!(calc.display, "200+4²%");
+        assert_eq!(calc.expression, calc.display);
+        assert_eq!(calc.calculate(), Ok(232.0));
+    }
+
+    #[test]
+    fn test_undo() {
+        let mut calc = Calculator::default();
+        calc.input_digit("2");
+        calc.input_operator("+");
+        calc.input_digit("3");
+        assert_eq!(calc.evaluate(), Ok(()));
+        assert_eq!(calc.display, "5");
+
+        calc.undo();
+        assert_eq!(calc.display, "2+3");
+        assert!(!calc.result_shown());
+        calc.undo();
+        assert_eq!(calc.display, "2+");
+        calc.redo();
+        calc.redo();
+        assert_eq!(calc.display, "5");
+        assert_eq!(calc.last_result, Some(5.0));
+        assert!(calc.result_shown());
+
+        // Cursor movement is not an undo step; new input drops the redo steps
+        calc.clear();
+        calc.move_cursor(-1, false);
+        calc.undo();
+        assert_eq!(calc.display, "5");
+        calc.input_digit("7");
+        calc.redo();
+        assert_eq!(calc.display, "7");
+    }
+
+    #[test]
+    fn test_typeset_input() {
+        let mut calc = Calculator::default();
+        calc.paste("sqrt(2.50+x^10");
+        let input = calc.typeset_input().unwrap();
+        assert_eq!(input.expr.to_string(), "sqrt(2.5+x^10)");
+        assert_eq!(input.numbers, ["2.50", "10"]);
+        assert_eq!(input.rest, "");
+
+        // Trailing text that does not parse yet is kept apart
+        calc.input_operator("×");
+        calc.input_function("sin");
+        let input = calc.typeset_input().unwrap();
+        assert_eq!(input.expr.to_string(), "sqrt(2.5+x^10·sin)");
+        assert_eq!(input.rest, "(");
+
+        // Input that does not parse before its last few characters is shown
+        // as typed
+        calc.clear();
+        calc.paste("2×(3+4))+1+2+3+4+5");
+        assert!(calc.typeset_input().is_none());
+
+        // Percentages, units and dates are shown as typed
+        for text in ["200+10%", "50 EUR×2", "2026-03-01+7 days"] {
+            calc.clear();
+            calc.paste(text);
+            assert!(calc.typeset_input().is_none(), "{}", text);
+        }
+        assert_eq!(calc.evaluate(), Ok(()));
+        assert!(calc.typeset_input().is_none());
+    }
+
+    #[test]
+    fn test_preview() {
+        let mut calc = Calculator::default();
+        calc.input_digit("5");
+        // A plain number has no preview
+        assert_eq!(calc.preview(), None);
+        calc.input_operator("×");
+        assert_eq!(calc.preview(), None);
+        calc.input_open_paren();
+        calc.input_digit("2");
+        calc.input_operator("+");
+        calc.input_digit("1");
+        // Open parentheses are closed for the preview
+        assert_eq!(calc.preview(), Some("15".to_string()));
+        assert!(calc.history.is_empty());
+        assert!(!calc.result_shown());
+
+        calc.set_display_format(DisplayFormat::Fix(2));
+        assert_eq!(calc.preview(), Some("15.00".to_string()));
+        calc.input_operator("÷");
+        calc.input_digit("0");
+        assert_eq!(calc.preview(), None);
+
+        // No preview of integrals, sums and products
+        for pasted in ["∫(x,x,0,1)", "2+Σ(k,k,1,10", "sum(1,2)+Π(k,k,1,5)"] {
+            calc.clear();
+            calc.paste(pasted);
+            assert_eq!(calc.preview(), None, "{pasted}");
+        }
+        calc.clear();
+        calc.paste("sum(1,2)×2");
+        assert_eq!(calc.preview(), Some("6.00".to_string()));
+
+        // No preview once the result is shown
+        calc.clear();
+        calc.paste("2+3");
+        assert_eq!(calc.evaluate(), Ok(()));
+        assert_eq!(calc.preview(), None);
+    }
+
+    #[test]
+    fn test_dates() {
+        let mut calc = Calculator::default();
+        calc.input_constant("2026-03-01");
+        calc.input_operator("+");
+        calc.input_digit("9");
+        calc.input_digit("0");
+        calc.input_unit("days");
+        assert_eq!(calc.expression, "2026-03-01+90 days");
+        assert_eq!(calc.evaluate(), Ok(()));
+        assert_eq!(calc.display, "2026-05-30");
+        assert_eq!(calc.rates_as_of(), None);
+
+        // The date result is a date literal again
+        calc.input_operator("−");
+        calc.input_constant("2026-01-01");
+        assert_eq!(calc.evaluate(), Ok(()));
+        assert_eq!(calc.display, "149 d");
+
+        calc.expression = "1:45:30 + 0:20:15".to_string();
+        assert_eq!(calc.evaluate(), Ok(()));
+        assert_eq!(calc.display, "2:05:45");
+        calc.expression = "2026-12-25 − today".to_string();
+        assert_eq!(calc.evaluate(), Ok(()));
+        assert!(calc.display.ends_with(" d"));
+    }
+
+    #[test]
+    fn test_currency_conversion() {
+        let mut calc = Calculator {
+            rates: RateTable::parse("as-of 2026-01-02\nbase EUR\nEUR 1\nUSD 1.25\n").unwrap(),
+            ..Default::default()
+        };
+        for digit in ["1", "0", "0"] {
+            calc.input_digit(digit);
+        }
+        calc.input_unit("USD");
+        calc.input_conversion();
+        calc.input_unit("EUR");
+        assert_eq!(calc.display, "100 USD to EUR");
+        assert_eq!(calc.evaluate(), Ok(()));
+        assert_eq!(calc.display, "80 EUR");
+        assert_eq!(calc.rates_as_of(), Some("2026-01-02"));
+
+        // Currency per hour times hours
+        calc.clear();
+        calc.input_digit("5");
+        calc.input_digit("0");
+        calc.input_unit("EUR");
+        calc.input_operator("÷");
+        calc.input_unit("h");
+        calc.input_operator("×");
+        calc.input_digit("8");
+        calc.input_unit("h");
+        assert_eq!(calc.display, "50 EUR÷h×8 h");
+        assert_eq!(calc.evaluate(), Ok(()));
+        assert_eq!(calc.display, "400 EUR");
+
+        // The result can be converted further
+        calc.input_conversion();
+        calc.input_unit("USD");
+        assert_eq!(calc.evaluate(), Ok(()));
+        assert_eq!(calc.display, "500 USD");
+
+        calc.input_conversion();
+        assert_eq!(calc.evaluate(), Err("Incomplete".to_string()));
+        calc.expression = "2 h to EUR".to_string();
+        assert_eq!(calc.evaluate(), Err("Incompatible units".to_string()));
+        calc.expression = "2+3".to_string();
+        assert_eq!(calc.evaluate(), Ok(()));
+        assert_eq!(calc.rates_as_of(), None);
+
+        // Typed names become units and `to`
+        calc.clear();
+        for digit in ["1", "0", "0"] {
+            calc.input_digit(digit);
+        }
+        for name in ["USD", "to", "EUR"] {
+            calc.input_name(name);
+        }
+        assert_eq!(calc.display, "100 USD to EUR");
+        assert_eq!(calc.evaluate(), Ok(()));
+        assert_eq!(calc.display, "80 EUR");
+        calc.clear();
+        calc.input_digit("5");
+        calc.input_name("days");
+        calc.input_name("to");
+        calc.input_name("hms");
+        assert_eq!(calc.display, "5 days to hms");
+        assert_eq!(calc.evaluate(), Ok(()));
+        assert_eq!(calc.display, "120:00:00");
+
+        // Currency per hour times hours, typed
+        calc.clear();
+        calc.input_digit("5");
+        calc.input_digit("0");
+        calc.input_name("EUR");
+        calc.input_operator("÷");
+        calc.input_name("h");
+        calc.input_operator("×");
+        calc.input_digit("8");
+        calc.input_name("h");
+        assert_eq!(calc.display, "50 EUR÷h×8 h");
+        assert_eq!(calc.evaluate(), Ok(()));
+        assert_eq!(calc.display, "400 EUR");
+    }
+
+    #[test]
+    fn test_curve_parameters() {
+        let calc = Calculator {
+            angle_mode: AngleMode::Degrees,
+            ..Default::default()
+        };
+        // θ follows the angle mode like any other angle
+        let r = calc.bind("1+cos(θ)", "theta").unwrap();
+        assert!((r(180.0)).abs() < 1e-12);
+        let r = calc.bind("2θ", "theta").unwrap();
+        assert_eq!(r(3.0), 6.0);
+
+        assert!((calc.evaluate_text("2π").unwrap() - 2.0 * std::f64::consts::PI).abs() < 1e-12);
+        assert!(calc.evaluate_text("2+").is_err());
+    }
 }
@@ -0,0 +1,296 @@
+use crate::{distributions, finance};
+
+/// Most completions offered at once
+pub const MAX_COMPLETIONS: usize = 8;
+
+/// A function, constant, named value or unit offered while typing letters
+#[derive(Clone, Debug, PartialEq)]
+pub struct Completion {
+    /// Name as typed, e.g. `pi` or `integral`
+    pub name: &'static str,
+    /// Text inserted into the expression, e.g. `π` or `∫`
+    pub insert: &'static str,
+    /// Parameters of a function; None for constants and named values
+    pub params: Option<String>,
+    pub description: String,
+}
+
+impl Completion {
+    fn function(name: &'static str, insert: &'static str, params: &str, description: &str) -> Self {
+        Self {
+            name,
+            insert,
+            params: Some(params.to_string()),
+            description: description.to_string(),
+        }
+    }
+
+    fn value(name: &'static str, insert: &'static str, description: &str) -> Self {
+        Self {
+            name,
+            insert,
+            params: None,
+            description: description.to_string(),
+        }
+    }
+
+    /// Whether accepting inserts a function call rather than a value
+    pub fn is_function(&self) -> bool {
+        self.params.is_some()
+    }
+
+    /// Signature as shown in the popup, e.g. `atan2(y, x)` or `π`
+    pub fn signature(&self) -> String {
+        match &self.params {
+            Some(params) => format!("{}({})", self.insert, params),
+            None => self.insert.to_string(),
+        }
+    }
+}
+
+/// Built-in functions: name as typed, text inserted, parameters, description
+const BUILTINS: [(&str, &str, &str, &str); 48] = [
+    ("sqrt", "sqrt", "x", "Square root"),
+    ("exp", "exp", "x", "e to the power x"),
+    ("ln", "ln", "x", "Natural logarithm"),
+    ("log10", "log10", "x", "Common logarithm"),
+    ("abs", "abs", "x", "Absolute value"),
+    ("sin", "sin", "x", "Sine"),
+    ("cos", "cos", "x", "Cosine"),
+    ("tan", "tan", "x", "Tangent"),
+    ("asin", "asin", "x", "Inverse sine"),
+    ("acos", "acos", "x", "Inverse cosine"),
+    ("atan", "atan", "x", "Inverse tangent"),
+    ("atan2", "atan2", "y, x", "Angle of the point (x, y)"),
+    ("sinh", "sinh", "x", "Hyperbolic sine"),
+    ("cosh", "cosh", "x", "Hyperbolic cosine"),
+    ("tanh", "tanh", "x", "Hyperbolic tangent"),
+    ("asinh", "asinh", "x", "Inverse hyperbolic sine"),
+    ("acosh", "acosh", "x", "Inverse hyperbolic cosine"),
+    ("atanh", "atanh", "x", "Inverse hyperbolic tangent"),
+    ("floor", "floor", "x", "Round down to an integer"),
+    ("ceil", "ceil", "x", "Round up to an integer"),
+    ("trunc", "trunc", "x", "Integer part"),
+    ("frac", "frac", "x", "Fractional part"),
+    ("signum", "signum", "x", "Sign: −1, 0 or 1"),
+    ("round", "round", "x, n", "Round to n decimals"),
+    ("pchange", "Δ%", "old, new", "Percentage change"),
+    ("npv", "npv", "rate, cash flows…", "Net present value"),
+    ("irr", "irr", "cash flows…", "Internal rate of return"),
+    ("deriv", "d/dx", "f, x, a", "Derivative of f at x = a"),
+    ("diff", "diff", "f, x", "Symbolic derivative of f"),
+    ("integral", "∫", "f, x, a, b", "Integral of f from a to b"),
+    ("summation", "Σ", "f, k, m, n", "Sum of f for k from m to n"),
+    (
+        "product",
+        "Π",
+        "f, k, m, n",
+        "Product of f for k from m to n",
+    ),
+    ("yhat", "ŷ", "x", "Regression estimate of y"),
+    ("xhat", "x̂", "y", "Regression estimate of x"),
+    ("det", "det", "A", "Determinant"),
+    ("inv", "inv", "A", "Inverse matrix"),
+    ("transpose", "transpose", "A", "Transposed matrix"),
+    ("rank", "rank", "A", "Rank of a matrix"),
+    ("dot", "dot", "u, v", "Dot product"),
+    ("cross", "cross", "u, v", "Cross product"),
+    ("norm", "norm", "v", "Length of a vector"),
+    ("eig", "eig", "A", "Eigenvalues"),
+    ("date", "date", "y, m, d, [h, min, s]", "Date and time"),
+    (
+        "hms",
+        "hms",
+        "h, min, [s]",
+        "Duration in hours, minutes and seconds",
+    ),
+    (
+        "workdays",
+        "workdays",
+        "start, end",
+        "Business days between two dates",
+    ),
+    (
+        "workday",
+        "workday",
+        "start, n",
+        "Date n business days later",
+    ),
+    ("unix", "unix", "date", "Unix timestamp of a date"),
+    (
+        "fromunix",
+        "fromunix",
+        "seconds",
+        "Date of a Unix timestamp",
+    ),
+];
+
+/// Summary functions of a list of values, also named values of the data set
+const SUMMARIES: [(&str, &str); 14] = [
+    ("count", "Number of values"),
+    ("sum", "Sum"),
+    ("mean", "Mean"),
+    ("median", "Median"),
+    ("mode", "Most frequent value"),
+    ("var", "Sample variance"),
+    ("pvar", "Population variance"),
+    ("stdev", "Sample standard deviation"),
+    ("pstdev", "Population standard deviation"),
+    ("min", "Smallest value"),
+    ("max", "Largest value"),
+    ("q1", "First quartile"),
+    ("q3", "Third quartile"),
+    ("iqr", "Interquartile range"),
+];
+
+/// Constants: name as typed, text inserted, description
+const CONSTANTS: [(&str, &str, &str); 6] = [
+    ("pi", "π", "Ratio of circumference to diameter"),
+    ("e", "e", "Euler's number"),
+    ("x", "x", "Variable of plots, solving and calculus"),
+    ("t", "t", "Parameter of parametric curves"),
+    ("theta", "θ", "Angle of polar curves"),
+    ("today", "today", "Today's date"),
+];
+
+/// Time units and `to`, typed after a number or value: name, description
+const UNITS: [(&str, &str); 6] = [
+    ("s", "Seconds"),
+    ("h", "Hours"),
+    ("d", "Days"),
+    ("days", "Days"),
+    ("wk", "Weeks"),
+    ("to", "Convert to the unit after it"),
+];
+
+/// Everything that can be completed
+fn catalog() -> Vec<Completion> {
+    let mut all: Vec<Completion> = BUILTINS
+        .iter()
+        .map(|&(name, insert, params, description)| {
+            Completion::function(name, insert, params, description)
+        })
+        .collect();
+    all.extend(
+        SUMMARIES
+            .iter()
+            .map(|&(name, description)| Completion::function(name, name, "values…", description)),
+    );
+    all.extend(
+        distributions::FUNCTIONS
+            .iter()
+            .chain(finance::FUNCTIONS.iter())
+            .map(|f| Completion::function(f.name, f.name, &f.params.join(", "), f.description)),
+    );
+    all.extend(
+        CONSTANTS
+            .iter()
+            .map(|&(name, insert, description)| Completion::value(name, insert, description)),
+    );
+    all.extend(SUMMARIES.iter().map(|&(name, description)| {
+        Completion::value(name, name, &format!("{description} of the data set"))
+    }));
+    all.extend(
+        UNITS
+            .iter()
+            .map(|&(name, description)| Completion::value(name, name, description)),
+    );
+    all
+}
+
+/// Completions whose name starts with `prefix`, ignoring case: an exact
+/// match first, then shorter names before longer ones
+pub fn complete(prefix: &str) -> Vec<Completion> {
+    if prefix.is_empty() {
+        return Vec::new();
+    }
+    let prefix = prefix.to_lowercase();
+    let mut matches: Vec<Completion> = catalog()
+        .into_iter()
+        .filter(|c| c.name.starts_with(&prefix))
+        .collect();
+    matches.sort_by_key(|c| (c.name != prefix, c.name.len(), c.name, !c.is_function()));
+    matches.truncate(MAX_COMPLETIONS);
+    matches
+}
+
+/// Whether typing `c` after `prefix` keeps completing a name
+pub fn continues(prefix: &str, c: char) -> bool {
+    c.is_ascii_alphabetic() || (!prefix.is_empty() && !complete(&format!("{prefix}{c}")).is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calculator::Calculator;
+    use crate::{stats, units};
+
+    fn names(prefix: &str) -> Vec<&'static str> {
+        complete(prefix).iter().map(|c| c.name).collect()
+    }
+
+    #[test]
+    fn test_complete() {
+        assert_eq!(names("sin"), ["sin", "sinh"]);
+        assert_eq!(names("SI"), ["sin", "sinh", "signum"]);
+        assert_eq!(names("at"), ["atan", "atan2", "atanh"]);
+        assert!(names("").is_empty());
+        assert!(names("zz").is_empty());
+        assert_eq!(complete("x")[0].insert, "x");
+        assert_eq!(complete("x")[1].insert, "x̂");
+        assert_eq!(complete("theta")[0].insert, "θ");
+        // A unit before longer names that start with it
+        assert_eq!(names("h"), ["h", "hms"]);
+        assert_eq!(names("to"), ["to", "today"]);
+        assert!(names("norm").starts_with(&["norm", "normcdf"]));
+        assert_eq!(names("q"), ["q1", "q1", "q3", "q3"]);
+        assert!(complete("a").len() <= MAX_COMPLETIONS);
+
+        // Functions before the data set value of the same name
+        let mean = complete("mean");
+        assert!(mean[0].is_function());
+        assert_eq!(mean[1].signature(), "mean");
+        assert_eq!(mean[1].description, "Mean of the data set");
+    }
+
+    #[test]
+    fn test_signature() {
+        let pi = &complete("pi")[0];
+        assert_eq!((pi.insert, pi.signature()), ("π", "π".to_string()));
+        assert_eq!(complete("integral")[0].signature(), "∫(f, x, a, b)");
+        assert_eq!(complete("normcdf")[0].signature(), "normcdf(x, μ, σ)");
+        assert_eq!(complete("atan2")[0].signature(), "atan2(y, x)");
+    }
+
+    #[test]
+    fn test_continues() {
+        assert!(continues("", 's'));
+        assert!(!continues("", '1'));
+        assert!(continues("log", '1'));
+        assert!(continues("atan", '2'));
+        assert!(!continues("sin", '2'));
+    }
+
+    #[test]
+    fn test_catalog() {
+        let all = catalog();
+        for name in stats::SUMMARY_NAMES {
+            assert_eq!(all.iter().filter(|c| c.name == name).count(), 2, "{name}");
+        }
+        for name in units::DATE_FUNCTIONS {
+            assert!(all.iter().any(|c| c.name == name), "{name}");
+        }
+        for (name, _) in units::TIME_UNITS {
+            assert!(all.iter().any(|c| c.name == name), "{name}");
+        }
+        assert!(distributions::FUNCTIONS
+            .iter()
+            .all(|f| all.iter().any(|c| c.name == f.name)));
+        for name in Calculator::default().context().function_names() {
+            assert!(
+                all.iter().any(|c| c.name == name && c.is_function()),
+                "{name}"
+            );
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use std::path::PathBuf;
+
+/// Rate table shipped with the calculator, used until a rate file is saved
+const DEFAULT_RATES: &str = include_str!("../assets/rates.txt");
+/// Name of the rate file in the configuration directory
+const FILE_NAME: &str = "rates.txt";
+
+/// Currency rates as units of each currency per 1 unit of the base
+/// currency, with the date they are valid for.
+///
+/// The text format has one `CODE rate` pair per line plus `as-of` and
+/// `base` lines; lines starting with `#` are comments.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RateTable {
+    pub as_of: String,
+    pub base: String,
+    pub rates: Vec<(String, f64)>,
+}
+
+impl Default for RateTable {
+    fn default() -> Self {
+        Self::parse(DEFAULT_RATES).expect("built-in rate table")
+    }
+}
+
+impl RateTable {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut table = Self {
+            as_of: String::new(),
+            base: String::new(),
+            rates: Vec::new(),
+        };
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = || format!("Invalid rate on line {}", number + 1);
+            let (key, value) = line.split_once(char::is_whitespace).ok_or_else(invalid)?;
+            let value = value.trim();
+            match key {
+                "as-of" => table.as_of = value.to_string(),
+                "base" if is_code(value) => table.base = value.to_string(),
+                code if is_code(code) => {
+                    let rate = value.parse().map_err(|_| invalid())?;
+                    table.set(code, rate)?;
+                }
+                _ => return Err(invalid()),
+            }
+        }
+        if table.rate(&table.base) != Some(1.0) {
+            return Err("Base currency needs the rate 1".to_string());
+        }
+        Ok(table)
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "# Currency rates: units of each currency per 1 {}\nas-of {}\nbase {}\n",
+            self.base, self.as_of, self.base
+        );
+        for (code, rate) in &self.rates {
+            text.push_str(&format!("{} {}\n", code, rate));
+        }
+        text
+    }
+
+    /// Units of `code` per 1 unit of the base currency
+    pub fn rate(&self, code: &str) -> Option<f64> {
+        self.rates.iter().find(|(c, _)| c == code).map(|&(_, r)| r)
+    }
+
+    /// Add a currency or change its rate
+    pub fn set(&mut self, code: &str, rate: f64) -> Result<(), String> {
+        if !is_code(code) {
+            return Err("Codes are 3 capital letters".to_string());
+        }
+        if !rate.is_finite() || rate <= 0.0 {
+            return Err("Rates must be positive".to_string());
+        }
+        match self.rates.iter_mut().find(|(c, _)| c == code) {
+            Some(entry) => entry.1 = rate,
+            None => self.rates.push((code.to_string(), rate)),
+        }
+        Ok(())
+    }
+
+    pub fn remove(&mut self, code: &str) {
+        if code != self.base {
+            self.rates.retain(|(c, _)| c != code);
+        }
+    }
+
+    /// Rates from the rate file, or the built-in table if there is none
+    pub fn load() -> Result<Self, String> {
+        match path().map(std::fs::read_to_string) {
+            Some(Ok(text)) => Self::parse(&text),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    /// Write the rates to the rate file, returning its path
+    pub fn save(&self) -> Result<PathBuf, String> {
+        let path = path().ok_or("No configuration directory")?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        std::fs::write(&path, self.to_text()).map_err(|e| e.to_string())?;
+        Ok(path)
+    }
+}
+
+/// Whether `text` looks like a currency code such as `EUR`
+pub fn is_code(text: &str) -> bool {
+    text.len() == 3 && text.chars().all(|c| c.is_ascii_uppercase())
+}
+
+/// Location of the rate file in the user's configuration directory
+pub fn path() -> Option<PathBuf> {
+    let var = |name| std::env::var_os(name).map(PathBuf::from);
+    let dir = if cfg!(target_os = "windows") {
+        var("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        var("HOME").map(|home| home.join("Library/Application Support"))
+    } else {
+        var("XDG_CONFIG_HOME").or_else(|| var("HOME").map(|home| home.join(".config")))
+    }?;
+    Some(dir.join("rust-calc").join(FILE_NAME))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let table =
+            RateTable::parse("# rates\nas-of 2026-01-02\nbase EUR\nEUR 1\nUSD 1.25\n").unwrap();
+        assert_eq!(table.as_of, "2026-01-02");
+        assert_eq!(table.rate("USD"), Some(1.25));
+        assert_eq!(table.rate("GBP"), None);
+        assert_eq!(RateTable::parse(&table.to_text()), Ok(table));
+
+        assert!(RateTable::parse("base EUR\nEUR 1\nUSD x\n").is_err());
+        assert!(RateTable::parse("base EUR\nEUR 1\nUsd 2\n").is_err());
+        // The base currency must be in the table
+        assert!(RateTable::parse("base EUR\nUSD 2\n").is_err());
+        assert!(RateTable::default().rate("USD").is_some());
+    }
+
+    #[test]
+    fn test_edit() {
+        let mut table = RateTable::default();
+        table.set("XAU", 0.0003).unwrap();
+        assert_eq!(table.rate("XAU"), Some(0.0003));
+        assert!(table.set("XAU", -1.0).is_err());
+        assert!(table.set("gold", 1.0).is_err());
+        table.remove("XAU");
+        table.remove("EUR");
+        assert_eq!(table.rate("XAU"), None);
+        assert_eq!(table.rate("EUR"), Some(1.0));
+    }
+}
@@ -0,0 +1,343 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const SECONDS_PER_DAY: f64 = 86400.0;
+
+/// Days since 1970-01-01 of 0001-01-01, and of 10000-01-01 just after the
+/// supported years
+const FIRST_DAY: i64 = -719_162;
+const END_DAY: i64 = 2_932_897;
+
+/// Number of days in the years 1 to 9999
+pub const DAYS_IN_RANGE: i64 = END_DAY - FIRST_DAY;
+
+/// Days since 1970-01-01 of a date in the proleptic Gregorian calendar
+pub fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Year, month and day of a number of days since 1970-01-01
+pub fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Unix time of a date and time of day (UTC), or None if it is not a valid
+/// date between the years 1 and 9999
+pub fn timestamp(
+    year: f64,
+    month: f64,
+    day: f64,
+    hour: f64,
+    minute: f64,
+    second: f64,
+) -> Option<f64> {
+    let whole = [year, month, day, hour, minute];
+    if whole.iter().any(|v| v.fract() != 0.0) || !second.is_finite() {
+        return None;
+    }
+    let (y, m, d) = (year as i64, month as i64, day as i64);
+    if !(1..=9999).contains(&y) || !(1..=12).contains(&m) || d < 1 || d > days_in_month(y, m) {
+        return None;
+    }
+    if !(0.0..24.0).contains(&hour)
+        || !(0.0..60.0).contains(&minute)
+        || !(0.0..60.0).contains(&second)
+    {
+        return None;
+    }
+    let days = days_from_civil(y, m, d) as f64;
+    Some(days * SECONDS_PER_DAY + hour * 3600.0 + minute * 60.0 + second)
+}
+
+/// Whether a Unix time falls in the years 1 to 9999
+pub fn in_range(seconds: f64) -> bool {
+    (FIRST_DAY as f64 * SECONDS_PER_DAY..END_DAY as f64 * SECONDS_PER_DAY).contains(&seconds)
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    days_from_civil(year + month / 12, month % 12 + 1, 1) - days_from_civil(year, month, 1)
+}
+
+/// ISO date of a Unix time, with the time of day if it is not midnight,
+/// e.g. `2026-03-01` or `2026-03-01T12:30:00`
+pub fn format_timestamp(seconds: f64) -> String {
+    let seconds = seconds.round();
+    let days = (seconds / SECONDS_PER_DAY).floor();
+    let (year, month, day) = civil_from_days(days as i64);
+    let date = format!("{:04}-{:02}-{:02}", year, month, day);
+    let time = (seconds - days * SECONDS_PER_DAY) as i64;
+    if time == 0 {
+        date
+    } else {
+        format!(
+            "{}T{:02}:{:02}:{:02}",
+            date,
+            time / 3600,
+            time / 60 % 60,
+            time % 60
+        )
+    }
+}
+
+/// Duration as h:mm:ss, e.g. `1:45:30` or `−0:20:15.5`
+pub fn format_hms(seconds: f64) -> String {
+    let sign = if seconds < 0.0 { "−" } else { "" };
+    // Round to milliseconds so that 59.9999 does not show as 60
+    let millis = (seconds.abs() * 1000.0).round() as i64;
+    let (whole, fraction) = (millis / 1000, millis % 1000);
+    let mut text = format!(
+        "{}{}:{:02}:{:02}",
+        sign,
+        whole / 3600,
+        whole / 60 % 60,
+        whole % 60
+    );
+    if fraction != 0 {
+        text.push_str(format!(".{:03}", fraction).trim_end_matches('0'));
+    }
+    text
+}
+
+/// Days since 1970-01-01 of the current date (UTC)
+pub fn today() -> i64 {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    (seconds / 86400) as i64
+}
+
+/// Whether a day (counted from 1970-01-01, a Thursday) is a Saturday or
+/// Sunday
+fn is_weekend(days: i64) -> bool {
+    (days + 3).rem_euclid(7) >= 5
+}
+
+/// Weekdays from `start` to `end`, both included; negative if `end` comes
+/// first
+pub fn workdays(start: i64, end: i64) -> i64 {
+    if end < start {
+        return -workdays(end, start);
+    }
+    // Whole weeks have five weekdays each; count the rest one by one
+    let weeks = (end - start + 1) / 7;
+    let rest = (start + weeks * 7..=end)
+        .filter(|&d| !is_weekend(d))
+        .count() as i64;
+    weeks * 5 + rest
+}
+
+/// The day `n` weekdays after `start` (before it if `n` is negative)
+pub fn add_workdays(start: i64, n: i64) -> i64 {
+    let step = n.signum();
+    let mut day = start;
+    let mut left = n.abs();
+    // Skip whole weeks first
+    day += (left - 1).max(0) / 5 * 7 * step;
+    left -= (left - 1).max(0) / 5 * 5;
+    while left > 0 {
+        day += step;
+        if !is_weekend(day) {
+            left -= 1;
+        }
+    }
+    day
+}
+
+/// Rewrite date literals (`2026-03-01`, `2026-03-01T12:30:00`) as
+/// `date(…)` calls and durations (`1:45:30`, `0:20`) as `hms(…)` calls.
+/// Dates use ASCII hyphens; `−` stays a minus sign.
+pub fn replace_literals(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut result = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let starts_number = chars[i].is_ascii_digit()
+            && (i == 0 || !(chars[i - 1].is_ascii_alphanumeric() || chars[i - 1] == '.'));
+        if starts_number {
+            if let Some((call, len)) =
+                date_literal(&chars[i..]).or_else(|| hms_literal(&chars[i..]))
+            {
+                result.push_str(&call);
+                i += len;
+                continue;
+            }
+        }
+        result.push(chars[i]);
+        i += 1;
+    }
+    result
+}
+
+/// Whether the whole text is a date literal such as `2026-03-01T08:30`
+pub fn is_date(text: &str) -> bool {
+    let chars: Vec<char> = text.chars().collect();
+    date_literal(&chars).is_some_and(|(_, len)| len == chars.len())
+}
+
+/// Whether the whole text is a duration literal such as `1:45:30`
+pub fn is_duration(text: &str) -> bool {
+    let chars: Vec<char> = text.chars().collect();
+    hms_literal(&chars).is_some_and(|(_, len)| len == chars.len())
+}
+
+/// Length of a date or duration literal at the start of `chars`
+pub fn literal_len(chars: &[char]) -> Option<usize> {
+    date_literal(chars)
+        .or_else(|| hms_literal(chars))
+        .map(|(_, len)| len)
+}
+
+/// Digits at the start of `chars`, with at most `max` of them
+fn digits(chars: &[char], max: usize) -> usize {
+    chars
+        .iter()
+        .take(max)
+        .take_while(|c| c.is_ascii_digit())
+        .count()
+}
+
+/// Fields of a literal: runs of `lengths` digits separated by `separators`.
+/// Returns the fields and the length of the literal.
+fn fields(
+    chars: &[char],
+    separators: &[char],
+    lengths: &[(usize, usize)],
+) -> Option<(Vec<String>, usize)> {
+    let mut pos = 0;
+    let mut values = Vec::new();
+    for (k, &(min, max)) in lengths.iter().enumerate() {
+        if k > 0 {
+            if chars.get(pos) != Some(&separators[k - 1]) {
+                return None;
+            }
+            pos += 1;
+        }
+        let n = digits(&chars[pos..], max);
+        if n < min {
+            return None;
+        }
+        values.push(chars[pos..pos + n].iter().collect());
+        pos += n;
+    }
+    Some((values, pos))
+}
+
+fn date_literal(chars: &[char]) -> Option<(String, usize)> {
+    let (mut values, mut len) = fields(chars, &['-', '-'], &[(4, 4), (1, 2), (1, 2)])?;
+    if chars.get(len) == Some(&'T') {
+        if let Some((time, time_len)) =
+            fields(&chars[len + 1..], &[':', ':'], &[(1, 2), (2, 2), (2, 2)])
+                .or_else(|| fields(&chars[len + 1..], &[':'], &[(1, 2), (2, 2)]))
+        {
+            values.extend(time);
+            len += 1 + time_len;
+        }
+    }
+    Some((format!("date({})", values.join(",")), len))
+}
+
+fn hms_literal(chars: &[char]) -> Option<(String, usize)> {
+    let (mut values, mut len) = fields(chars, &[':', ':'], &[(1, 6), (2, 2), (2, 2)])
+        .or_else(|| fields(chars, &[':'], &[(1, 6), (2, 2)]))?;
+    // Fraction of a second
+    if values.len() == 3 && chars.get(len) == Some(&'.') {
+        let n = digits(&chars[len + 1..], 9);
+        let fraction: String = chars[len + 1..len + 1 + n].iter().collect();
+        values[2] = format!("{}.{}", values[2], fraction);
+        len += 1 + n;
+    }
+    Some((format!("hms({})", values.join(",")), len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_calendar() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 3, 1), 11017);
+        assert_eq!(civil_from_days(11017), (2000, 3, 1));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        assert_eq!(
+            timestamp(2026.0, 3.0, 1.0, 0.0, 0.0, 0.0),
+            Some(1772323200.0)
+        );
+        assert!(timestamp(2024.0, 2.0, 29.0, 0.0, 0.0, 0.0).is_some());
+        assert_eq!(timestamp(2026.0, 2.0, 29.0, 0.0, 0.0, 0.0), None);
+        assert_eq!(timestamp(2026.0, 13.0, 1.0, 0.0, 0.0, 0.0), None);
+        assert_eq!(days_from_civil(1, 1, 1), FIRST_DAY);
+        assert_eq!(days_from_civil(10000, 1, 1), END_DAY);
+        assert!(in_range(FIRST_DAY as f64 * SECONDS_PER_DAY));
+        assert!(!in_range(END_DAY as f64 * SECONDS_PER_DAY));
+        assert!(!in_range(f64::NAN));
+        assert_eq!(format_timestamp(1772323200.0), "2026-03-01");
+        assert_eq!(
+            format_timestamp(1772323200.0 + 45000.0),
+            "2026-03-01T12:30:00"
+        );
+    }
+
+    #[test]
+    fn test_hms() {
+        assert_eq!(format_hms(7545.0), "2:05:45");
+        assert_eq!(format_hms(-1215.5), "−0:20:15.5");
+        assert_eq!(format_hms(59.9999), "0:01:00");
+    }
+
+    #[test]
+    fn test_workdays() {
+        let monday = days_from_civil(2026, 3, 2);
+        assert_eq!(workdays(monday, monday + 4), 5);
+        assert_eq!(workdays(monday, monday + 6), 5);
+        assert_eq!(workdays(monday, monday + 13), 10);
+        assert_eq!(workdays(monday + 5, monday + 6), 0);
+        assert_eq!(workdays(monday + 4, monday), -5);
+
+        assert_eq!(add_workdays(monday, 4), monday + 4);
+        assert_eq!(add_workdays(monday, 5), monday + 7);
+        assert_eq!(add_workdays(monday + 4, 1), monday + 7);
+        assert_eq!(add_workdays(monday, 10), monday + 14);
+        assert_eq!(add_workdays(monday, -1), monday - 3);
+        assert_eq!(add_workdays(monday, 0), monday);
+    }
+
+    #[test]
+    fn test_literals() {
+        assert_eq!(
+            replace_literals("2026-03-01+90days"),
+            "date(2026,03,01)+90days"
+        );
+        assert_eq!(
+            replace_literals("2026-03-01T08:30−today"),
+            "date(2026,03,01,08,30)−today"
+        );
+        assert_eq!(
+            replace_literals("1:45:30+0:20:15.5"),
+            "hms(1,45,30)+hms(0,20,15.5)"
+        );
+        assert_eq!(replace_literals("0:20"), "hms(0,20)");
+        // Subtraction with the minus sign and other numbers stay as they are
+        assert_eq!(replace_literals("2026−03−01"), "2026−03−01");
+        assert_eq!(replace_literals("1.5e-3+x2"), "1.5e-3+x2");
+
+        assert!(is_date("2026-03-01T08:30"));
+        assert!(!is_date("2026-03-01+1"));
+        assert!(is_duration("1:45:30.25"));
+        assert!(!is_duration("1:4"));
+    }
+}
@@ -0,0 +1,150 @@
+/// Largest denominator of a fraction shown in exact form
+const MAX_DENOMINATOR: i64 = 1000;
+/// Largest denominator of a π multiple or surd
+const MAX_SYMBOL_DENOMINATOR: i64 = 100;
+/// Largest numerator of a fraction shown in exact form
+const MAX_NUMERATOR: i64 = 1_000_000;
+/// Largest numerator of a π multiple or surd
+const MAX_SYMBOL_NUMERATOR: i64 = 1000;
+/// Largest number under a square root
+const MAX_RADICAND: i64 = 100;
+/// Relative difference below which a value counts as exact: a few units
+/// in the 15th significant digit that results are kept to
+const TOLERANCE: f64 = 1e-14;
+
+/// Exact form of `value` as a fraction (`3/4`), rational multiple of π
+/// (`3π/4`) or simple surd (`2√2`, `√3/2`); None for integers and values
+/// without a simple exact form
+pub fn exact_form(value: f64) -> Option<String> {
+    if !value.is_finite() || value.fract() == 0.0 {
+        return None;
+    }
+    if let Some((p, q)) = rational(value, MAX_NUMERATOR, MAX_DENOMINATOR) {
+        return Some(format!("{}{}/{}", sign(p), p.abs(), q));
+    }
+    if let Some((p, q)) = rational(
+        value / std::f64::consts::PI,
+        MAX_SYMBOL_NUMERATOR,
+        MAX_SYMBOL_DENOMINATOR,
+    ) {
+        return Some(multiple(p, q, "π"));
+    }
+    (2..=MAX_RADICAND)
+        .filter(|&n| is_squarefree(n))
+        .find_map(|n| {
+            rational(
+                value / (n as f64).sqrt(),
+                MAX_SYMBOL_NUMERATOR,
+                MAX_SYMBOL_DENOMINATOR,
+            )
+            .map(|(p, q)| multiple(p, q, &format!("√{}", n)))
+        })
+}
+
+/// Fraction p/q (q > 0, lowest terms, |p| and q within the limits) equal
+/// to `x` within the tolerance, found among the continued fraction
+/// convergents of `x`
+fn rational(x: f64, max_numerator: i64, max_denominator: i64) -> Option<(i64, i64)> {
+    if x == 0.0 {
+        return None;
+    }
+    // Convergents h/k of the continued fraction
+    let (mut h0, mut h1) = (0i64, 1i64);
+    let (mut k0, mut k1) = (1i64, 0i64);
+    let mut rest = x;
+    loop {
+        let a = rest.floor();
+        if a.abs() > max_numerator as f64 {
+            return None;
+        }
+        let a = a as i64;
+        (h0, h1) = (h1, a * h1 + h0);
+        (k0, k1) = (k1, a * k1 + k0);
+        if k1 > max_denominator || h1.abs() > max_numerator {
+            return None;
+        }
+        if (x - h1 as f64 / k1 as f64).abs() <= TOLERANCE * x.abs().max(1.0) {
+            return Some((h1, k1));
+        }
+        rest = 1.0 / (rest - a as f64);
+        if !rest.is_finite() {
+            return None;
+        }
+    }
+}
+
+/// `p/q` times `symbol`, e.g. `−π/2` or `3√2/4`
+fn multiple(p: i64, q: i64, symbol: &str) -> String {
+    let mut text = sign(p).to_string();
+    if p.abs() != 1 {
+        text.push_str(&p.abs().to_string());
+    }
+    text.push_str(symbol);
+    if q != 1 {
+        text.push_str(&format!("/{}", q));
+    }
+    text
+}
+
+fn sign(p: i64) -> &'static str {
+    if p < 0 {
+        "−"
+    } else {
+        ""
+    }
+}
+
+fn is_squarefree(n: i64) -> bool {
+    (2..=n).take_while(|d| d * d <= n).all(|d| n % (d * d) != 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn test_fractions() {
+        assert_eq!(exact_form(0.75), Some("3/4".to_string()));
+        assert_eq!(exact_form(-1.0 / 3.0), Some("−1/3".to_string()));
+        assert_eq!(exact_form(4.0), None);
+        assert_eq!(exact_form(f64::NAN), None);
+    }
+
+    #[test]
+    fn test_pi_multiples() {
+        assert_eq!(exact_form(PI), Some("π".to_string()));
+        assert_eq!(exact_form(3.0 * PI / 4.0), Some("3π/4".to_string()));
+        assert_eq!(exact_form(-PI / 6.0), Some("−π/6".to_string()));
+    }
+
+    #[test]
+    fn test_surds() {
+        assert_eq!(exact_form(8f64.sqrt()), Some("2√2".to_string()));
+        assert_eq!(
+            exact_form(60f64.to_radians().sin()),
+            Some("√3/2".to_string())
+        );
+        assert_eq!(
+            exact_form(-3.0 * 2f64.sqrt() / 4.0),
+            Some("−3√2/4".to_string())
+        );
+        // No simple exact form
+        assert_eq!(exact_form(1f64.exp()), None);
+        assert_eq!(exact_form(2f64.ln()), None);
+    }
+
+    #[test]
+    fn test_large_values() {
+        assert_eq!(exact_form(12345.5), Some("24691/2".to_string()));
+        assert_eq!(exact_form(250.0 * PI), Some("250π".to_string()));
+        // Ordinary large results have no exact form
+        assert_eq!(exact_form(79592.97508099454), None);
+        assert_eq!(exact_form(9855.573246083839), None);
+        let matched = (0..1000)
+            .map(|i| 1e3 + i as f64 * 98.765_432_1 + (i as f64).sqrt() / 7.0)
+            .filter(|&x| x.fract() != 0.0 && exact_form(x).is_some())
+            .count();
+        assert_eq!(matched, 0);
+    }
+}
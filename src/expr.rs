@@ -0,0 +1,817 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::f64::consts;
+use std::fmt;
+use std::ops::{Bound, RangeBounds};
+use std::str::FromStr;
+
+use crate::calculus;
+use crate::format::{round_decimal, RoundingMode};
+use crate::symbolic;
+
+/// Parsed expression tree
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    Num(f64),
+    Var(String),
+    Neg(Box<Expr>),
+    Binary(Op, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+    /// Vector literal `[a, b, …]`; a list of vectors is a matrix (see `matrix`)
+    List(Vec<Expr>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Pow,
+}
+
+/// Numerical calculus operations and their argument counts. The second
+/// argument is the bound variable, e.g. `integral(x², x, 0, 1)` or
+/// `summation(k, k, 1, 10)`. With two arguments `deriv(f, x)` is the
+/// symbolic derivative (see `symbolic`).
+pub const CALCULUS: [(&str, usize); 4] = [
+    ("deriv", 3),
+    ("integral", 4),
+    ("summation", 4),
+    ("product", 4),
+];
+
+/// Error for matrix values where only numbers are allowed
+const MATRIX_NOT_ALLOWED: &str = "Matrix not allowed";
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Num(f64),
+    Name(String),
+    Op(char),
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Comma,
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || c == '.' {
+            let start = i;
+            i = number_end(&chars, i);
+            let number: String = chars[start..i].iter().collect();
+            let value = number.parse().map_err(|_| "Syntax error".to_string())?;
+            tokens.push(Token::Num(value));
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Name(chars[start..i].iter().collect()));
+        } else {
+            tokens.push(match c {
+                '+' | '-' | '*' | '/' | '%' | '^' | '√' => Token::Op(c),
+                '(' => Token::LParen,
+                ')' => Token::RParen,
+                '[' => Token::LBracket,
+                ']' => Token::RBracket,
+                ',' => Token::Comma,
+                _ => return Err("Syntax error".to_string()),
+            });
+            i += 1;
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// End of the number literal starting at `start`
+fn number_end(chars: &[char], start: usize) -> usize {
+    let mut i = start;
+    while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+        i += 1;
+    }
+    // Exponent such as 1.5e12, unless the e is the constant (2e = 2·e)
+    if matches!(chars.get(i), Some('e' | 'E')) {
+        let mut j = i + 1;
+        if matches!(chars.get(j), Some('+' | '-')) {
+            j += 1;
+        }
+        if chars.get(j).is_some_and(|c| c.is_ascii_digit()) {
+            i = j;
+            while i < chars.len() && chars[i].is_ascii_digit() {
+                i += 1;
+            }
+        }
+    }
+    i
+}
+
+/// Number literals of an expression as written, in the order of the
+/// numbers in its parsed tree, e.g. `1.50` and `2` in `1.50*2`
+pub fn number_literals(text: &str) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut literals = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_ascii_digit() || c == '.' {
+            let end = number_end(&chars, i);
+            literals.push(chars[i..end].iter().collect());
+            i = end;
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            // Digits of names such as log10
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+        } else {
+            i += 1;
+        }
+    }
+    literals
+}
+
+/// Recursive descent parser. Precedence from low to high: `+ −`, `× ÷ %`
+/// (and implicit multiplication), unary minus, `^` (right associative).
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn sum(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.product()?;
+        while let Some(Token::Op(c @ ('+' | '-'))) = self.peek() {
+            let op = if *c == '+' { Op::Add } else { Op::Sub };
+            self.pos += 1;
+            let rhs = self.product()?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn product(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.unary()?;
+        loop {
+            let (op, rhs) = match self.peek() {
+                Some(Token::Op(c @ ('*' | '/' | '%'))) => {
+                    let op = match c {
+                        '*' => Op::Mul,
+                        '/' => Op::Div,
+                        _ => Op::Rem,
+                    };
+                    self.pos += 1;
+                    (op, self.unary()?)
+                }
+                // Implicit multiplication, e.g. 2k, 3(k+1) or 2√2
+                Some(
+                    Token::Num(_)
+                    | Token::Name(_)
+                    | Token::LParen
+                    | Token::LBracket
+                    | Token::Op('√'),
+                ) => (Op::Mul, self.power()?),
+                _ => return Ok(lhs),
+            };
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        match self.peek() {
+            Some(Token::Op('-')) => {
+                self.pos += 1;
+                Ok(Expr::Neg(Box::new(self.unary()?)))
+            }
+            Some(Token::Op('+')) => {
+                self.pos += 1;
+                self.unary()
+            }
+            _ => self.power(),
+        }
+    }
+
+    fn power(&mut self) -> Result<Expr, String> {
+        let base = self.primary()?;
+        if self.peek() == Some(&Token::Op('^')) {
+            self.pos += 1;
+            let exponent = self.unary()?;
+            return Ok(Expr::Binary(Op::Pow, Box::new(base), Box::new(exponent)));
+        }
+        Ok(base)
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Num(value)) => Ok(Expr::Num(value)),
+            Some(Token::Name(name)) => {
+                if self.peek() != Some(&Token::LParen) {
+                    return Ok(Expr::Var(name));
+                }
+                self.pos += 1;
+                let mut args = vec![self.sum()?];
+                while self.peek() == Some(&Token::Comma) {
+                    self.pos += 1;
+                    args.push(self.sum()?);
+                }
+                self.close_paren()?;
+                Ok(Expr::Call(name, args))
+            }
+            // Square root of the following operand, as in exact results (√3/2)
+            Some(Token::Op('√')) => Ok(Expr::Call("sqrt".to_string(), vec![self.primary()?])),
+            Some(Token::LParen) => {
+                let inner = self.sum()?;
+                self.close_paren()?;
+                Ok(inner)
+            }
+            Some(Token::LBracket) => {
+                let mut items = vec![self.sum()?];
+                while self.peek() == Some(&Token::Comma) {
+                    self.pos += 1;
+                    items.push(self.sum()?);
+                }
+                match self.next() {
+                    Some(Token::RBracket) => Ok(Expr::List(items)),
+                    None => Err("Missing ]".to_string()),
+                    _ => Err("Syntax error".to_string()),
+                }
+            }
+            _ => Err("Syntax error".to_string()),
+        }
+    }
+
+    fn close_paren(&mut self) -> Result<(), String> {
+        match self.next() {
+            Some(Token::RParen) => Ok(()),
+            None => Err("Missing )".to_string()),
+            _ => Err("Syntax error".to_string()),
+        }
+    }
+}
+
+impl FromStr for Expr {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, String> {
+        let mut parser = Parser {
+            tokens: tokenize(text)?,
+            pos: 0,
+        };
+        let expr = parser.sum()?;
+        if parser.pos < parser.tokens.len() {
+            return Err("Syntax error".to_string());
+        }
+        Ok(expr)
+    }
+}
+
+type Eval = Box<dyn Fn(&[f64]) -> f64>;
+
+struct Function {
+    eval: Eval,
+    min_args: usize,
+    max_args: usize,
+}
+
+/// Variables and functions available to expressions
+pub struct Context {
+    vars: HashMap<String, f64>,
+    funcs: HashMap<String, Function>,
+    /// sin, cos and tan take degrees, and the inverse functions return them
+    degrees: bool,
+}
+
+impl Default for Context {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Context {
+    /// Context with the constants π and e and the built-in functions
+    pub fn new() -> Self {
+        let mut ctx = Self {
+            vars: HashMap::new(),
+            funcs: HashMap::new(),
+            degrees: false,
+        };
+        ctx.var("pi", consts::PI);
+        ctx.var("e", consts::E);
+
+        type Unary = fn(f64) -> f64;
+        let unary: [(&str, Unary); 22] = [
+            ("sqrt", f64::sqrt),
+            ("exp", f64::exp),
+            ("ln", f64::ln),
+            ("log10", f64::log10),
+            ("abs", f64::abs),
+            ("sin", f64::sin),
+            ("cos", f64::cos),
+            ("tan", f64::tan),
+            ("asin", f64::asin),
+            ("acos", f64::acos),
+            ("atan", f64::atan),
+            ("sinh", f64::sinh),
+            ("cosh", f64::cosh),
+            ("tanh", f64::tanh),
+            ("asinh", f64::asinh),
+            ("acosh", f64::acosh),
+            ("atanh", f64::atanh),
+            ("floor", f64::floor),
+            ("ceil", f64::ceil),
+            ("trunc", f64::trunc),
+            ("frac", f64::fract),
+            ("signum", f64::signum),
+        ];
+        for (name, f) in unary {
+            ctx.func(name, f);
+        }
+        ctx.funcn("atan2", |xs| xs[0].atan2(xs[1]), 2..=2);
+        ctx.rounding(RoundingMode::HalfUp);
+        // Percentage change from the first value to the second
+        ctx.funcn("pchange", |xs| (xs[1] - xs[0]) / xs[0].abs() * 100.0, 2..=2);
+        ctx.funcn("max", |xs| xs.iter().copied().fold(f64::NAN, f64::max), 1..);
+        ctx.funcn("min", |xs| xs.iter().copied().fold(f64::NAN, f64::min), 1..);
+        ctx
+    }
+
+    /// Let `round(x)` and `round(x,n)` (n decimals) round ties with `mode`
+    pub fn rounding(&mut self, mode: RoundingMode) -> &mut Self {
+        let round = move |xs: &[f64]| match xs.get(1).copied().unwrap_or(0.0) {
+            n if n.fract() == 0.0 && n.abs() <= 300.0 => round_decimal(xs[0], n as i32, mode),
+            _ => f64::NAN,
+        };
+        self.funcn("round", round, 1..=2)
+    }
+
+    /// Let sin, cos and tan take their argument in degrees, and asin, acos,
+    /// atan and atan2 return degrees
+    pub fn use_degrees(&mut self) -> &mut Self {
+        self.degrees = true;
+        self.func("sin", |x| x.to_radians().sin());
+        self.func("cos", |x| x.to_radians().cos());
+        self.func("tan", |x| x.to_radians().tan());
+        self.func("asin", |x| x.asin().to_degrees());
+        self.func("acos", |x| x.acos().to_degrees());
+        self.func("atan", |x| x.atan().to_degrees());
+        self.funcn("atan2", |xs| xs[0].atan2(xs[1]).to_degrees(), 2..=2)
+    }
+
+    pub fn var(&mut self, name: &str, value: f64) -> &mut Self {
+        self.vars.insert(name.to_string(), value);
+        self
+    }
+
+    /// Names of the functions, built-in and registered
+    #[cfg(test)]
+    pub fn function_names(&self) -> impl Iterator<Item = &str> {
+        self.funcs.keys().map(String::as_str)
+    }
+
+    pub fn func(&mut self, name: &str, f: impl Fn(f64) -> f64 + 'static) -> &mut Self {
+        self.funcn(name, move |xs| f(xs[0]), 1..=1)
+    }
+
+    /// Function taking a number of arguments within `args`
+    pub fn funcn(
+        &mut self,
+        name: &str,
+        f: impl Fn(&[f64]) -> f64 + 'static,
+        args: impl RangeBounds<usize>,
+    ) -> &mut Self {
+        let min_args = match args.start_bound() {
+            Bound::Included(&n) => n,
+            Bound::Excluded(&n) => n + 1,
+            Bound::Unbounded => 0,
+        };
+        let max_args = match args.end_bound() {
+            Bound::Included(&n) => n,
+            Bound::Excluded(&n) => n - 1,
+            Bound::Unbounded => usize::MAX,
+        };
+        self.funcs.insert(
+            name.to_string(),
+            Function {
+                eval: Box::new(f),
+                min_args,
+                max_args,
+            },
+        );
+        self
+    }
+}
+
+/// Variables bound by calculus operations, innermost first
+enum Scope<'a> {
+    Global,
+    Bound(&'a str, f64, &'a Scope<'a>),
+}
+
+impl Scope<'_> {
+    fn lookup(&self, name: &str) -> Option<f64> {
+        match self {
+            Scope::Global => None,
+            Scope::Bound(var, value, parent) => {
+                if *var == name {
+                    Some(*value)
+                } else {
+                    parent.lookup(name)
+                }
+            }
+        }
+    }
+
+    fn contains(&self, name: &str) -> bool {
+        self.lookup(name).is_some()
+    }
+}
+
+struct Evaluator<'a> {
+    ctx: &'a Context,
+    /// Summed error estimate of the outermost derivatives and integrals
+    error: Cell<Option<f64>>,
+}
+
+impl Evaluator<'_> {
+    fn has_var(&self, name: &str, scope: &Scope) -> bool {
+        scope.contains(name) || self.ctx.vars.contains_key(name)
+    }
+
+    fn eval(&self, expr: &Expr, scope: &Scope) -> Result<f64, String> {
+        match expr {
+            Expr::Num(value) => Ok(*value),
+            Expr::Var(name) => scope
+                .lookup(name)
+                .or_else(|| self.ctx.vars.get(name).copied())
+                .ok_or_else(|| "Unknown name".to_string()),
+            Expr::Neg(inner) => Ok(-self.eval(inner, scope)?),
+            Expr::Binary(op, lhs, rhs) => {
+                let a = self.eval(lhs, scope)?;
+                let b = self.eval(rhs, scope)?;
+                Ok(match op {
+                    Op::Add => a + b,
+                    Op::Sub => a - b,
+                    Op::Mul => a * b,
+                    Op::Div => a / b,
+                    Op::Rem => a % b,
+                    Op::Pow => a.powf(b),
+                })
+            }
+            Expr::Call(name, args) => {
+                if let Some((body, var)) = symbolic::as_derivative(expr) {
+                    let derivative = symbolic::differentiate(body, var, self.ctx.degrees)?;
+                    return self.eval(&derivative, scope);
+                }
+                if CALCULUS.iter().any(|(n, _)| n == name) {
+                    return self.calculus(name, args, scope);
+                }
+                if let Some(func) = self.ctx.funcs.get(name) {
+                    let values = args
+                        .iter()
+                        .map(|arg| self.eval(arg, scope))
+                        .collect::<Result<Vec<f64>, String>>()?;
+                    return Ok((func.eval)(&values));
+                }
+                // A variable followed by parentheses, e.g. k(k+1)
+                let factor = self.eval(&Expr::Var(name.clone()), scope)?;
+                Ok(factor * self.eval(&args[0], scope)?)
+            }
+            Expr::List(_) => Err(MATRIX_NOT_ALLOWED.to_string()),
+        }
+    }
+
+    fn calculus(&self, name: &str, args: &[Expr], scope: &Scope) -> Result<f64, String> {
+        let (body, var) = match args {
+            [body, Expr::Var(var), ..] => (body, var.as_str()),
+            _ => return Err("Syntax error".to_string()),
+        };
+        let limits = args[2..]
+            .iter()
+            .map(|arg| self.eval(arg, scope))
+            .collect::<Result<Vec<f64>, String>>()?;
+
+        // Errors inside the body are reported after the numerical method
+        let failure = RefCell::new(None);
+        let f = |t: f64| match self.eval(body, &Scope::Bound(var, t, scope)) {
+            Ok(value) => value,
+            Err(e) => {
+                failure.borrow_mut().get_or_insert(e);
+                f64::NAN
+            }
+        };
+
+        let (value, error) = match name {
+            "deriv" => {
+                let estimate = calculus::derivative(f, limits[0]);
+                (estimate.value, Some(estimate.error))
+            }
+            "integral" => {
+                let estimate = calculus::integrate(f, limits[0], limits[1]);
+                (estimate.value, Some(estimate.error))
+            }
+            "summation" => (calculus::sum(f, limits[0], limits[1])?, None),
+            _ => (calculus::product(f, limits[0], limits[1])?, None),
+        };
+        if let Some(e) = failure.into_inner() {
+            return Err(e);
+        }
+
+        if let (Scope::Global, Some(error)) = (scope, error) {
+            self.error
+                .set(Some(self.error.get().unwrap_or(0.0) + error));
+        }
+        Ok(value)
+    }
+
+    /// Check names and argument counts without evaluating
+    fn check(&self, expr: &Expr, scope: &Scope) -> Result<(), String> {
+        match expr {
+            Expr::Num(_) => Ok(()),
+            Expr::Var(name) => {
+                if self.has_var(name, scope) {
+                    Ok(())
+                } else {
+                    Err("Unknown name".to_string())
+                }
+            }
+            Expr::Neg(inner) => self.check(inner, scope),
+            Expr::Binary(_, lhs, rhs) => {
+                self.check(lhs, scope)?;
+                self.check(rhs, scope)
+            }
+            Expr::Call(name, args) => {
+                if let Some((body, var)) = symbolic::as_derivative(expr) {
+                    return self.check(body, &Scope::Bound(var, 0.0, scope));
+                }
+                if let Some((_, count)) = CALCULUS.iter().find(|(n, _)| n == name) {
+                    let (body, var) = match args.as_slice() {
+                        [body, Expr::Var(var), ..] if args.len() == *count => (body, var),
+                        _ => return Err("Wrong arguments".to_string()),
+                    };
+                    for limit in &args[2..] {
+                        self.check(limit, scope)?;
+                    }
+                    return self.check(body, &Scope::Bound(var, 0.0, scope));
+                }
+
+                match self.ctx.funcs.get(name) {
+                    Some(func) if !(func.min_args..=func.max_args).contains(&args.len()) => {
+                        return Err("Wrong arguments".to_string());
+                    }
+                    Some(_) => {}
+                    None if args.len() == 1 && self.has_var(name, scope) => {}
+                    None => return Err("Unknown name".to_string()),
+                }
+                args.iter().try_for_each(|arg| self.check(arg, scope))
+            }
+            Expr::List(_) => Err(MATRIX_NOT_ALLOWED.to_string()),
+        }
+    }
+}
+
+impl Expr {
+    /// Check that every name is known and every call has the right arguments
+    pub fn check(&self, ctx: &Context) -> Result<(), String> {
+        Evaluator {
+            ctx,
+            error: Cell::new(None),
+        }
+        .check(self, &Scope::Global)
+    }
+
+    /// Evaluate the expression. The second value is the error estimate of
+    /// numerical derivatives and integrals, if there are any.
+    pub fn eval_with_error(&self, ctx: &Context) -> Result<(f64, Option<f64>), String> {
+        let evaluator = Evaluator {
+            ctx,
+            error: Cell::new(None),
+        };
+        evaluator.check(self, &Scope::Global)?;
+        let value = evaluator.eval(self, &Scope::Global)?;
+        Ok((value, evaluator.error.get()))
+    }
+
+    /// Turn the expression into a function of `var` (NaN where evaluation fails)
+    pub fn bind(self, ctx: Context, var: &str) -> Result<impl Fn(f64) -> f64, String> {
+        let var = var.to_string();
+        let evaluator = Evaluator {
+            ctx: &ctx,
+            error: Cell::new(None),
+        };
+        evaluator.check(&self, &Scope::Bound(&var, 0.0, &Scope::Global))?;
+
+        Ok(move |value: f64| {
+            let evaluator = Evaluator {
+                ctx: &ctx,
+                error: Cell::new(None),
+            };
+            evaluator
+                .eval(&self, &Scope::Bound(&var, value, &Scope::Global))
+                .unwrap_or(f64::NAN)
+        })
+    }
+}
+
+/// Binding strength of an expression's outermost operation, from 1 (`+`
+/// and `−`) to 5 (numbers, names and calls)
+pub fn precedence(expr: &Expr) -> u8 {
+    match expr {
+        Expr::Num(v) if *v < 0.0 => 3,
+        Expr::Num(_) | Expr::Var(_) | Expr::Call(..) | Expr::List(_) => 5,
+        Expr::Binary(Op::Pow, ..) => 4,
+        Expr::Neg(_) => 3,
+        Expr::Binary(Op::Mul | Op::Div | Op::Rem, ..) => 2,
+        Expr::Binary(Op::Add | Op::Sub, ..) => 1,
+    }
+}
+
+fn wrap(expr: &Expr, parens: bool) -> String {
+    if parens {
+        format!("({})", expr)
+    } else {
+        expr.to_string()
+    }
+}
+
+/// Display symbol of a built-in name, e.g. `π` for `pi` or `∫` for
+/// `integral`
+pub fn display_name(name: &str) -> &str {
+    match name {
+        "pi" => "π",
+        "theta" => "θ",
+        "integral" => "∫",
+        "summation" => "Σ",
+        "product" => "Π",
+        "yhat" => "ŷ",
+        "xhat" => "x̂",
+        "pchange" => "Δ%",
+        _ => name,
+    }
+}
+
+/// Formats with the display symbols (`−`, `·`, `²`, `π`, ...), so that the
+/// text can be entered again as an expression
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Num(v) if *v < 0.0 => write!(f, "−{}", Expr::Num(-v)),
+            Expr::Num(v) if v.fract() == 0.0 && v.abs() < 1e15 => write!(f, "{}", *v as i64),
+            Expr::Num(v) => write!(f, "{}", v),
+            Expr::Var(name) => write!(f, "{}", display_name(name)),
+            Expr::Neg(a) => write!(f, "−{}", wrap(a, precedence(a) < 2)),
+            Expr::Binary(op, a, b) => {
+                let (pa, pb) = (precedence(a), precedence(b));
+                match op {
+                    Op::Add => write!(f, "{}+{}", a, wrap(b, pb == 3)),
+                    Op::Sub => write!(f, "{}−{}", a, wrap(b, pb <= 1 || pb == 3)),
+                    Op::Mul => {
+                        let rhs = wrap(b, pb <= 1 || pb == 3);
+                        // Coefficients are written in front: 2x, 3sin(x), 2(x+1)
+                        let juxtapose = matches!(**a, Expr::Num(v) if v >= 0.0)
+                            && rhs.starts_with(|c: char| c.is_alphabetic() || c == '(');
+                        let sep = if juxtapose { "" } else { "·" };
+                        write!(f, "{}{}{}", wrap(a, pa < 2), sep, rhs)
+                    }
+                    Op::Div | Op::Rem => {
+                        let symbol = if *op == Op::Div { "/" } else { "%" };
+                        write!(f, "{}{}{}", wrap(a, pa < 2), symbol, wrap(b, pb <= 3))
+                    }
+                    Op::Pow => {
+                        write!(f, "{}", wrap(a, pa < 5))?;
+                        match **b {
+                            Expr::Num(2.0) => write!(f, "²"),
+                            Expr::Num(3.0) => write!(f, "³"),
+                            _ => write!(f, "^{}", wrap(b, pb < 5)),
+                        }
+                    }
+                }
+            }
+            Expr::Call(name, args) => {
+                match (name.as_str(), args.get(1)) {
+                    ("deriv", Some(Expr::Var(var))) => write!(f, "d/d{}(", var)?,
+                    _ => write!(f, "{}(", display_name(name))?,
+                }
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", arg)?;
+                }
+                write!(f, ")")
+            }
+            Expr::List(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(text: &str) -> Result<f64, String> {
+        text.parse::<Expr>()?
+            .eval_with_error(&Context::new())
+            .map(|(value, _)| value)
+    }
+
+    #[test]
+    fn test_precedence() {
+        assert_eq!(eval("2+3*4"), Ok(14.0));
+        assert_eq!(eval("-2^2"), Ok(-4.0));
+        assert_eq!(eval("2^3^2"), Ok(512.0));
+        assert_eq!(eval("2^-1"), Ok(0.5));
+        assert_eq!(eval("7%4"), Ok(3.0));
+        assert_eq!(eval("1.5e3"), Ok(1500.0));
+        assert_eq!(eval("2e"), Ok(2.0 * consts::E));
+        assert_eq!(eval("3√4/2"), Ok(3.0));
+    }
+
+    #[test]
+    fn test_functions() {
+        assert_eq!(eval("max(1,5,3)"), Ok(5.0));
+        assert_eq!(eval("log10(1000)"), Ok(3.0));
+        assert_eq!(eval("atan2(1,1)"), Ok(consts::FRAC_PI_4));
+        assert!(eval("atan2(1)").is_err());
+        assert!(eval("foo(1)").is_err());
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        assert_eq!(eval("(1+2"), Err("Missing )".to_string()));
+        assert_eq!(eval("1+"), Err("Syntax error".to_string()));
+        assert_eq!(eval("1)"), Err("Syntax error".to_string()));
+        assert_eq!(eval("y"), Err("Unknown name".to_string()));
+    }
+
+    #[test]
+    fn test_bound_variables() {
+        assert_eq!(eval("summation(k^2,k,1,10)"), Ok(385.0));
+        assert_eq!(eval("product(k,k,1,5)"), Ok(120.0));
+        assert_eq!(eval("summation(2k(k+1),k,1,3)"), Ok(40.0));
+        // Inner variable shadows the outer one; outer one is visible inside
+        assert_eq!(eval("summation(summation(k,k,1,j),j,1,3)"), Ok(10.0));
+        // The bound variable is not visible outside its operation
+        assert!(eval("summation(k,k,1,3)+k").is_err());
+        assert!(eval("summation(k,2,1,3)").is_err());
+    }
+
+    #[test]
+    fn test_display() {
+        let show = |text: &str| text.parse::<Expr>().unwrap().to_string();
+        assert_eq!(show("2*x*sin(x)+x^2*cos(x)"), "2x·sin(x)+x²·cos(x)");
+        assert_eq!(show("-(a+b)/(c*d)"), "−(a+b)/(c·d)");
+        assert_eq!(show("a-(b-c)"), "a−(b−c)");
+        assert_eq!(show("(x+1)^(1/2)*pi"), "(x+1)^(1/2)·π");
+        assert_eq!(show("2*(x+1)"), "2(x+1)");
+        assert_eq!(show("x^-1"), "x^(−1)");
+        assert_eq!(show("integral(x^3,x,0,1)"), "∫(x³,x,0,1)");
+    }
+
+    #[test]
+    fn test_symbolic_derivative() {
+        // The derivative is a function of x, evaluated at the value of x
+        let f = "deriv(x^3,x)"
+            .parse::<Expr>()
+            .unwrap()
+            .bind(Context::new(), "x");
+        assert_eq!(f.unwrap()(2.0), 12.0);
+        assert_eq!(eval("summation(deriv(k^2,k),k,1,3)"), Ok(12.0));
+    }
+
+    #[test]
+    fn test_bind() {
+        let f = "x^2+1".parse::<Expr>().unwrap().bind(Context::new(), "x");
+        assert_eq!(f.unwrap()(3.0), 10.0);
+        let g = "y+1".parse::<Expr>().unwrap().bind(Context::new(), "x");
+        assert!(g.is_err());
+    }
+}
@@ -0,0 +1,403 @@
+use crate::distributions::Function;
+use crate::solver;
+
+/// Largest number of rows in an amortisation or depreciation schedule
+pub const MAX_PERIODS: usize = 1000;
+
+/// Financial functions callable from expressions, with the argument order
+/// of spreadsheets; rates are per period (0.05 for 5%) and `type` is 1 for
+/// payments at the beginning of each period
+pub const FUNCTIONS: [Function; 7] = [
+    Function {
+        name: "pv",
+        params: &["rate", "n", "pmt", "fv", "type"],
+        required: 3,
+        description: "Present value",
+        eval: |a| pv(a[0], a[1], a[2], arg_or(a, 3, 0.0), is_due(a, 4)),
+    },
+    Function {
+        name: "fv",
+        params: &["rate", "n", "pmt", "pv", "type"],
+        required: 3,
+        description: "Future value",
+        eval: |a| fv(a[0], a[1], a[2], arg_or(a, 3, 0.0), is_due(a, 4)),
+    },
+    Function {
+        name: "pmt",
+        params: &["rate", "n", "pv", "fv", "type"],
+        required: 3,
+        description: "Payment per period",
+        eval: |a| pmt(a[0], a[1], a[2], arg_or(a, 3, 0.0), is_due(a, 4)),
+    },
+    Function {
+        name: "nper",
+        params: &["rate", "pmt", "pv", "fv", "type"],
+        required: 3,
+        description: "Number of periods",
+        eval: |a| nper(a[0], a[1], a[2], arg_or(a, 3, 0.0), is_due(a, 4)),
+    },
+    Function {
+        name: "rate",
+        params: &["n", "pmt", "pv", "fv", "type"],
+        required: 3,
+        description: "Interest rate per period",
+        eval: |a| rate(a[0], a[1], a[2], arg_or(a, 3, 0.0), is_due(a, 4)),
+    },
+    Function {
+        name: "sln",
+        params: &["cost", "salvage", "life"],
+        required: 3,
+        description: "Straight-line depreciation",
+        eval: |a| sln(a[0], a[1], a[2]),
+    },
+    Function {
+        name: "ddb",
+        params: &["cost", "salvage", "life", "period", "factor"],
+        required: 4,
+        description: "Declining-balance depreciation",
+        eval: |a| ddb(a[0], a[1], a[2], a[3], arg_or(a, 4, 2.0)),
+    },
+];
+
+/// Variable of a time value of money problem
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TvmVariable {
+    N,
+    Rate,
+    Pv,
+    Pmt,
+    Fv,
+}
+
+impl TvmVariable {
+    pub const ALL: [TvmVariable; 5] = [
+        TvmVariable::N,
+        TvmVariable::Rate,
+        TvmVariable::Pv,
+        TvmVariable::Pmt,
+        TvmVariable::Fv,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TvmVariable::N => "N",
+            TvmVariable::Rate => "I/Y",
+            TvmVariable::Pv => "PV",
+            TvmVariable::Pmt => "PMT",
+            TvmVariable::Fv => "FV",
+        }
+    }
+}
+
+/// Time value of money problem: `n` periods at `rate` per period, with the
+/// present value, payment and future value as signed cash flows (money
+/// received is positive, money paid out negative); payments are made at
+/// the end of each period, or at the beginning if `due`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Tvm {
+    pub n: f64,
+    pub rate: f64,
+    pub pv: f64,
+    pub pmt: f64,
+    pub fv: f64,
+    pub due: bool,
+}
+
+/// One row of an amortisation schedule; interest and principal have the
+/// sign of the payment
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Payment {
+    pub period: usize,
+    pub interest: f64,
+    pub principal: f64,
+    pub balance: f64,
+}
+
+impl Tvm {
+    /// Value of `variable` that balances the other four
+    pub fn solve(&self, variable: TvmVariable) -> Result<f64, String> {
+        let Tvm {
+            n,
+            rate: r,
+            pv: p,
+            pmt: m,
+            fv: f,
+            due,
+        } = *self;
+        let value = match variable {
+            TvmVariable::N => nper(r, m, p, f, due),
+            TvmVariable::Rate => rate(n, m, p, f, due),
+            TvmVariable::Pv => pv(r, n, m, f, due),
+            TvmVariable::Pmt => pmt(r, n, p, f, due),
+            TvmVariable::Fv => fv(r, n, m, p, due),
+        };
+        if value.is_finite() {
+            Ok(value)
+        } else {
+            Err(format!("No solution for {}", variable.label()))
+        }
+    }
+
+    /// Interest, principal and remaining balance after each payment
+    pub fn amortization(&self) -> Result<Vec<Payment>, String> {
+        let periods = whole_periods(self.n)?;
+        let mut balance = self.pv;
+        Ok((1..=periods)
+            .map(|period| {
+                // A payment at the beginning of the first period carries no
+                // interest yet
+                let interest = if self.due && period == 1 {
+                    0.0
+                } else {
+                    -balance * self.rate
+                };
+                let principal = self.pmt - interest;
+                balance += principal;
+                Payment {
+                    period,
+                    interest,
+                    principal,
+                    balance,
+                }
+            })
+            .collect())
+    }
+}
+
+/// Present value of `n` payments of `pmt` and a final `fv`
+pub fn pv(rate: f64, n: f64, pmt: f64, fv: f64, due: bool) -> f64 {
+    -(fv + pmt * annuity(rate, n, due)) / (1.0 + rate).powf(n)
+}
+
+/// Future value of `pv` and `n` payments of `pmt`
+pub fn fv(rate: f64, n: f64, pmt: f64, pv: f64, due: bool) -> f64 {
+    -(pv * (1.0 + rate).powf(n) + pmt * annuity(rate, n, due))
+}
+
+/// Payment per period that turns `pv` into `fv` in `n` periods
+pub fn pmt(rate: f64, n: f64, pv: f64, fv: f64, due: bool) -> f64 {
+    -(pv * (1.0 + rate).powf(n) + fv) / annuity(rate, n, due)
+}
+
+/// Number of periods in which payments of `pmt` turn `pv` into `fv`
+pub fn nper(rate: f64, pmt: f64, pv: f64, fv: f64, due: bool) -> f64 {
+    if rate == 0.0 {
+        return -(pv + fv) / pmt;
+    }
+    let payment = pmt * if due { 1.0 + rate } else { 1.0 };
+    ((payment - fv * rate) / (payment + pv * rate)).ln() / rate.ln_1p()
+}
+
+/// Interest rate per period at which the cash flows balance (NaN if there
+/// is none)
+pub fn rate(n: f64, pmt: f64, pv: f64, fv: f64, due: bool) -> f64 {
+    let scale = pv.abs() + (pmt * n).abs() + fv.abs();
+    let balance = |r: f64| (pv * (1.0 + r).powf(n) + pmt * annuity(r, n, due) + fv) / scale;
+    find_rate(balance)
+}
+
+/// Net present value at `rate` of cash flows, the first of which is at time 0
+pub fn npv(rate: f64, flows: &[f64]) -> f64 {
+    flows
+        .iter()
+        .enumerate()
+        .map(|(k, cf)| cf / (1.0 + rate).powi(k as i32))
+        .sum()
+}
+
+/// Internal rate of return: the rate at which the NPV of the cash flows is
+/// zero (NaN if there is none)
+pub fn irr(flows: &[f64]) -> f64 {
+    let scale: f64 = flows.iter().map(|cf| cf.abs()).sum();
+    find_rate(|r| npv(r, flows) / scale)
+}
+
+/// Straight-line depreciation per period
+pub fn sln(cost: f64, salvage: f64, life: f64) -> f64 {
+    (cost - salvage) / life
+}
+
+/// Declining-balance depreciation in `period` (1 to `life`) at `factor`
+/// times the straight-line rate (2 for double declining); the book value
+/// does not drop below the salvage value
+pub fn ddb(cost: f64, salvage: f64, life: f64, period: f64, factor: f64) -> f64 {
+    if period < 1.0 || period > life || period.fract() != 0.0 || life <= 0.0 {
+        return f64::NAN;
+    }
+    let mut book = cost;
+    let mut amount = 0.0;
+    for _ in 0..period as usize {
+        amount = (book * factor / life).min(book - salvage).max(0.0);
+        book -= amount;
+    }
+    amount
+}
+
+/// Depreciation and book value at the end of each period, either straight
+/// line or declining balance at `factor` times the straight-line rate
+pub fn depreciation(
+    cost: f64,
+    salvage: f64,
+    life: f64,
+    factor: Option<f64>,
+) -> Result<Vec<(f64, f64)>, String> {
+    let periods = whole_periods(life)?;
+    if !cost.is_finite() || !salvage.is_finite() || salvage > cost {
+        return Err("Salvage exceeds cost".to_string());
+    }
+    let mut book = cost;
+    Ok((1..=periods)
+        .map(|period| {
+            let amount = match factor {
+                Some(factor) => ddb(cost, salvage, life, period as f64, factor),
+                None => sln(cost, salvage, life),
+            };
+            book -= amount;
+            (amount, book)
+        })
+        .collect())
+}
+
+/// Annuity factor: value at the end of `n` periods of a payment of 1 per
+/// period
+fn annuity(rate: f64, n: f64, due: bool) -> f64 {
+    if rate == 0.0 {
+        return n;
+    }
+    // (1+r)ⁿ−1 without cancellation for small rates
+    let growth = (n * rate.ln_1p()).exp_m1();
+    growth / rate * if due { 1.0 + rate } else { 1.0 }
+}
+
+/// Root of `f` between −99% and 100% per period, nearest to 10%
+fn find_rate(f: impl Fn(f64) -> f64) -> f64 {
+    let guess = 0.1;
+    solver::solve(f, -0.99, 1.0, Some(guess))
+        .ok()
+        .and_then(|report| {
+            report
+                .roots
+                .into_iter()
+                .min_by(|a, b| (a - guess).abs().total_cmp(&(b - guess).abs()))
+        })
+        .unwrap_or(f64::NAN)
+}
+
+fn whole_periods(n: f64) -> Result<usize, String> {
+    if !(n >= 1.0 && n.fract() == 0.0) {
+        return Err("N must be a whole number".to_string());
+    }
+    if n > MAX_PERIODS as f64 {
+        return Err(format!("More than {} periods", MAX_PERIODS));
+    }
+    Ok(n as usize)
+}
+
+fn arg_or(args: &[f64], index: usize, default: f64) -> f64 {
+    args.get(index).copied().unwrap_or(default)
+}
+
+fn is_due(args: &[f64], index: usize) -> bool {
+    arg_or(args, index, 0.0) != 0.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-6 * expected.abs().max(1.0),
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_tvm() {
+        // 30-year mortgage of 300000 at 5% a year, paid monthly
+        let payment = pmt(0.05 / 12.0, 360.0, 300_000.0, 0.0, false);
+        assert_close(payment, -1610.464869);
+
+        let loan = Tvm {
+            n: 360.0,
+            rate: 0.05 / 12.0,
+            pv: 300_000.0,
+            pmt: payment,
+            fv: 0.0,
+            due: false,
+        };
+        assert_close(loan.solve(TvmVariable::N).unwrap(), 360.0);
+        assert_close(loan.solve(TvmVariable::Rate).unwrap(), 0.05 / 12.0);
+        assert_close(loan.solve(TvmVariable::Pv).unwrap(), 300_000.0);
+        assert_close(loan.solve(TvmVariable::Fv).unwrap(), 0.0);
+
+        // Saving 100 at the beginning of each year for 10 years at 6%
+        assert_close(fv(0.06, 10.0, -100.0, 0.0, true), 1397.164264);
+        assert_close(fv(0.0, 10.0, -100.0, -50.0, false), 1050.0);
+        assert_close(pv(0.1, 2.0, 0.0, 121.0, false), -100.0);
+
+        // Paying out without receiving anything has no rate
+        let gift = Tvm {
+            n: 10.0,
+            pmt: -100.0,
+            ..Default::default()
+        };
+        assert!(gift.solve(TvmVariable::Rate).is_err());
+    }
+
+    #[test]
+    fn test_amortization() {
+        let loan = Tvm {
+            n: 12.0,
+            rate: 0.01,
+            pv: 1000.0,
+            pmt: pmt(0.01, 12.0, 1000.0, 0.0, false),
+            fv: 0.0,
+            due: false,
+        };
+        let schedule = loan.amortization().unwrap();
+        assert_eq!(schedule.len(), 12);
+        assert_close(schedule[0].interest, -10.0);
+        assert_close(schedule[0].principal, loan.pmt + 10.0);
+        assert!(schedule[11].balance.abs() < 1e-9);
+
+        let due = Tvm {
+            due: true,
+            pmt: pmt(0.01, 12.0, 1000.0, 0.0, true),
+            ..loan
+        };
+        let schedule = due.amortization().unwrap();
+        assert_eq!(schedule[0].interest, 0.0);
+        assert!(schedule[11].balance.abs() < 1e-9);
+
+        assert!(Tvm { n: 2.5, ..loan }.amortization().is_err());
+    }
+
+    #[test]
+    fn test_cash_flows() {
+        let flows = [-1000.0, 300.0, 400.0, 500.0];
+        assert_close(npv(0.1, &flows), -21.036814);
+        let r = irr(&flows);
+        assert_close(r, 0.0889633947);
+        assert!(npv(r, &flows).abs() < 1e-6);
+        assert!(irr(&[100.0, 200.0]).is_nan());
+    }
+
+    #[test]
+    fn test_depreciation() {
+        assert_eq!(sln(10_000.0, 1000.0, 5.0), 1800.0);
+        assert_eq!(ddb(10_000.0, 1000.0, 5.0, 1.0, 2.0), 4000.0);
+        assert_eq!(ddb(10_000.0, 1000.0, 5.0, 2.0, 2.0), 2400.0);
+        assert!(ddb(10_000.0, 1000.0, 5.0, 6.0, 2.0).is_nan());
+
+        let schedule = depreciation(10_000.0, 1000.0, 5.0, Some(2.0)).unwrap();
+        assert_eq!(schedule.len(), 5);
+        // The book value stops at the salvage value
+        assert_close(schedule[4].1, 1000.0);
+        let schedule = depreciation(10_000.0, 1000.0, 5.0, None).unwrap();
+        assert_eq!(schedule[4], (1800.0, 1000.0));
+        assert!(depreciation(100.0, 200.0, 5.0, None).is_err());
+    }
+}
@@ -0,0 +1,743 @@
+use crate::calculator::format_result;
+
+/// Most digits offered for the FIX, SCI, ENG and SIG formats
+pub const MAX_DIGITS: u8 = 10;
+
+/// SI prefixes of the ENG format and their powers of ten
+const SI_PREFIXES: [(&str, i32); 8] = [
+    ("p", -12),
+    ("n", -9),
+    ("µ", -6),
+    ("m", -3),
+    ("", 0),
+    ("k", 3),
+    ("M", 6),
+    ("G", 9),
+];
+
+/// How numeric results are shown. The value itself is kept at full
+/// precision; only the display, history and exports use the format.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum DisplayFormat {
+    /// Up to 10 decimals, scientific notation for very large and small values
+    #[default]
+    Auto,
+    /// Fixed number of decimals
+    Fix(u8),
+    /// Scientific notation with a number of decimals in the mantissa
+    Sci(u8),
+    /// Like SCI with exponents that are multiples of 3, optionally shown as
+    /// SI prefixes (`12.5k`)
+    Eng(u8, bool),
+    /// Number of significant figures
+    Sig(u8),
+}
+
+impl DisplayFormat {
+    /// Formats offered in the mode bar, with their default digits
+    pub const ALL: [DisplayFormat; 5] = [
+        DisplayFormat::Auto,
+        DisplayFormat::Fix(2),
+        DisplayFormat::Sci(4),
+        DisplayFormat::Eng(3, false),
+        DisplayFormat::Sig(6),
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            DisplayFormat::Auto => "AUTO",
+            DisplayFormat::Fix(_) => "FIX",
+            DisplayFormat::Sci(_) => "SCI",
+            DisplayFormat::Eng(..) => "ENG",
+            DisplayFormat::Sig(_) => "SIG",
+        }
+    }
+
+    /// Digits of the format, or None for AUTO
+    pub fn digits(self) -> Option<u8> {
+        match self {
+            DisplayFormat::Auto => None,
+            DisplayFormat::Fix(n)
+            | DisplayFormat::Sci(n)
+            | DisplayFormat::Eng(n, _)
+            | DisplayFormat::Sig(n) => Some(n),
+        }
+    }
+
+    /// The same format with other digits, kept in the allowed range
+    pub fn with_digits(self, digits: u8) -> Self {
+        let n = digits.min(MAX_DIGITS);
+        match self {
+            DisplayFormat::Auto => DisplayFormat::Auto,
+            DisplayFormat::Fix(_) => DisplayFormat::Fix(n),
+            DisplayFormat::Sci(_) => DisplayFormat::Sci(n),
+            DisplayFormat::Eng(_, prefixes) => DisplayFormat::Eng(n, prefixes),
+            // At least one significant figure
+            DisplayFormat::Sig(_) => DisplayFormat::Sig(n.max(1)),
+        }
+    }
+
+    /// Short text for the mode bar, e.g. `FIX2`
+    pub fn name(self) -> String {
+        match self.digits() {
+            Some(n) => format!("{}{}", self.label(), n),
+            None => self.label().to_string(),
+        }
+    }
+
+    /// Format a finite value, rounding to the shown digits with `mode`
+    pub fn format(self, value: f64, mode: RoundingMode) -> String {
+        let value = match self {
+            DisplayFormat::Auto => value,
+            DisplayFormat::Fix(n) if value.abs() < 1e12 => round_decimal(value, n as i32, mode),
+            DisplayFormat::Fix(n) | DisplayFormat::Sci(n) => {
+                round_significant(value, n as i32 + 1, mode)
+            }
+            // Up to two more digits before the point than SCI
+            DisplayFormat::Eng(n, _) => {
+                let extra = exponent(&format!("{:e}", value)).rem_euclid(3);
+                round_significant(value, n as i32 + 1 + extra, mode)
+            }
+            DisplayFormat::Sig(n) => round_significant(value, n.max(1) as i32, mode),
+        };
+        let text = match self {
+            DisplayFormat::Auto => return format_result(value),
+            // Values too large for fixed decimals switch to SCI
+            DisplayFormat::Fix(n) if value.abs() >= 1e12 => scientific(value, n),
+            DisplayFormat::Fix(n) => format!("{:.*}", n as usize, value),
+            DisplayFormat::Sci(n) => scientific(value, n),
+            DisplayFormat::Eng(n, prefixes) => engineering(value, n, prefixes),
+            DisplayFormat::Sig(n) => significant(value, n),
+        };
+        // No sign on values that round to zero
+        match text.strip_prefix('-') {
+            Some(rest) if !rest.chars().any(|c| ('1'..='9').contains(&c)) => rest.to_string(),
+            _ => text,
+        }
+    }
+}
+
+/// Decimal separator and digit grouping of shown numbers. Expressions and
+/// copied results always use `.` without grouping; only the display is
+/// localized.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Locale {
+    /// `1.234,5` instead of `1,234.5`
+    pub decimal_comma: bool,
+    /// Separate the digits of whole parts in groups of three
+    pub grouping: bool,
+}
+
+impl Locale {
+    pub fn decimal(self) -> char {
+        if self.decimal_comma {
+            ','
+        } else {
+            '.'
+        }
+    }
+
+    pub fn group(self) -> char {
+        if self.decimal_comma {
+            '.'
+        } else {
+            ','
+        }
+    }
+
+    /// Display text of an expression or result. With a decimal comma the
+    /// commas between arguments become `;`. Digits of names (`log10`),
+    /// exponents and date and time literals are left as they are.
+    pub fn localize(self, text: &str) -> String {
+        if self == Self::default() {
+            return text.to_string();
+        }
+        let chars: Vec<char> = text.chars().collect();
+        let mut result = String::with_capacity(text.len());
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            let prev = |k: usize| (i >= k).then(|| chars[i - k]);
+            let starts_number = c.is_ascii_digit()
+                && !prev(1).is_some_and(|p| p.is_alphanumeric() || p == '_' || p == '.')
+                // Second or third field of a date or time
+                && !(matches!(prev(1), Some('-' | ':'))
+                    && prev(2).is_some_and(|p| p.is_ascii_digit()));
+            if !starts_number {
+                result.push(match c {
+                    ',' if self.decimal_comma => ';',
+                    _ => c,
+                });
+                i += 1;
+                continue;
+            }
+            let whole = chars[i..].iter().take_while(|c| c.is_ascii_digit()).count();
+            let next = chars.get(i + whole);
+            let literal = next == Some(&':')
+                || next == Some(&'-') && chars.get(i + whole + 1).is_some_and(char::is_ascii_digit);
+            if literal {
+                result.extend(&chars[i..i + whole]);
+                i += whole;
+                continue;
+            }
+            for (k, digit) in chars[i..i + whole].iter().enumerate() {
+                if self.grouping && k > 0 && (whole - k) % 3 == 0 {
+                    result.push(self.group());
+                }
+                result.push(*digit);
+            }
+            i += whole;
+            let fraction = match chars.get(i) {
+                Some('.') => {
+                    1 + chars[i + 1..]
+                        .iter()
+                        .take_while(|c| c.is_ascii_digit())
+                        .count()
+                }
+                _ => 0,
+            };
+            if fraction > 0 {
+                result.push(self.decimal());
+                result.extend(&chars[i + 1..i + fraction]);
+                i += fraction;
+            }
+        }
+        result
+    }
+
+    /// Position in the localized text of each character boundary of
+    /// `text`, so a cursor can be shown and placed in the display. Only
+    /// group separators are inserted; every other character maps to one.
+    pub fn positions(self, text: &str) -> Vec<usize> {
+        let shown: Vec<char> = self.localize(text).chars().collect();
+        let mut positions = Vec::with_capacity(shown.len() + 1);
+        let mut j = 0;
+        for c in text.chars() {
+            if c.is_ascii_digit() && shown.get(j).is_some_and(|s| !s.is_ascii_digit()) {
+                j += 1;
+            }
+            positions.push(j);
+            j += 1;
+        }
+        positions.push(shown.len());
+        positions
+    }
+
+    /// Plain text of pasted text in this locale: group separators (the
+    /// locale's, spaces or `'`) are removed from numbers such as
+    /// `1,234,567.5`, decimal commas become points and, with a decimal
+    /// comma, `;` becomes the argument separator. A single group of three
+    /// digits (`1,234`) counts as grouping only when grouping is on.
+    pub fn delocalize(self, text: &str) -> String {
+        const GROUPS: [char; 5] = [' ', '\'', '\u{A0}', '\u{2009}', '\u{202F}'];
+        let chars: Vec<char> = text.chars().collect();
+        let mut result = String::with_capacity(text.len());
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            let starts_number = c.is_ascii_digit()
+                && !(i > 0
+                    && (chars[i - 1].is_alphanumeric() || matches!(chars[i - 1], '_' | '.')));
+            if !starts_number {
+                result.push(match c {
+                    ';' if self.decimal_comma => ',',
+                    _ => c,
+                });
+                i += 1;
+                continue;
+            }
+            let mut end = i;
+            while end < chars.len()
+                && (chars[end].is_ascii_digit()
+                    || matches!(chars[end], '.' | ',')
+                    || GROUPS.contains(&chars[end]))
+            {
+                end += 1;
+            }
+            while !chars[end - 1].is_ascii_digit() {
+                end -= 1;
+            }
+            let run = &chars[i..end];
+            let grouped = std::iter::once(self.group())
+                .chain(GROUPS)
+                .find_map(|g| self.grouped_length(run, g).map(|len| (g, len)));
+            let len = match grouped {
+                Some((g, len)) => {
+                    for &c in &run[..len] {
+                        if c == self.decimal() {
+                            result.push('.');
+                        } else if c != g {
+                            result.push(c);
+                        }
+                    }
+                    len
+                }
+                None => {
+                    // Whole part with an optional fraction
+                    let whole = run.iter().take_while(|c| c.is_ascii_digit()).count();
+                    result.extend(&run[..whole]);
+                    let fraction = match run.get(whole) {
+                        Some(&d) if d == self.decimal() => run[whole + 1..]
+                            .iter()
+                            .take_while(|c| c.is_ascii_digit())
+                            .count(),
+                        _ => 0,
+                    };
+                    if fraction > 0 {
+                        result.push('.');
+                        result.extend(&run[whole + 1..whole + 1 + fraction]);
+                        whole + 1 + fraction
+                    } else {
+                        whole
+                    }
+                }
+            };
+            i += len;
+        }
+        result
+    }
+
+    /// Length of a number grouped with `group` at the start of `run`, such
+    /// as `1,234,567.5`
+    fn grouped_length(self, run: &[char], group: char) -> Option<usize> {
+        let digits = |from: usize| {
+            run[from.min(run.len())..]
+                .iter()
+                .take_while(|c| c.is_ascii_digit())
+                .count()
+        };
+        let first = digits(0);
+        if !(1..=3).contains(&first) {
+            return None;
+        }
+        let mut pos = first;
+        let mut groups = 0;
+        while run.get(pos) == Some(&group) && digits(pos + 1) == 3 {
+            pos += 4;
+            groups += 1;
+        }
+        if groups == 0 || digits(pos) > 0 {
+            return None;
+        }
+        let fraction = match run.get(pos) {
+            Some(&d) if d == self.decimal() => digits(pos + 1),
+            _ => 0,
+        };
+        let ambiguous = group == self.group() && groups == 1 && fraction == 0 && !self.grouping;
+        if ambiguous {
+            return None;
+        }
+        Some(if fraction > 0 {
+            pos + 1 + fraction
+        } else {
+            pos
+        })
+    }
+
+    /// Parse a number typed or pasted with either separator and optional
+    /// grouping (`1,234.5`, `1.234,5`, `1 234,5`). With both separators the
+    /// last one is the decimal; a single one is the decimal unless it is
+    /// the group separator followed by exactly three digits.
+    pub fn parse_number(self, text: &str) -> Option<f64> {
+        let text: String = text
+            .trim()
+            .chars()
+            .filter(|c| !matches!(c, ' ' | '\'' | '\u{2009}' | '\u{202F}' | '_'))
+            .map(|c| if c == '−' { '-' } else { c })
+            .collect();
+        let separators: Vec<(usize, char)> = text
+            .char_indices()
+            .filter(|(_, c)| matches!(c, '.' | ','))
+            .collect();
+        let decimal = match separators.as_slice() {
+            [] => None,
+            [(i, c)] => {
+                let digits_after = text[i + 1..]
+                    .chars()
+                    .take_while(char::is_ascii_digit)
+                    .count();
+                let grouped =
+                    self.grouping && *c == self.group() && digits_after == 3 && text.len() == i + 4;
+                (!grouped).then_some(*c)
+            }
+            [.., (_, last)] => {
+                // Repeated separators are groups, e.g. 1,234,567
+                let first = separators[0].1;
+                separators.iter().any(|(_, c)| *c != first).then_some(*last)
+            }
+        };
+        let number: String = text
+            .chars()
+            .filter_map(|c| match c {
+                '.' | ',' if Some(c) == decimal => Some('.'),
+                '.' | ',' => None,
+                _ => Some(c),
+            })
+            .collect();
+        number.parse().ok()
+    }
+}
+
+/// How a value is rounded to fewer digits
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum RoundingMode {
+    /// Ties to the even digit (banker's rounding)
+    HalfEven,
+    /// Ties away from zero
+    #[default]
+    HalfUp,
+    TowardZero,
+    Floor,
+    Ceiling,
+}
+
+impl RoundingMode {
+    pub const ALL: [RoundingMode; 5] = [
+        RoundingMode::HalfEven,
+        RoundingMode::HalfUp,
+        RoundingMode::TowardZero,
+        RoundingMode::Floor,
+        RoundingMode::Ceiling,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            RoundingMode::HalfEven => "HALF-EVEN",
+            RoundingMode::HalfUp => "HALF-UP",
+            RoundingMode::TowardZero => "TRUNC",
+            RoundingMode::Floor => "FLOOR",
+            RoundingMode::Ceiling => "CEIL",
+        }
+    }
+}
+
+/// Most significant digits kept in results
+pub const MAX_PRECISION: u8 = 17;
+
+/// Rounding of shown and stored results
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rounding {
+    pub mode: RoundingMode,
+    /// Significant digits kept in every result
+    pub precision: u8,
+    /// Store results as shown in the display format, so that further
+    /// calculation uses the rounded value
+    pub stored: bool,
+}
+
+impl Default for Rounding {
+    fn default() -> Self {
+        Self {
+            mode: RoundingMode::default(),
+            precision: 15,
+            stored: false,
+        }
+    }
+}
+
+impl Rounding {
+    /// Value of a result as it is stored
+    pub fn store(self, value: f64, format: DisplayFormat) -> f64 {
+        let value = round_significant(value, self.precision as i32, self.mode);
+        if !self.stored {
+            return value;
+        }
+        // The shown text is the rounded value, once SI prefixes are written
+        // as exponents
+        let format = match format {
+            DisplayFormat::Eng(n, true) => DisplayFormat::Eng(n, false),
+            format => format,
+        };
+        format.format(value, self.mode).parse().unwrap_or(value)
+    }
+}
+
+/// Round to a number of decimals (negative for tens, hundreds, …). The
+/// decimal digits of the value are rounded, so 2.675 is a tie even though
+/// its binary value is slightly below it.
+pub fn round_decimal(value: f64, decimals: i32, mode: RoundingMode) -> f64 {
+    if !value.is_finite() || value == 0.0 {
+        return value;
+    }
+    // Shortest digits that give the value back, e.g. 2.675e0
+    let text = format!("{:e}", value.abs());
+    let (mantissa, power) = text.split_once('e').unwrap_or((&text, "0"));
+    let power: i32 = power.parse().unwrap_or(0);
+    let mut digits: String = mantissa.chars().filter(char::is_ascii_digit).collect();
+    // Digits at 10^-decimals and above are kept
+    let mut keep = power + 1 + decimals;
+    if keep >= digits.len() as i32 {
+        return value;
+    }
+    if keep < 0 {
+        digits.insert_str(0, &"0".repeat(-keep as usize));
+        keep = 0;
+    }
+    let (kept, rest) = digits.split_at(keep as usize);
+    let kept: u128 = if kept.is_empty() {
+        0
+    } else {
+        kept.parse().unwrap_or(0)
+    };
+    let negative = value < 0.0;
+    let up = match mode {
+        RoundingMode::HalfUp => rest.as_bytes()[0] >= b'5',
+        RoundingMode::HalfEven => match rest.as_bytes()[0] {
+            b'5' if rest[1..].bytes().all(|b| b == b'0') => kept % 2 == 1,
+            d => d >= b'5',
+        },
+        RoundingMode::TowardZero => false,
+        RoundingMode::Floor => negative,
+        RoundingMode::Ceiling => !negative,
+    };
+    let magnitude: f64 = format!("{}e{}", kept + u128::from(up), -decimals)
+        .parse()
+        .unwrap_or(value.abs());
+    if negative {
+        -magnitude
+    } else {
+        magnitude
+    }
+}
+
+/// Round to a number of significant figures
+pub fn round_significant(value: f64, figures: i32, mode: RoundingMode) -> f64 {
+    if !value.is_finite() || value == 0.0 {
+        return value;
+    }
+    let power = exponent(&format!("{:e}", value));
+    round_decimal(value, figures - 1 - power, mode)
+}
+
+/// Exponent of a number in scientific notation, e.g. 3 for `1.0e3`
+fn exponent(text: &str) -> i32 {
+    text.split_once('e')
+        .map_or(0, |(_, e)| e.parse().unwrap_or(0))
+}
+
+fn scientific(value: f64, decimals: u8) -> String {
+    format!("{:.*e}", decimals as usize, value)
+}
+
+fn engineering(value: f64, decimals: u8, prefixes: bool) -> String {
+    if value == 0.0 {
+        return format!("{:.*}", decimals as usize, 0.0);
+    }
+    let mut power = exponent(&format!("{:e}", value)).div_euclid(3) * 3;
+    let mut mantissa = format!("{:.*}", decimals as usize, value / 10f64.powi(power));
+    // Rounding may reach 1000 (999.96 → 1000.0): move to the next power
+    if mantissa
+        .trim_start_matches('-')
+        .split('.')
+        .next()
+        .unwrap_or("")
+        .len()
+        > 3
+    {
+        power += 3;
+        mantissa = format!("{:.*}", decimals as usize, value / 10f64.powi(power));
+    }
+    match SI_PREFIXES.iter().find(|(_, p)| *p == power) {
+        Some((prefix, _)) if prefixes => format!("{}{}", mantissa, prefix),
+        _ => format!("{}e{}", mantissa, power),
+    }
+}
+
+fn significant(value: f64, figures: u8) -> String {
+    if value == 0.0 {
+        return "0".to_string();
+    }
+    let figures = figures.max(1);
+    let text = scientific(value, figures - 1);
+    let power = exponent(&text);
+    if !(-5..12).contains(&power) {
+        return text;
+    }
+    // Trailing zeros are significant and stay
+    let decimals = (figures as i32 - 1 - power).max(0) as usize;
+    format!("{:.*}", decimals, text.parse().unwrap_or(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HALF_UP: RoundingMode = RoundingMode::HalfUp;
+
+    #[test]
+    fn test_fixed_and_scientific() {
+        assert_eq!(
+            DisplayFormat::Auto.format(1.0 / 3.0, HALF_UP),
+            "0.3333333333"
+        );
+        assert_eq!(DisplayFormat::Fix(2).format(1234.5678, HALF_UP), "1234.57");
+        assert_eq!(DisplayFormat::Fix(0).format(2.5, HALF_UP), "3");
+        assert_eq!(
+            DisplayFormat::Fix(0).format(2.5, RoundingMode::HalfEven),
+            "2"
+        );
+        assert_eq!(DisplayFormat::Fix(2).format(-0.001, HALF_UP), "0.00");
+        assert_eq!(DisplayFormat::Fix(2).format(2e15, HALF_UP), "2.00e15");
+        assert_eq!(DisplayFormat::Sci(3).format(1234.5678, HALF_UP), "1.235e3");
+        assert_eq!(DisplayFormat::Sci(2).format(-0.000123, HALF_UP), "-1.23e-4");
+    }
+
+    #[test]
+    fn test_engineering() {
+        assert_eq!(
+            DisplayFormat::Eng(2, false).format(12345.0, HALF_UP),
+            "12.35e3"
+        );
+        assert_eq!(
+            DisplayFormat::Eng(2, false).format(0.00047, HALF_UP),
+            "470.00e-6"
+        );
+        assert_eq!(
+            DisplayFormat::Eng(1, true).format(0.00047, HALF_UP),
+            "470.0µ"
+        );
+        assert_eq!(
+            DisplayFormat::Eng(1, true).format(-2.2e-9, HALF_UP),
+            "-2.2n"
+        );
+        assert_eq!(DisplayFormat::Eng(1, true).format(1.5e6, HALF_UP), "1.5M");
+        assert_eq!(DisplayFormat::Eng(1, true).format(42.0, HALF_UP), "42.0");
+        // Rounding up to the next power of 1000
+        assert_eq!(DisplayFormat::Eng(1, true).format(999.97, HALF_UP), "1.0k");
+        assert_eq!(DisplayFormat::Eng(1, true).format(999.94, HALF_UP), "999.9");
+        // No prefix beyond giga
+        assert_eq!(DisplayFormat::Eng(1, true).format(3e15, HALF_UP), "3.0e15");
+    }
+
+    #[test]
+    fn test_significant() {
+        assert_eq!(DisplayFormat::Sig(3).format(1234.5678, HALF_UP), "1230");
+        assert_eq!(
+            DisplayFormat::Sig(3).format(0.00012345, HALF_UP),
+            "0.000123"
+        );
+        assert_eq!(DisplayFormat::Sig(3).format(1.5, HALF_UP), "1.50");
+        assert_eq!(DisplayFormat::Sig(2).format(9.96, HALF_UP), "10");
+        assert_eq!(DisplayFormat::Sig(3).format(6.02e23, HALF_UP), "6.02e23");
+        assert_eq!(DisplayFormat::Sig(0).with_digits(0), DisplayFormat::Sig(1));
+        assert_eq!(DisplayFormat::Eng(3, true).name(), "ENG3");
+    }
+
+    #[test]
+    fn test_rounding_modes() {
+        use RoundingMode::*;
+        let round = |value, decimals, mode| round_decimal(value, decimals, mode);
+        // 2.675 is a decimal tie although its binary value is just below
+        assert_eq!(round(2.675, 2, HalfUp), 2.68);
+        assert_eq!(round(2.665, 2, HalfEven), 2.66);
+        assert_eq!(round(2.675, 2, HalfEven), 2.68);
+        assert_eq!(round(2.6651, 2, HalfEven), 2.67);
+        assert_eq!(round(-2.5, 0, HalfUp), -3.0);
+        assert_eq!(round(-2.5, 0, HalfEven), -2.0);
+        assert_eq!(round(-2.71, 1, TowardZero), -2.7);
+        assert_eq!(round(-2.71, 1, Floor), -2.8);
+        assert_eq!(round(2.71, 1, Ceiling), 2.8);
+        assert_eq!(round(1234.5, -2, HalfUp), 1200.0);
+        assert_eq!(round(0.004, 2, HalfUp), 0.0);
+        assert_eq!(round(0.004, 2, Ceiling), 0.01);
+        assert_eq!(round(1.5, 3, Floor), 1.5);
+        assert_eq!(round_significant(0.1 + 0.2, 15, HalfUp), 0.3);
+        assert_eq!(DisplayFormat::Sig(2).format(0.125, HalfEven), "0.12");
+    }
+
+    #[test]
+    fn test_stored_rounding() {
+        let mut rounding = Rounding::default();
+        assert_eq!(rounding.store(0.1 + 0.2, DisplayFormat::Fix(0)), 0.3);
+        rounding.stored = true;
+        assert_eq!(rounding.store(2.0 / 3.0, DisplayFormat::Fix(2)), 0.67);
+        assert_eq!(rounding.store(1234.5, DisplayFormat::Eng(1, true)), 1200.0);
+        assert_eq!(rounding.store(2.0 / 3.0, DisplayFormat::Auto), 0.6666666667);
+    }
+
+    #[test]
+    fn test_localize() {
+        let point = Locale {
+            decimal_comma: false,
+            grouping: true,
+        };
+        let comma = Locale {
+            decimal_comma: true,
+            grouping: true,
+        };
+        assert_eq!(Locale::default().localize("1234567.891"), "1234567.891");
+        assert_eq!(point.localize("1234567.891"), "1,234,567.891");
+        assert_eq!(comma.localize("-1234567.891"), "-1.234.567,891");
+        assert_eq!(comma.localize("mean(1.5,2500)"), "mean(1,5;2.500)");
+        // Names, exponents and dates keep their digits
+        assert_eq!(point.localize("log10(12345)×x2"), "log10(12,345)×x2");
+        assert_eq!(point.localize("1.234568e12"), "1.234568e12");
+        assert_eq!(
+            point.localize("2026-03-01T08:30+1:45:30"),
+            "2026-03-01T08:30+1:45:30"
+        );
+        assert_eq!(point.localize("0.123456"), "0.123456");
+    }
+
+    #[test]
+    fn test_positions() {
+        let point = Locale {
+            decimal_comma: false,
+            grouping: true,
+        };
+        assert_eq!(Locale::default().positions("1+2"), [0, 1, 2, 3]);
+        // "12,345+1"
+        assert_eq!(point.positions("12345+1"), [0, 1, 3, 4, 5, 6, 7, 8]);
+        // A literal comma is not taken for a group separator
+        assert_eq!(point.positions("f(1,2)"), [0, 1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn test_delocalize() {
+        let point = Locale::default();
+        let comma = Locale {
+            decimal_comma: true,
+            grouping: false,
+        };
+        assert_eq!(point.delocalize("1,234,567.89×2"), "1234567.89×2");
+        assert_eq!(point.delocalize("1 234 567+1'000"), "1234567+1000");
+        // A single group is an argument separator unless grouping is on
+        assert_eq!(point.delocalize("max(1,234)"), "max(1,234)");
+        assert_eq!(
+            Locale {
+                grouping: true,
+                ..point
+            }
+            .delocalize("1,234"),
+            "1234"
+        );
+        assert_eq!(comma.delocalize("1.234.567,5+2,5"), "1234567.5+2.5");
+        assert_eq!(comma.delocalize("mean(1,5;2)"), "mean(1.5,2)");
+        assert_eq!(comma.delocalize("1.5"), "1.5");
+        assert_eq!(point.delocalize("100 USD+log10(2)"), "100 USD+log10(2)");
+    }
+
+    #[test]
+    fn test_parse_number() {
+        let point = Locale::default();
+        let grouped = Locale {
+            decimal_comma: false,
+            grouping: true,
+        };
+        let comma = Locale {
+            decimal_comma: true,
+            grouping: true,
+        };
+        assert_eq!(point.parse_number("1,5"), Some(1.5));
+        assert_eq!(point.parse_number("−2.25"), Some(-2.25));
+        assert_eq!(point.parse_number("1,234,567.5"), Some(1234567.5));
+        assert_eq!(grouped.parse_number("1,234"), Some(1234.0));
+        assert_eq!(grouped.parse_number("1,23"), Some(1.23));
+        assert_eq!(comma.parse_number("1.234,5"), Some(1234.5));
+        assert_eq!(comma.parse_number("1 234 567,5"), Some(1234567.5));
+        assert_eq!(comma.parse_number("1.234"), Some(1234.0));
+        assert_eq!(comma.parse_number("2,5e3"), Some(2500.0));
+        assert_eq!(point.parse_number("1.2.3,4,5"), None);
+        assert_eq!(point.parse_number("abc"), None);
+    }
+}
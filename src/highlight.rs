@@ -0,0 +1,202 @@
+use crate::format::Locale;
+
+/// What a character of the display belongs to, for syntax colouring
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Kind {
+    Number,
+    Operator,
+    Function,
+    Constant,
+    Variable,
+    Bracket,
+    Other,
+}
+
+/// Names shown in the constant colour
+const CONSTANTS: [&str; 3] = ["π", "e", "pi"];
+
+/// Whether `c` is a letter of a name, including the symbols of named
+/// functions (`∫`, `Σ`, `Π`, `Δ`) and combining marks as in x̂
+fn is_name(c: char) -> bool {
+    c.is_alphabetic() || c == '_' || "∫ΣΠΔ".contains(c) || ('\u{0300}'..='\u{036F}').contains(&c)
+}
+
+/// Kind of each character of a display text in `locale`. Digits with
+/// their decimal and group separators, exponents and date and time fields
+/// are numbers; names followed by `(` are functions.
+pub fn classify(text: &str, locale: Locale) -> Vec<Kind> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut kinds = vec![Kind::Other; chars.len()];
+    let digit = |i: usize| chars.get(i).is_some_and(char::is_ascii_digit);
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        let kind = if c.is_ascii_digit() || (c == locale.decimal() && digit(i + 1)) {
+            while i < chars.len() {
+                let number_char = chars[i].is_ascii_digit()
+                    || ((chars[i] == locale.decimal()
+                        || (locale.grouping && chars[i] == locale.group())
+                        || matches!(chars[i], '-' | ':'))
+                        && digit(i + 1)
+                        && i > start)
+                    || (matches!(chars[i], 'e' | 'E')
+                        && (digit(i + 1)
+                            || (matches!(chars.get(i + 1), Some('-' | '−' | '+'))
+                                && digit(i + 2))));
+                if !number_char {
+                    break;
+                }
+                if matches!(chars[i], 'e' | 'E') && !digit(i + 1) {
+                    i += 1;
+                }
+                i += 1;
+            }
+            Kind::Number
+        } else if text[char_offset(&chars, i)..].starts_with("d/d")
+            && chars.get(i + 3).is_some_and(|c| c.is_alphabetic())
+            && chars.get(i + 4) == Some(&'(')
+        {
+            i += 4;
+            Kind::Function
+        } else if c == '√' {
+            i += 1;
+            Kind::Function
+        } else if is_name(c) {
+            while i < chars.len() && (is_name(chars[i]) || chars[i].is_ascii_digit()) {
+                i += 1;
+            }
+            // Δ% is a function name
+            if c == 'Δ' && chars.get(i) == Some(&'%') {
+                i += 1;
+            }
+            let name: String = chars[start..i].iter().collect();
+            if chars.get(i) == Some(&'(') {
+                Kind::Function
+            } else if CONSTANTS.contains(&name.as_str()) {
+                Kind::Constant
+            } else if name == "to" {
+                Kind::Operator
+            } else {
+                Kind::Variable
+            }
+        } else {
+            i += 1;
+            match c {
+                '(' | ')' | '[' | ']' | '⸣' | ',' | ';' => Kind::Bracket,
+                '+' | '-' | '−' | '×' | '÷' | '/' | '*' | '^' | '%' | '·' | '²' | '³' | '=' => {
+                    Kind::Operator
+                }
+                _ => Kind::Other,
+            }
+        };
+        kinds[start..i].fill(kind);
+    }
+    kinds
+}
+
+fn char_offset(chars: &[char], index: usize) -> usize {
+    chars[..index].iter().map(|c| c.len_utf8()).sum()
+}
+
+/// Index of the bracket that matches the one at `index`, if any
+pub fn partner(text: &str, index: usize) -> Option<usize> {
+    let chars: Vec<char> = text.chars().collect();
+    let (open, close, forward) = match chars.get(index)? {
+        '(' => ('(', ')', true),
+        '[' => ('[', ']', true),
+        ')' => ('(', ')', false),
+        ']' => ('[', ']', false),
+        _ => return None,
+    };
+    let mut depth = 0;
+    let indices: Box<dyn Iterator<Item = usize>> = if forward {
+        Box::new(index..chars.len())
+    } else {
+        Box::new((0..=index).rev())
+    };
+    for i in indices {
+        if chars[i] == open {
+            depth += if forward { 1 } else { -1 };
+        } else if chars[i] == close {
+            depth += if forward { -1 } else { 1 };
+        }
+        if depth == 0 {
+            return Some(i);
+        }
+    }
+    None
+}
+
+/// Bracket next to the cursor (before it, else after it) and its partner
+pub fn bracket_at(text: &str, cursor: usize) -> Option<(usize, Option<usize>)> {
+    let chars: Vec<char> = text.chars().collect();
+    [cursor.checked_sub(1), Some(cursor)]
+        .into_iter()
+        .flatten()
+        .find(|&i| chars.get(i).is_some_and(|c| "()[]".contains(*c)))
+        .map(|i| (i, partner(text, i)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Kind::*;
+
+    /// Kinds as letters, one per character
+    fn kinds(text: &str) -> String {
+        classify(text, Locale::default())
+            .iter()
+            .map(|kind| match kind {
+                Number => 'n',
+                Operator => 'o',
+                Function => 'f',
+                Constant => 'c',
+                Variable => 'v',
+                Bracket => 'b',
+                Other => ' ',
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(kinds("2×sin(x)+π"), "nofffbvboc");
+        assert_eq!(kinds("1234.5−1.5e−12"), "nnnnnnonnnnnnn");
+        assert_eq!(kinds("log10(2)^2"), "fffffbnbon");
+        assert_eq!(kinds("√2+x̂(3)"), "fnoffbnb");
+        assert_eq!(kinds("d/dx(x²,x)⸣"), "ffffbvobvbb");
+        assert_eq!(kinds("100 USD to EUR"), "nnn vvv oo vvv");
+        assert_eq!(kinds("2026-03-01+1:45:30"), "nnnnnnnnnnonnnnnnn");
+        assert_eq!(kinds("Δ%(80,100)"), "ffbnnbnnnb");
+        let comma = Locale {
+            decimal_comma: true,
+            grouping: false,
+        };
+        assert_eq!(
+            classify("1,5;2.5", comma)[..4],
+            [Number, Number, Number, Bracket]
+        );
+        let grouped = Locale {
+            decimal_comma: false,
+            grouping: true,
+        };
+        assert!(classify("1,234.5", grouped).iter().all(|&k| k == Number));
+    }
+
+    #[test]
+    fn test_partner() {
+        let text = "sin((1+2)×[3])";
+        assert_eq!(partner(text, 3), Some(13));
+        assert_eq!(partner(text, 13), Some(3));
+        assert_eq!(partner(text, 4), Some(8));
+        assert_eq!(partner(text, 10), Some(12));
+        assert_eq!(partner(text, 0), None);
+        assert_eq!(partner("(1+2", 0), None);
+
+        assert_eq!(bracket_at(text, 9), Some((8, Some(4))));
+        assert_eq!(bracket_at(text, 4), Some((3, Some(13))));
+        assert_eq!(bracket_at(text, 6), None);
+        assert_eq!(bracket_at("2×(3", 2), Some((2, None)));
+    }
+}
@@ -0,0 +1,114 @@
+/// Largest system offered by the solver panel
+pub const MAX_UNKNOWNS: usize = 6;
+
+/// Solve the n×n system A·x = b by Gaussian elimination with partial pivoting
+pub fn solve(a: &[Vec<f64>], b: &[f64]) -> Result<Vec<f64>, String> {
+    let n = b.len();
+    if n == 0 || a.len() != n || a.iter().any(|row| row.len() != n) {
+        return Err("Matrix must be square".to_string());
+    }
+    if a.iter().flatten().chain(b).any(|v| !v.is_finite()) {
+        return Err("Invalid coefficient".to_string());
+    }
+
+    // Augmented matrix [A | b]
+    let mut m: Vec<Vec<f64>> = a
+        .iter()
+        .zip(b)
+        .map(|(row, bi)| {
+            let mut r = row.clone();
+            r.push(*bi);
+            r
+        })
+        .collect();
+
+    let scale = a.iter().flatten().fold(0.0f64, |s, v| s.max(v.abs()));
+    let tolerance = 1e-12 * scale.max(f64::MIN_POSITIVE);
+
+    for col in 0..n {
+        let pivot = (col..n)
+            .max_by(|&i, &j| m[i][col].abs().total_cmp(&m[j][col].abs()))
+            .unwrap();
+        if m[pivot][col].abs() <= tolerance {
+            return Err("No unique solution".to_string());
+        }
+        m.swap(col, pivot);
+
+        let (upper, lower) = m.split_at_mut(col + 1);
+        let pivot_row = &upper[col];
+        for row in lower.iter_mut() {
+            let factor = row[col] / pivot_row[col];
+            if factor != 0.0 {
+                for (v, p) in row[col..].iter_mut().zip(&pivot_row[col..]) {
+                    *v -= factor * p;
+                }
+            }
+        }
+    }
+
+    // Back substitution
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        let sum: f64 = (row + 1..n).map(|k| m[row][k] * x[k]).sum();
+        x[row] = (m[row][n] - sum) / m[row][row];
+    }
+    Ok(x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_two_unknowns() {
+        // 2x + y = 5, x − y = 1
+        let x = solve(&[vec![2.0, 1.0], vec![1.0, -1.0]], &[5.0, 1.0]).unwrap();
+        assert!((x[0] - 2.0).abs() < 1e-12);
+        assert!((x[1] - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_pivoting() {
+        // Zero on the diagonal needs a row swap
+        let x = solve(
+            &[
+                vec![0.0, 2.0, 1.0],
+                vec![1.0, 1.0, 1.0],
+                vec![2.0, 1.0, 3.0],
+            ],
+            &[7.0, 6.0, 13.0],
+        )
+        .unwrap();
+        for (xi, expected) in x.iter().zip([1.0, 2.0, 3.0]) {
+            assert!((xi - expected).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_six_unknowns() {
+        // Diagonally dominant system with known solution 1..=6
+        let n = 6;
+        let a: Vec<Vec<f64>> = (0..n)
+            .map(|i| {
+                (0..n)
+                    .map(|j| if i == j { 10.0 } else { (i + j) as f64 * 0.5 })
+                    .collect()
+            })
+            .collect();
+        let expected: Vec<f64> = (1..=n).map(|v| v as f64).collect();
+        let b: Vec<f64> = a
+            .iter()
+            .map(|row| row.iter().zip(&expected).map(|(aij, xj)| aij * xj).sum())
+            .collect();
+        let x = solve(&a, &b).unwrap();
+        for (xi, e) in x.iter().zip(&expected) {
+            assert!((xi - e).abs() < 1e-10);
+        }
+    }
+
+    #[test]
+    fn test_singular() {
+        assert!(solve(&[vec![1.0, 2.0], vec![2.0, 4.0]], &[3.0, 6.0]).is_err());
+        assert!(solve(&[vec![1.0, 2.0]], &[3.0]).is_err());
+    }
+}
@@ -2,7 +2,29 @@
 
 mod app;
 mod calculator;
+mod calculus;
+mod completion;
+mod complex;
+mod currency;
+mod dates;
+mod distributions;
+mod exact;
+mod expr;
+mod finance;
+mod format;
+mod highlight;
 mod icon;
+mod linear;
+mod matrix;
+mod plot;
+mod polynomial;
+mod regression;
+mod solver;
+mod stats;
+mod symbolic;
+mod table;
+mod typeset;
+mod units;
 
 use app::CalculatorApp;
 use eframe::egui;
@@ -16,8 +38,8 @@ fn main() -> eframe::Result<()> {
 
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
-            .with_inner_size([360.0, 560.0])
-            .with_min_inner_size([350.0, 540.0])
+            .with_inner_size([360.0, 600.0])
+            .with_min_inner_size([350.0, 576.0])
             .with_decorations(false)
             .with_transparent(true)
             .with_title("Rust Calculator")
@@ -0,0 +1,508 @@
+use std::fmt;
+
+use crate::calculator::format_result;
+use crate::expr::{Context, Expr, Op};
+use crate::linear;
+use crate::polynomial;
+
+/// Largest matrix offered by the matrix editor
+pub const MAX_SIZE: usize = 6;
+
+/// Matrix functions; arguments are matrices or vectors
+const FUNCTIONS: [&str; 8] = [
+    "det",
+    "inv",
+    "transpose",
+    "rank",
+    "dot",
+    "cross",
+    "norm",
+    "eig",
+];
+
+const DIMENSION_MISMATCH: &str = "Dimension mismatch";
+const WRONG_ARGUMENTS: &str = "Wrong arguments";
+
+/// Real matrix stored row by row. A vector is a matrix with one column.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Matrix {
+    rows: usize,
+    cols: usize,
+    data: Vec<f64>,
+}
+
+/// Result of an expression with matrices
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Number(f64),
+    Matrix(Matrix),
+}
+
+impl Matrix {
+    /// Matrix from its rows, which must all have the same length
+    pub fn from_rows(rows: &[Vec<f64>]) -> Result<Self, String> {
+        let cols = rows.first().map_or(0, Vec::len);
+        if cols == 0 || rows.iter().any(|row| row.len() != cols) {
+            return Err(DIMENSION_MISMATCH.to_string());
+        }
+        Ok(Self {
+            rows: rows.len(),
+            cols,
+            data: rows.concat(),
+        })
+    }
+
+    fn vector(data: Vec<f64>) -> Self {
+        Self {
+            rows: data.len(),
+            cols: 1,
+            data,
+        }
+    }
+
+    fn identity(n: usize) -> Self {
+        let mut data = vec![0.0; n * n];
+        data.iter_mut().step_by(n + 1).for_each(|v| *v = 1.0);
+        Self {
+            rows: n,
+            cols: n,
+            data,
+        }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> f64 {
+        self.data[row * self.cols + col]
+    }
+
+    fn is_vector(&self) -> bool {
+        self.rows == 1 || self.cols == 1
+    }
+
+    fn is_square(&self) -> bool {
+        self.rows == self.cols
+    }
+
+    fn row(&self, i: usize) -> &[f64] {
+        &self.data[i * self.cols..(i + 1) * self.cols]
+    }
+
+    pub fn is_finite(&self) -> bool {
+        self.data.iter().all(|v| v.is_finite())
+    }
+
+    pub fn transpose(&self) -> Self {
+        Self {
+            rows: self.cols,
+            cols: self.rows,
+            data: (0..self.cols)
+                .flat_map(|j| (0..self.rows).map(move |i| self.get(i, j)))
+                .collect(),
+        }
+    }
+
+    fn scale(&self, factor: f64) -> Self {
+        Self {
+            data: self.data.iter().map(|v| v * factor).collect(),
+            ..self.clone()
+        }
+    }
+
+    /// Element-wise combination of two matrices of the same size
+    fn zip(&self, other: &Self, f: impl Fn(f64, f64) -> f64) -> Result<Self, String> {
+        if (self.rows, self.cols) != (other.rows, other.cols) {
+            return Err(DIMENSION_MISMATCH.to_string());
+        }
+        Ok(Self {
+            data: self
+                .data
+                .iter()
+                .zip(&other.data)
+                .map(|(a, b)| f(*a, *b))
+                .collect(),
+            ..self.clone()
+        })
+    }
+
+    pub fn mul(&self, other: &Self) -> Result<Self, String> {
+        if self.cols != other.rows {
+            return Err(DIMENSION_MISMATCH.to_string());
+        }
+        let data = (0..self.rows)
+            .flat_map(|i| {
+                (0..other.cols).map(move |j| {
+                    (0..self.cols)
+                        .map(|k| self.get(i, k) * other.get(k, j))
+                        .sum()
+                })
+            })
+            .collect();
+        Ok(Self {
+            rows: self.rows,
+            cols: other.cols,
+            data,
+        })
+    }
+
+    /// Integer power of a square matrix; negative powers use the inverse
+    fn pow(&self, exponent: f64) -> Result<Self, String> {
+        if !self.is_square() {
+            return Err("Matrix must be square".to_string());
+        }
+        if exponent.fract() != 0.0 || exponent.abs() > 1e6 {
+            return Err("Exponent must be an integer".to_string());
+        }
+        let mut base = if exponent < 0.0 {
+            self.inverse()?
+        } else {
+            self.clone()
+        };
+        let mut n = exponent.abs() as u64;
+        let mut result = Self::identity(self.rows);
+        while n > 0 {
+            if n % 2 == 1 {
+                result = result.mul(&base)?;
+            }
+            base = base.mul(&base)?;
+            n /= 2;
+        }
+        Ok(result)
+    }
+
+    /// Row echelon form by Gaussian elimination with partial pivoting.
+    /// Returns the rank and the determinant (meaningful for square matrices).
+    fn eliminate(&self) -> (usize, f64) {
+        let mut m: Vec<Vec<f64>> = (0..self.rows).map(|i| self.row(i).to_vec()).collect();
+        let scale = self.data.iter().fold(0.0f64, |s, v| s.max(v.abs()));
+        let tolerance = 1e-12 * scale.max(f64::MIN_POSITIVE);
+
+        let mut rank = 0;
+        let mut det = 1.0;
+        for col in 0..self.cols {
+            if rank == self.rows {
+                break;
+            }
+            let pivot = (rank..self.rows)
+                .max_by(|&i, &j| m[i][col].abs().total_cmp(&m[j][col].abs()))
+                .unwrap();
+            if m[pivot][col].abs() <= tolerance {
+                det = 0.0;
+                continue;
+            }
+            if pivot != rank {
+                m.swap(rank, pivot);
+                det = -det;
+            }
+            det *= m[rank][col];
+
+            let (upper, lower) = m.split_at_mut(rank + 1);
+            let pivot_row = &upper[rank];
+            for row in lower.iter_mut() {
+                let factor = row[col] / pivot_row[col];
+                if factor != 0.0 {
+                    for (v, p) in row[col..].iter_mut().zip(&pivot_row[col..]) {
+                        *v -= factor * p;
+                    }
+                }
+            }
+            rank += 1;
+        }
+        (rank, det)
+    }
+
+    pub fn det(&self) -> Result<f64, String> {
+        if !self.is_square() {
+            return Err("Matrix must be square".to_string());
+        }
+        Ok(self.eliminate().1)
+    }
+
+    pub fn rank(&self) -> usize {
+        self.eliminate().0
+    }
+
+    /// Inverse, column by column from A·x = eᵢ
+    pub fn inverse(&self) -> Result<Self, String> {
+        if !self.is_square() {
+            return Err("Matrix must be square".to_string());
+        }
+        let rows: Vec<Vec<f64>> = (0..self.rows).map(|i| self.row(i).to_vec()).collect();
+        let columns = (0..self.rows)
+            .map(|j| {
+                let unit: Vec<f64> = (0..self.rows).map(|i| f64::from(i == j)).collect();
+                linear::solve(&rows, &unit).map_err(|_| "Singular matrix".to_string())
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self::from_rows(&columns)?.transpose())
+    }
+
+    /// Euclidean norm of a vector (Frobenius norm of a matrix)
+    pub fn norm(&self) -> f64 {
+        self.data.iter().map(|v| v * v).sum::<f64>().sqrt()
+    }
+
+    pub fn dot(&self, other: &Self) -> Result<f64, String> {
+        if !self.is_vector() || !other.is_vector() || self.data.len() != other.data.len() {
+            return Err(DIMENSION_MISMATCH.to_string());
+        }
+        Ok(self.data.iter().zip(&other.data).map(|(a, b)| a * b).sum())
+    }
+
+    pub fn cross(&self, other: &Self) -> Result<Self, String> {
+        match (self.data.as_slice(), other.data.as_slice()) {
+            ([a1, a2, a3], [b1, b2, b3]) => Ok(Self::vector(vec![
+                a2 * b3 - a3 * b2,
+                a3 * b1 - a1 * b3,
+                a1 * b2 - a2 * b1,
+            ])),
+            _ => Err("Vectors must have 3 elements".to_string()),
+        }
+    }
+
+    /// Real eigenvalues in ascending order, as roots of the characteristic
+    /// polynomial (Faddeev–LeVerrier)
+    pub fn eigenvalues(&self) -> Result<Vec<f64>, String> {
+        if !self.is_square() {
+            return Err("Matrix must be square".to_string());
+        }
+        let n = self.rows;
+        if n > polynomial::MAX_DEGREE {
+            return Err("Matrix too large".to_string());
+        }
+
+        // Coefficients of det(λI − A), highest degree first
+        let mut coefficients = vec![1.0];
+        let mut m = Self::identity(n).scale(0.0);
+        for k in 1..=n {
+            m = self
+                .mul(&m)?
+                .zip(&Self::identity(n), |a, i| a + i * coefficients[k - 1])?;
+            let am = self.mul(&m)?;
+            let trace: f64 = (0..n).map(|i| am.get(i, i)).sum();
+            coefficients.push(-trace / k as f64);
+        }
+
+        let roots = polynomial::roots(&coefficients)?;
+        if roots.iter().any(|z| !z.is_real()) {
+            return Err("Complex eigenvalues".to_string());
+        }
+        Ok(roots.iter().map(|z| z.re).collect())
+    }
+}
+
+/// Vectors are written as `[1,2,3]`, other matrices row by row as
+/// `[[1,2],[3,4]]`, so that results can be entered again
+impl fmt::Display for Matrix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let list = |values: &[f64]| {
+            let items: Vec<String> = values.iter().map(|v| format_result(*v)).collect();
+            format!("[{}]", items.join(","))
+        };
+        if self.cols == 1 {
+            return write!(f, "{}", list(&self.data));
+        }
+        let rows: Vec<String> = (0..self.rows).map(|i| list(self.row(i))).collect();
+        write!(f, "[{}]", rows.join(","))
+    }
+}
+
+/// Whether the expression has matrix literals or functions, which the
+/// scalar evaluator does not handle
+pub fn uses_matrices(expr: &Expr) -> bool {
+    match expr {
+        Expr::Num(_) | Expr::Var(_) => false,
+        Expr::List(_) => true,
+        Expr::Neg(a) => uses_matrices(a),
+        Expr::Binary(_, a, b) => uses_matrices(a) || uses_matrices(b),
+        Expr::Call(name, args) => {
+            FUNCTIONS.contains(&name.as_str()) || args.iter().any(uses_matrices)
+        }
+    }
+}
+
+/// Evaluate an expression whose value may be a matrix. Parts without
+/// matrices go to the scalar evaluator.
+pub fn evaluate(expr: &Expr, ctx: &Context) -> Result<Value, String> {
+    if !uses_matrices(expr) {
+        return Ok(Value::Number(expr.eval_with_error(ctx)?.0));
+    }
+    match expr {
+        Expr::List(items) => list(
+            items
+                .iter()
+                .map(|item| evaluate(item, ctx))
+                .collect::<Result<_, _>>()?,
+        ),
+        Expr::Neg(a) => binary(Op::Mul, Value::Number(-1.0), evaluate(a, ctx)?, ctx),
+        Expr::Binary(op, a, b) => binary(*op, evaluate(a, ctx)?, evaluate(b, ctx)?, ctx),
+        Expr::Call(name, args) => {
+            let args = args
+                .iter()
+                .map(|arg| evaluate(arg, ctx))
+                .collect::<Result<Vec<_>, _>>()?;
+            call(name, args, ctx)
+        }
+        _ => unreachable!("expression without matrices"),
+    }
+}
+
+/// `[1,2,3]` is a vector; a list of vectors of the same length is a matrix
+/// with the vectors as its rows
+fn list(items: Vec<Value>) -> Result<Value, String> {
+    if let Some(values) = numbers(&items) {
+        return Ok(Value::Matrix(Matrix::vector(values)));
+    }
+    let rows = items
+        .into_iter()
+        .map(|item| match item {
+            Value::Matrix(m) if m.cols == 1 => Ok(m.data),
+            _ => Err(DIMENSION_MISMATCH.to_string()),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Matrix::from_rows(&rows).map(Value::Matrix)
+}
+
+fn numbers(values: &[Value]) -> Option<Vec<f64>> {
+    values
+        .iter()
+        .map(|value| match value {
+            Value::Number(v) => Some(*v),
+            Value::Matrix(_) => None,
+        })
+        .collect()
+}
+
+fn binary(op: Op, a: Value, b: Value, ctx: &Context) -> Result<Value, String> {
+    use Value::{Matrix as M, Number as N};
+
+    Ok(match (op, a, b) {
+        (op, N(a), N(b)) => {
+            let expr = Expr::Binary(op, Box::new(Expr::Num(a)), Box::new(Expr::Num(b)));
+            N(expr.eval_with_error(ctx)?.0)
+        }
+        (Op::Add, M(a), M(b)) => M(a.zip(&b, |x, y| x + y)?),
+        (Op::Sub, M(a), M(b)) => M(a.zip(&b, |x, y| x - y)?),
+        (Op::Mul, M(a), M(b)) => M(a.mul(&b)?),
+        (Op::Mul, N(k), M(m)) | (Op::Mul, M(m), N(k)) => M(m.scale(k)),
+        (Op::Div, M(m), N(k)) => M(m.scale(1.0 / k)),
+        (Op::Pow, M(m), N(n)) => M(m.pow(n)?),
+        _ => return Err(WRONG_ARGUMENTS.to_string()),
+    })
+}
+
+fn call(name: &str, args: Vec<Value>, ctx: &Context) -> Result<Value, String> {
+    use Value::{Matrix as M, Number as N};
+
+    if !FUNCTIONS.contains(&name) {
+        // Scalar function of matrix results, e.g. abs(det(A))
+        let values = numbers(&args).ok_or_else(|| WRONG_ARGUMENTS.to_string())?;
+        let expr = Expr::Call(
+            name.to_string(),
+            values.into_iter().map(Expr::Num).collect(),
+        );
+        return Ok(N(expr.eval_with_error(ctx)?.0));
+    }
+
+    Ok(match (name, args.as_slice()) {
+        ("det", [M(m)]) => N(m.det()?),
+        ("inv", [M(m)]) => M(m.inverse()?),
+        ("transpose", [M(m)]) => M(m.transpose()),
+        ("rank", [M(m)]) => N(m.rank() as f64),
+        ("norm", [M(m)]) => N(m.norm()),
+        ("dot", [M(a), M(b)]) => N(a.dot(b)?),
+        ("cross", [M(a), M(b)]) => M(a.cross(b)?),
+        ("eig", [M(m)]) => M(Matrix::vector(m.eigenvalues()?)),
+        _ => return Err(WRONG_ARGUMENTS.to_string()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(text: &str) -> Result<Value, String> {
+        evaluate(&text.parse()?, &Context::new())
+    }
+
+    fn matrix(text: &str) -> Matrix {
+        match eval(text) {
+            Ok(Value::Matrix(m)) => m,
+            other => panic!("{} gave {:?}", text, other),
+        }
+    }
+
+    #[test]
+    fn test_literals() {
+        let m = matrix("[[1,2,3],[4,5,6]]");
+        assert_eq!((m.rows(), m.cols()), (2, 3));
+        assert_eq!(m.get(1, 0), 4.0);
+        assert_eq!(m.to_string(), "[[1,2,3],[4,5,6]]");
+        assert_eq!(matrix("[1,2/4,-3]").to_string(), "[1,0.5,-3]");
+        assert_eq!(eval("[[1,2],[3]]"), Err(DIMENSION_MISMATCH.to_string()));
+        assert_eq!(eval("[[1,2],3]"), Err(DIMENSION_MISMATCH.to_string()));
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        assert_eq!(
+            matrix("[[1,2],[3,4]]+[[1,1],[1,1]]").to_string(),
+            "[[2,3],[4,5]]"
+        );
+        assert_eq!(
+            matrix("[[1,2],[3,4]]*[[0,1],[1,0]]").to_string(),
+            "[[2,1],[4,3]]"
+        );
+        assert_eq!(matrix("2[1,2]-[1,1]").to_string(), "[1,3]");
+        assert_eq!(matrix("[[1,2],[3,4]]*[1,1]").to_string(), "[3,7]");
+        assert_eq!(
+            matrix("-[[1,2],[3,4]]/2").to_string(),
+            "[[-0.5,-1],[-1.5,-2]]"
+        );
+        assert_eq!(matrix("[[1,1],[0,1]]^3").to_string(), "[[1,3],[0,1]]");
+        assert_eq!(eval("[1,2]+[1,2,3]"), Err(DIMENSION_MISMATCH.to_string()));
+        assert_eq!(eval("[1,2]+1"), Err(WRONG_ARGUMENTS.to_string()));
+    }
+
+    #[test]
+    fn test_functions() {
+        assert_eq!(eval("det([[1,2],[3,4]])"), Ok(Value::Number(-2.0)));
+        assert_eq!(eval("rank([[1,2],[2,4]])"), Ok(Value::Number(1.0)));
+        assert_eq!(eval("dot([1,2,3],[4,5,6])"), Ok(Value::Number(32.0)));
+        assert_eq!(eval("norm([3,4])"), Ok(Value::Number(5.0)));
+        assert_eq!(eval("abs(det([[1,2],[3,4]]))"), Ok(Value::Number(2.0)));
+        assert_eq!(matrix("cross([1,0,0],[0,1,0])").to_string(), "[0,0,1]");
+        assert_eq!(
+            matrix("transpose([[1,2],[3,4]])").to_string(),
+            "[[1,3],[2,4]]"
+        );
+        assert_eq!(
+            matrix("inv([[4,7],[2,6]])").to_string(),
+            "[[0.6,-0.7],[-0.2,0.4]]"
+        );
+        assert_eq!(
+            eval("inv([[1,2],[2,4]])"),
+            Err("Singular matrix".to_string())
+        );
+        assert_eq!(eval("det([1,2])"), Err("Matrix must be square".to_string()));
+    }
+
+    #[test]
+    fn test_eigenvalues() {
+        assert_eq!(matrix("eig([[2,1],[1,2]])").to_string(), "[1,3]");
+        assert_eq!(
+            matrix("eig([[2,0,0],[0,3,4],[0,4,9]])").to_string(),
+            "[1,2,11]"
+        );
+        assert_eq!(
+            eval("eig([[0,-1],[1,0]])"),
+            Err("Complex eigenvalues".to_string())
+        );
+    }
+}
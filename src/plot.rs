@@ -0,0 +1,331 @@
+use crate::calculus;
+use crate::solver;
+
+/// Parameter values sampled for a curve unless set otherwise
+pub const DEFAULT_STEPS: usize = 500;
+/// Largest number of parameter values sampled for a curve
+pub const MAX_STEPS: usize = 10_000;
+
+/// Visible region of the plot in graph coordinates
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct View {
+    pub x_min: f64,
+    pub x_max: f64,
+    pub y_min: f64,
+    pub y_max: f64,
+}
+
+impl Default for View {
+    fn default() -> Self {
+        Self {
+            x_min: -10.0,
+            x_max: 10.0,
+            y_min: -7.0,
+            y_max: 7.0,
+        }
+    }
+}
+
+impl View {
+    pub fn width(&self) -> f64 {
+        self.x_max - self.x_min
+    }
+
+    pub fn height(&self) -> f64 {
+        self.y_max - self.y_min
+    }
+
+    /// Move the view by `dx`, `dy` in graph coordinates
+    pub fn pan(&mut self, dx: f64, dy: f64) {
+        self.x_min += dx;
+        self.x_max += dx;
+        self.y_min += dy;
+        self.y_max += dy;
+    }
+
+    /// Scale the view by `factor` (< 1 zooms in), keeping `(x, y)` in place
+    pub fn zoom(&mut self, factor: f64, x: f64, y: f64) {
+        // Keep the range between rounding noise and overflow
+        let factor = factor
+            .max(1e-9 / self.width().min(self.height()))
+            .min(1e9 / self.width().max(self.height()));
+        self.x_min = x + (self.x_min - x) * factor;
+        self.x_max = x + (self.x_max - x) * factor;
+        self.y_min = y + (self.y_min - y) * factor;
+        self.y_max = y + (self.y_max - y) * factor;
+    }
+}
+
+/// Marked point on a graph
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FeatureKind {
+    Root,
+    Minimum,
+    Maximum,
+    Intersection,
+}
+
+impl FeatureKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            FeatureKind::Root => "root",
+            FeatureKind::Minimum => "min",
+            FeatureKind::Maximum => "max",
+            FeatureKind::Intersection => "intersection",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Feature {
+    pub kind: FeatureKind,
+    pub x: f64,
+    pub y: f64,
+}
+
+/// Sample `f` at `n` + 1 points across the view. The curve is split into
+/// separate lines where it is undefined and at poles.
+pub fn sample(f: impl Fn(f64) -> f64, view: &View, n: usize) -> Vec<Vec<(f64, f64)>> {
+    let step = view.width() / n as f64;
+    let mut lines = Vec::new();
+    let mut line: Vec<(f64, f64)> = Vec::new();
+
+    for i in 0..=n {
+        let x = view.x_min + step * i as f64;
+        let y = f(x);
+        if !y.is_finite() {
+            if line.len() > 1 {
+                lines.push(std::mem::take(&mut line));
+            }
+            line.clear();
+            continue;
+        }
+        if let Some(&(x0, y0)) = line.last() {
+            // A large jump where the midpoint is not in between is a pole
+            let mid = f(0.5 * (x0 + x));
+            let between = mid >= y0.min(y) && mid <= y0.max(y);
+            if (y - y0).abs() > view.height() && !between {
+                if line.len() > 1 {
+                    lines.push(std::mem::take(&mut line));
+                }
+                line.clear();
+            }
+        }
+        line.push((x, y));
+    }
+    if line.len() > 1 {
+        lines.push(line);
+    }
+    lines
+}
+
+/// Sample the parametric curve (x(t), y(t)) at `steps` + 1 values of t from
+/// `t_min` to `t_max`. The curve is split where it is undefined and where
+/// it jumps by more than the size of the view.
+pub fn sample_curve(
+    x: impl Fn(f64) -> f64,
+    y: impl Fn(f64) -> f64,
+    (t_min, t_max): (f64, f64),
+    steps: usize,
+    view: &View,
+) -> Vec<Vec<(f64, f64)>> {
+    let step = (t_max - t_min) / steps as f64;
+    let jump = view.width().max(view.height());
+    let mut lines = Vec::new();
+    let mut line: Vec<(f64, f64)> = Vec::new();
+
+    for i in 0..=steps {
+        let t = t_min + step * i as f64;
+        let point = (x(t), y(t));
+        let defined = point.0.is_finite() && point.1.is_finite();
+        let jumped = line
+            .last()
+            .is_some_and(|&(x0, y0)| (point.0 - x0).hypot(point.1 - y0) > jump);
+        if !defined || jumped {
+            if line.len() > 1 {
+                lines.push(std::mem::take(&mut line));
+            }
+            line.clear();
+        }
+        if defined {
+            line.push(point);
+        }
+    }
+    if line.len() > 1 {
+        lines.push(line);
+    }
+    lines
+}
+
+/// Sample the polar curve r(θ) for θ from `theta_min` to `theta_max`;
+/// `radians` is the size of one unit of θ in radians (π/180 for degrees)
+pub fn sample_polar(
+    r: impl Fn(f64) -> f64,
+    theta_range: (f64, f64),
+    steps: usize,
+    radians: f64,
+    view: &View,
+) -> Vec<Vec<(f64, f64)>> {
+    sample_curve(
+        |theta| r(theta) * (theta * radians).cos(),
+        |theta| r(theta) * (theta * radians).sin(),
+        theta_range,
+        steps,
+        view,
+    )
+}
+
+/// Roots of `f` in `[lo, hi]`
+pub fn roots(f: impl Fn(f64) -> f64, lo: f64, hi: f64) -> Vec<Feature> {
+    solver::solve(&f, lo, hi, None)
+        .map(|report| report.roots)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|x| Feature {
+            kind: FeatureKind::Root,
+            x,
+            y: 0.0,
+        })
+        .collect()
+}
+
+/// Local minima and maxima of `f` in `[lo, hi]`: roots of the numerical
+/// derivative where `f` is lower (or higher) on both sides
+pub fn extrema(f: impl Fn(f64) -> f64, lo: f64, hi: f64) -> Vec<Feature> {
+    let slope = |x: f64| calculus::derivative(&f, x).value;
+    let h = 1e-4 * (hi - lo);
+    solver::solve(slope, lo, hi, None)
+        .map(|report| report.roots)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|x| {
+            let (left, y, right) = (f(x - h), f(x), f(x + h));
+            let kind = if left > y && right > y {
+                FeatureKind::Minimum
+            } else if left < y && right < y {
+                FeatureKind::Maximum
+            } else {
+                return None;
+            };
+            Some(Feature { kind, x, y })
+        })
+        .collect()
+}
+
+/// Points in `[lo, hi]` where the graphs of `f` and `g` meet
+pub fn intersections(
+    f: impl Fn(f64) -> f64,
+    g: impl Fn(f64) -> f64,
+    lo: f64,
+    hi: f64,
+) -> Vec<Feature> {
+    roots(|x| f(x) - g(x), lo, hi)
+        .into_iter()
+        .map(|root| Feature {
+            kind: FeatureKind::Intersection,
+            x: root.x,
+            y: f(root.x),
+        })
+        .collect()
+}
+
+/// Evenly spaced values in `[lo, hi]` at a round step (1, 2 or 5 times a
+/// power of ten), at most about `max_count` of them
+pub fn ticks(lo: f64, hi: f64, max_count: usize) -> Vec<f64> {
+    let raw = (hi - lo) / max_count as f64;
+    if !raw.is_finite() || raw <= 0.0 {
+        return Vec::new();
+    }
+    let magnitude = 10f64.powf(raw.log10().floor());
+    let step = [1.0, 2.0, 5.0, 10.0]
+        .iter()
+        .map(|m| m * magnitude)
+        .find(|s| *s >= raw)
+        .unwrap_or(10.0 * magnitude);
+    let first = (lo / step).ceil() as i64;
+    let last = (hi / step).floor() as i64;
+    // Multiply instead of adding steps so that 0 is exactly 0
+    (first..=last).map(|i| i as f64 * step).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn test_view() {
+        let mut view = View::default();
+        view.pan(5.0, -2.0);
+        assert_eq!((view.x_min, view.x_max), (-5.0, 15.0));
+        assert_eq!((view.y_min, view.y_max), (-9.0, 5.0));
+
+        let mut view = View::default();
+        view.zoom(0.5, 10.0, 0.0);
+        assert_eq!((view.x_min, view.x_max), (0.0, 10.0));
+        assert_eq!((view.y_min, view.y_max), (-3.5, 3.5));
+    }
+
+    #[test]
+    fn test_sample_splits_at_poles() {
+        let view = View::default();
+        // tan has poles at ±π/2, ±3π/2 and ±5π/2 within [−10, 10]
+        assert_eq!(sample(f64::tan, &view, 400).len(), 7);
+        assert_eq!(sample(|x| x * x, &view, 400).len(), 1);
+        // sqrt is undefined left of 0
+        let lines = sample(f64::sqrt, &view, 400);
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0][0].0 >= 0.0);
+    }
+
+    #[test]
+    fn test_curves() {
+        let view = View::default();
+        let circle = sample_curve(f64::cos, f64::sin, (0.0, 2.0 * PI), 100, &view);
+        assert_eq!(circle.len(), 1);
+        assert_eq!(circle[0].len(), 101);
+        assert!((circle[0][25].0).abs() < 1e-12 && (circle[0][25].1 - 1.0).abs() < 1e-12);
+
+        // Cardioid r = 1 + cos θ in degrees: θ = 90 is the point (0, 1)
+        let cardioid = sample_polar(
+            |t| 1.0 + t.to_radians().cos(),
+            (0.0, 360.0),
+            360,
+            PI / 180.0,
+            &view,
+        );
+        let (x, y) = cardioid[0][90];
+        assert!(x.abs() < 1e-12 && (y - 1.0).abs() < 1e-12);
+
+        // Undefined parameter values split the curve
+        let split = sample_curve(|t| t, |t| (t * t - 1.0).sqrt(), (-2.0, 2.0), 40, &view);
+        assert_eq!(split.len(), 2);
+    }
+
+    #[test]
+    fn test_features() {
+        let f = |x: f64| x * x * x - 3.0 * x;
+        let zeros: Vec<f64> = roots(f, -5.0, 5.0).iter().map(|r| r.x).collect();
+        assert_eq!(zeros.len(), 3);
+        assert!((zeros[2] - 3f64.sqrt()).abs() < 1e-9);
+
+        let turns = extrema(f, -5.0, 5.0);
+        assert_eq!(turns.len(), 2);
+        assert_eq!(turns[0].kind, FeatureKind::Maximum);
+        assert!((turns[0].x + 1.0).abs() < 1e-7);
+        assert!((turns[1].y + 2.0).abs() < 1e-9);
+
+        // x³ has a flat point at 0 that is no extremum
+        assert!(extrema(|x| x * x * x, -5.0, 5.0).is_empty());
+
+        let meets = intersections(|x| x * x, |x| x + 2.0, -5.0, 5.0);
+        assert_eq!(meets.len(), 2);
+        assert!((meets[1].x - 2.0).abs() < 1e-9 && (meets[1].y - 4.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_ticks() {
+        assert_eq!(ticks(-10.0, 10.0, 5), vec![-10.0, -5.0, 0.0, 5.0, 10.0]);
+        assert_eq!(ticks(0.05, 0.5, 4), vec![0.2, 0.4]);
+    }
+}
@@ -0,0 +1,260 @@
+use crate::complex::Complex;
+
+pub const MAX_DEGREE: usize = 6;
+
+const MAX_ITERATIONS: usize = 500;
+
+/// Roots of a polynomial given by its coefficients, highest degree first
+/// (`[1, −5, 6]` is x² − 5x + 6). Real roots come first in ascending order,
+/// followed by complex roots.
+pub fn roots(coefficients: &[f64]) -> Result<Vec<Complex>, String> {
+    if coefficients.iter().any(|c| !c.is_finite()) {
+        return Err("Invalid coefficient".to_string());
+    }
+
+    // Drop leading zeros; the degree is what remains
+    let start = coefficients
+        .iter()
+        .position(|c| *c != 0.0)
+        .ok_or_else(|| "All coefficients are zero".to_string())?;
+    let mut coef = coefficients[start..].to_vec();
+    if coef.len() < 2 {
+        return Err("Degree must be at least 1".to_string());
+    }
+    if coef.len() - 1 > MAX_DEGREE {
+        return Err(format!("Degree must be at most {}", MAX_DEGREE));
+    }
+
+    // Trailing zeros are roots at 0
+    let mut result = Vec::new();
+    while coef.len() > 1 && *coef.last().unwrap() == 0.0 {
+        coef.pop();
+        result.push(Complex::new(0.0, 0.0));
+    }
+
+    // Make the polynomial monic
+    let lead = coef[0];
+    let monic: Vec<Complex> = coef.iter().map(|c| Complex::new(c / lead, 0.0)).collect();
+    let degree = monic.len() - 1;
+
+    if degree > 0 {
+        let mut found = durand_kerner(&monic);
+        for root in found.iter_mut() {
+            *root = polish(&monic, *root, 3);
+        }
+        merge_clusters(&monic, &mut found);
+        for root in found.iter_mut() {
+            // Treat a negligible imaginary part as rounding noise
+            if root.im.abs() <= 1e-9 * (1.0 + root.re.abs()) {
+                root.im = 0.0;
+            }
+            if root.re.abs() <= 1e-14 * (1.0 + root.im.abs()) {
+                root.re = 0.0;
+            }
+        }
+        result.extend(found);
+    }
+
+    result.sort_by(|a, b| {
+        b.is_real()
+            .cmp(&a.is_real())
+            .then(a.re.total_cmp(&b.re))
+            .then(a.im.total_cmp(&b.im))
+    });
+    Ok(result)
+}
+
+/// Evaluate a polynomial (highest degree first) at z with Horner's scheme
+fn eval(coef: &[Complex], z: Complex) -> Complex {
+    coef.iter().fold(Complex::default(), |acc, c| acc * z + *c)
+}
+
+/// Size of the rounding error of `eval` at z; residuals below it are noise
+fn rounding_floor(coef: &[Complex], z: Complex) -> f64 {
+    let r = z.abs();
+    let magnitude = coef.iter().fold(0.0, |acc, c| acc * r + c.abs());
+    8.0 * f64::EPSILON * magnitude
+}
+
+/// Simultaneous iteration on all roots of a monic polynomial
+fn durand_kerner(monic: &[Complex]) -> Vec<Complex> {
+    let degree = monic.len() - 1;
+
+    // Start on a circle enclosing all roots (Cauchy bound)
+    let radius = 1.0 + monic[1..].iter().map(|c| c.abs()).fold(0.0, f64::max);
+    let seed = Complex::new(0.4, 0.9);
+    let mut z: Vec<Complex> = Vec::with_capacity(degree);
+    let mut power = Complex::new(radius.min(2.0), 0.0);
+    for _ in 0..degree {
+        z.push(power);
+        power = power * seed;
+    }
+
+    for _ in 0..MAX_ITERATIONS {
+        let mut max_step: f64 = 0.0;
+        for i in 0..degree {
+            let mut denom = Complex::new(1.0, 0.0);
+            for j in 0..degree {
+                if i != j {
+                    denom = denom * (z[i] - z[j]);
+                }
+            }
+            if denom.abs() == 0.0 {
+                denom = Complex::new(1e-12, 1e-12);
+            }
+            let step = eval(monic, z[i]) / denom;
+            z[i] = z[i] - step;
+            max_step = max_step.max(step.abs() / (1.0 + z[i].abs()));
+        }
+        if max_step < 1e-15 {
+            break;
+        }
+    }
+
+    z
+}
+
+/// Multiple roots converge slowly and end up as a small cluster around the
+/// true value. An m-fold root is a simple root of the (m−1)th derivative, so
+/// the cluster is replaced by that root, found by Newton from the cluster mean.
+fn merge_clusters(monic: &[Complex], roots: &mut [Complex]) {
+    let mut assigned = vec![false; roots.len()];
+    for i in 0..roots.len() {
+        if assigned[i] {
+            continue;
+        }
+        let cluster: Vec<usize> = (i..roots.len())
+            .filter(|&j| {
+                !assigned[j] && (roots[j] - roots[i]).abs() < 1e-3 * (1.0 + roots[i].abs())
+            })
+            .collect();
+        for &j in &cluster {
+            assigned[j] = true;
+        }
+        if cluster.len() < 2 {
+            continue;
+        }
+
+        let sum = cluster
+            .iter()
+            .fold(Complex::default(), |acc, &j| acc + roots[j]);
+        let mean = sum / Complex::new(cluster.len() as f64, 0.0);
+        let mut d = monic.to_vec();
+        for _ in 1..cluster.len() {
+            d = derivative(&d);
+        }
+        let merged = polish(&d, mean, 20);
+
+        // Distinct but close roots would get a worse residual from merging
+        let worst = cluster
+            .iter()
+            .map(|&j| eval(monic, roots[j]).abs())
+            .fold(0.0, f64::max);
+        if eval(monic, merged).abs() <= worst.max(rounding_floor(monic, merged)) {
+            for &j in &cluster {
+                roots[j] = merged;
+            }
+        }
+    }
+}
+
+fn derivative(coef: &[Complex]) -> Vec<Complex> {
+    let degree = coef.len() - 1;
+    coef[..degree]
+        .iter()
+        .enumerate()
+        .map(|(i, c)| *c * Complex::new((degree - i) as f64, 0.0))
+        .collect()
+}
+
+/// Newton steps to sharpen a root of `coef`
+fn polish(coef: &[Complex], mut z: Complex, steps: usize) -> Complex {
+    if coef.len() < 2 {
+        return z;
+    }
+    let derivative = derivative(coef);
+
+    for _ in 0..steps {
+        let d = eval(&derivative, z);
+        if d.abs() == 0.0 {
+            break;
+        }
+        let next = z - eval(coef, z) / d;
+        if !next.re.is_finite() || !next.im.is_finite() {
+            break;
+        }
+        // Only accept steps that make the residual smaller
+        if eval(coef, next).abs() >= eval(coef, z).abs() {
+            break;
+        }
+        z = next;
+    }
+    z
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx(z: Complex, re: f64, im: f64) -> bool {
+        (z.re - re).abs() < 1e-9 && (z.im - im).abs() < 1e-9
+    }
+
+    #[test]
+    fn test_quadratic() {
+        let r = roots(&[1.0, -5.0, 6.0]).unwrap();
+        assert_eq!(r.len(), 2);
+        assert!(approx(r[0], 2.0, 0.0));
+        assert!(approx(r[1], 3.0, 0.0));
+    }
+
+    #[test]
+    fn test_complex_roots() {
+        // x² + 2x + 5 = 0 → −1 ± 2i
+        let r = roots(&[1.0, 2.0, 5.0]).unwrap();
+        assert!(approx(r[0], -1.0, -2.0));
+        assert!(approx(r[1], -1.0, 2.0));
+        assert!(!r[0].is_real());
+    }
+
+    #[test]
+    fn test_degree_six() {
+        // (x−1)(x−2)(x−3)(x+1)(x²+1)
+        let r = roots(&[1.0, -5.0, 6.0, 0.0, -1.0, 5.0, -6.0]).unwrap();
+        assert_eq!(r.len(), 6);
+        assert!(approx(r[0], -1.0, 0.0));
+        assert!(approx(r[1], 1.0, 0.0));
+        assert!(approx(r[2], 2.0, 0.0));
+        assert!(approx(r[3], 3.0, 0.0));
+        assert!(approx(r[4], 0.0, -1.0));
+        assert!(approx(r[5], 0.0, 1.0));
+    }
+
+    #[test]
+    fn test_multiple_roots() {
+        // (x − 1)⁴
+        let r = roots(&[1.0, -4.0, 6.0, -4.0, 1.0]).unwrap();
+        assert!(r.iter().all(|z| z.is_real() && (z.re - 1.0).abs() < 1e-9));
+
+        // (x² + 1)²
+        let r = roots(&[1.0, 0.0, 2.0, 0.0, 1.0]).unwrap();
+        assert!(approx(r[0], 0.0, -1.0) && approx(r[1], 0.0, -1.0));
+        assert!(approx(r[2], 0.0, 1.0) && approx(r[3], 0.0, 1.0));
+    }
+
+    #[test]
+    fn test_zero_and_leading_zero_coefficients() {
+        // 0x³ + x² − x = x(x − 1)
+        let r = roots(&[0.0, 1.0, -1.0, 0.0]).unwrap();
+        assert_eq!(r.len(), 2);
+        assert!(approx(r[0], 0.0, 0.0));
+        assert!(approx(r[1], 1.0, 0.0));
+    }
+
+    #[test]
+    fn test_invalid_polynomials() {
+        assert!(roots(&[0.0, 0.0]).is_err());
+        assert!(roots(&[5.0]).is_err());
+        assert!(roots(&[1.0; 8]).is_err());
+    }
+}
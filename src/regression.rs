@@ -0,0 +1,366 @@
+/// Regression model for paired (x, y) data
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Model {
+    /// y = a + b·x
+    #[default]
+    Linear,
+    /// y = a + b·x + c·x²
+    Quadratic,
+    /// y = a·e^(b·x)
+    Exponential,
+    /// y = a + b·ln(x)
+    Logarithmic,
+    /// y = a·x^b
+    Power,
+}
+
+impl Model {
+    pub const ALL: [Model; 5] = [
+        Model::Linear,
+        Model::Quadratic,
+        Model::Exponential,
+        Model::Logarithmic,
+        Model::Power,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Model::Linear => "LIN",
+            Model::Quadratic => "QUAD",
+            Model::Exponential => "EXP",
+            Model::Logarithmic => "LOG",
+            Model::Power => "PWR",
+        }
+    }
+
+    pub fn formula(self) -> &'static str {
+        match self {
+            Model::Linear => "y = a + bx",
+            Model::Quadratic => "y = a + bx + cx²",
+            Model::Exponential => "y = a·e^(bx)",
+            Model::Logarithmic => "y = a + b·ln(x)",
+            Model::Power => "y = a·x^b",
+        }
+    }
+}
+
+/// Fitted regression curve
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Fit {
+    pub model: Model,
+    pub a: f64,
+    pub b: f64,
+    /// Only used by the quadratic model
+    pub c: f64,
+    /// Correlation coefficient. For the exponential, logarithmic and power
+    /// models it is the r of the linearised data; for the quadratic model it
+    /// is the (non-negative) multiple correlation coefficient.
+    pub r: f64,
+    pub r2: f64,
+}
+
+impl Fit {
+    /// Predicted y for a given x (ŷ)
+    pub fn predict(&self, x: f64) -> f64 {
+        match self.model {
+            Model::Linear => self.a + self.b * x,
+            Model::Quadratic => self.a + self.b * x + self.c * x * x,
+            Model::Exponential => self.a * (self.b * x).exp(),
+            Model::Logarithmic => self.a + self.b * x.ln(),
+            Model::Power => self.a * x.powf(self.b),
+        }
+    }
+
+    /// Estimated x for a given y (x̂). For the quadratic model this is the
+    /// root (−b + √D) / 2c.
+    pub fn estimate_x(&self, y: f64) -> f64 {
+        match self.model {
+            Model::Linear => (y - self.a) / self.b,
+            Model::Quadratic => {
+                if self.c == 0.0 {
+                    return (y - self.a) / self.b;
+                }
+                let d = self.b * self.b - 4.0 * self.c * (self.a - y);
+                (-self.b + d.sqrt()) / (2.0 * self.c)
+            }
+            Model::Exponential => (y / self.a).ln() / self.b,
+            Model::Logarithmic => ((y - self.a) / self.b).exp(),
+            Model::Power => (y / self.a).powf(1.0 / self.b),
+        }
+    }
+}
+
+/// Fit a regression model to paired data, each pair counted `weights`
+/// times
+pub fn fit(model: Model, xs: &[f64], ys: &[f64], weights: &[f64]) -> Result<Fit, String> {
+    if xs.len() != ys.len() || xs.len() != weights.len() {
+        return Err("Mismatched data".to_string());
+    }
+    let min_points = if model == Model::Quadratic { 3.0 } else { 2.0 };
+    if weights.iter().sum::<f64>() < min_points {
+        return Err("Not enough data".to_string());
+    }
+
+    match model {
+        Model::Linear => {
+            let (a, b, r) = linear_fit(xs, ys, weights)?;
+            Ok(Fit {
+                model,
+                a,
+                b,
+                c: 0.0,
+                r,
+                r2: r * r,
+            })
+        }
+        Model::Quadratic => quadratic_fit(xs, ys, weights),
+        Model::Exponential => {
+            let ln_y = ln_all(ys, "y must be positive")?;
+            let (ln_a, b, r) = linear_fit(xs, &ln_y, weights)?;
+            Ok(Fit {
+                model,
+                a: ln_a.exp(),
+                b,
+                c: 0.0,
+                r,
+                r2: r * r,
+            })
+        }
+        Model::Logarithmic => {
+            let ln_x = ln_all(xs, "x must be positive")?;
+            let (a, b, r) = linear_fit(&ln_x, ys, weights)?;
+            Ok(Fit {
+                model,
+                a,
+                b,
+                c: 0.0,
+                r,
+                r2: r * r,
+            })
+        }
+        Model::Power => {
+            let ln_x = ln_all(xs, "x must be positive")?;
+            let ln_y = ln_all(ys, "y must be positive")?;
+            let (ln_a, b, r) = linear_fit(&ln_x, &ln_y, weights)?;
+            Ok(Fit {
+                model,
+                a: ln_a.exp(),
+                b,
+                c: 0.0,
+                r,
+                r2: r * r,
+            })
+        }
+    }
+}
+
+fn ln_all(values: &[f64], err: &str) -> Result<Vec<f64>, String> {
+    if values.iter().any(|v| *v <= 0.0) {
+        return Err(err.to_string());
+    }
+    Ok(values.iter().map(|v| v.ln()).collect())
+}
+
+/// Weighted least-squares line y = a + b·x, returning (a, b, r)
+fn linear_fit(xs: &[f64], ys: &[f64], weights: &[f64]) -> Result<(f64, f64, f64), String> {
+    let n: f64 = weights.iter().sum();
+    let mean_x = xs.iter().zip(weights).map(|(x, w)| x * w).sum::<f64>() / n;
+    let mean_y = ys.iter().zip(weights).map(|(y, w)| y * w).sum::<f64>() / n;
+
+    let mut sxx = 0.0;
+    let mut syy = 0.0;
+    let mut sxy = 0.0;
+    for ((x, y), w) in xs.iter().zip(ys).zip(weights) {
+        sxx += w * (x - mean_x) * (x - mean_x);
+        syy += w * (y - mean_y) * (y - mean_y);
+        sxy += w * (x - mean_x) * (y - mean_y);
+    }
+
+    if sxx == 0.0 {
+        return Err("x values are all equal".to_string());
+    }
+
+    let b = sxy / sxx;
+    let a = mean_y - b * mean_x;
+    // A horizontal line fits perfectly but has no defined correlation
+    let r = if syy == 0.0 {
+        f64::NAN
+    } else {
+        sxy / (sxx * syy).sqrt()
+    };
+
+    Ok((a, b, r))
+}
+
+fn quadratic_fit(xs: &[f64], ys: &[f64], weights: &[f64]) -> Result<Fit, String> {
+    // Normal equations for y = a + b·x + c·x², each sum weighted
+    let mut s = [0.0; 5]; // Σx⁰ .. Σx⁴
+    let mut t = [0.0; 3]; // Σy, Σxy, Σx²y
+    for ((x, y), w) in xs.iter().zip(ys).zip(weights) {
+        let mut p = *w;
+        for (k, sk) in s.iter_mut().enumerate() {
+            *sk += p;
+            if k < 3 {
+                t[k] += p * y;
+            }
+            p *= x;
+        }
+    }
+
+    let m = [[s[0], s[1], s[2]], [s[1], s[2], s[3]], [s[2], s[3], s[4]]];
+    let det = det3(&m);
+    if det.abs() < 1e-12 * s[4].abs().max(1.0) {
+        return Err("Need 3 distinct x values".to_string());
+    }
+
+    // Cramer's rule
+    let mut coef = [0.0; 3];
+    for (i, c) in coef.iter_mut().enumerate() {
+        let mut mi = m;
+        for row in 0..3 {
+            mi[row][i] = t[row];
+        }
+        *c = det3(&mi) / det;
+    }
+
+    let fit = Fit {
+        model: Model::Quadratic,
+        a: coef[0],
+        b: coef[1],
+        c: coef[2],
+        r: 0.0,
+        r2: 0.0,
+    };
+
+    let mean_y = t[0] / s[0];
+    let ss_tot: f64 = ys
+        .iter()
+        .zip(weights)
+        .map(|(y, w)| w * (y - mean_y).powi(2))
+        .sum();
+    let ss_res: f64 = xs
+        .iter()
+        .zip(ys)
+        .zip(weights)
+        .map(|((x, y), w)| w * (y - fit.predict(*x)).powi(2))
+        .sum();
+    let r2 = if ss_tot == 0.0 {
+        f64::NAN
+    } else {
+        (1.0 - ss_res / ss_tot).max(0.0)
+    };
+
+    Ok(Fit {
+        r: r2.sqrt(),
+        r2,
+        ..fit
+    })
+}
+
+fn det3(m: &[[f64; 3]; 3]) -> f64 {
+    m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+        - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+        + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn test_linear() {
+        let xs = [1.0, 2.0, 3.0, 4.0, 5.0];
+        let ys = [2.0, 4.0, 5.0, 4.0, 5.0];
+        let f = fit(Model::Linear, &xs, &ys, &[1.0; 5]).unwrap();
+        assert!(approx(f.a, 2.2));
+        assert!(approx(f.b, 0.6));
+        assert!(approx(f.r, 0.7745966692));
+        assert!(approx(f.r2, 0.6));
+        assert!(approx(f.predict(6.0), 5.8));
+        assert!(approx(f.estimate_x(5.8), 6.0));
+    }
+
+    #[test]
+    fn test_quadratic_exact() {
+        let xs = [-1.0, 0.0, 1.0, 2.0, 3.0];
+        let ys: Vec<f64> = xs.iter().map(|x| 1.0 - 2.0 * x + 0.5 * x * x).collect();
+        let f = fit(Model::Quadratic, &xs, &ys, &[1.0; 5]).unwrap();
+        assert!(approx(f.a, 1.0));
+        assert!(approx(f.b, -2.0));
+        assert!(approx(f.c, 0.5));
+        assert!(approx(f.r2, 1.0));
+        assert!(approx(f.predict(4.0), 1.0));
+    }
+
+    #[test]
+    fn test_exponential_and_power() {
+        let xs = [1.0, 2.0, 3.0, 4.0];
+        let ys: Vec<f64> = xs.iter().map(|x: &f64| 3.0 * (0.5 * x).exp()).collect();
+        let f = fit(Model::Exponential, &xs, &ys, &[1.0; 4]).unwrap();
+        assert!(approx(f.a, 3.0));
+        assert!(approx(f.b, 0.5));
+        assert!(approx(f.r, 1.0));
+        assert!(approx(f.estimate_x(f.predict(2.5)), 2.5));
+
+        let ys: Vec<f64> = xs.iter().map(|x: &f64| 2.0 * x.powf(1.5)).collect();
+        let f = fit(Model::Power, &xs, &ys, &[1.0; 4]).unwrap();
+        assert!(approx(f.a, 2.0));
+        assert!(approx(f.b, 1.5));
+    }
+
+    #[test]
+    fn test_logarithmic() {
+        let xs = [1.0, 2.0, 4.0, 8.0];
+        let ys: Vec<f64> = xs.iter().map(|x: &f64| 1.0 + 2.0 * x.ln()).collect();
+        let f = fit(Model::Logarithmic, &xs, &ys, &[1.0; 4]).unwrap();
+        assert!(approx(f.a, 1.0));
+        assert!(approx(f.b, 2.0));
+        assert!(approx(f.estimate_x(f.predict(3.0)), 3.0));
+    }
+
+    #[test]
+    fn test_weights() {
+        // Weights count each pair as repeated
+        let weighted = fit(
+            Model::Quadratic,
+            &[0.0, 1.0, 3.0],
+            &[1.0, 2.0, 7.0],
+            &[2.0, 1.0, 3.0],
+        );
+        let xs = [0.0, 0.0, 1.0, 3.0, 3.0, 3.0];
+        let ys = [1.0, 1.0, 2.0, 7.0, 7.0, 7.0];
+        let repeated = fit(Model::Quadratic, &xs, &ys, &[1.0; 6]);
+        assert_eq!(weighted, repeated);
+        let f = fit(
+            Model::Linear,
+            &[1.0, 2.0, 4.0],
+            &[1.0, 3.0, 4.0],
+            &[1.0, 2.0, 1.0],
+        )
+        .unwrap();
+        let g = fit(
+            Model::Linear,
+            &[1.0, 2.0, 2.0, 4.0],
+            &[1.0, 3.0, 3.0, 4.0],
+            &[1.0; 4],
+        )
+        .unwrap();
+        assert!(approx(f.a, g.a) && approx(f.b, g.b) && approx(f.r, g.r));
+        // Two points, one counted twice, are not enough for a parabola
+        assert!(fit(Model::Quadratic, &[1.0, 2.0], &[1.0, 3.0], &[2.0, 1.0]).is_err());
+        assert!(fit(Model::Linear, &[1.0, 2.0], &[1.0, 3.0], &[1.0]).is_err());
+    }
+
+    #[test]
+    fn test_invalid_data() {
+        assert!(fit(Model::Linear, &[1.0], &[2.0], &[1.0]).is_err());
+        assert!(fit(Model::Linear, &[2.0, 2.0], &[1.0, 3.0], &[1.0; 2]).is_err());
+        assert!(fit(Model::Quadratic, &[1.0, 2.0], &[1.0, 3.0], &[1.0; 2]).is_err());
+        assert!(fit(Model::Exponential, &[1.0, 2.0], &[-1.0, 3.0], &[1.0; 2]).is_err());
+        assert!(fit(Model::Power, &[0.0, 2.0], &[1.0, 3.0], &[1.0; 2]).is_err());
+    }
+}
@@ -0,0 +1,246 @@
+/// Number of sub-intervals scanned for sign changes
+const SCAN_STEPS: usize = 400;
+const MAX_ITERATIONS: usize = 100;
+/// Largest |f(x)| still accepted as a root
+const RESIDUAL_TOLERANCE: f64 = 1e-7;
+
+/// Roots found by `solve` plus notes about attempts that failed
+#[derive(Debug, Default)]
+pub struct Report {
+    pub roots: Vec<f64>,
+    pub warnings: Vec<String>,
+}
+
+/// Find the roots of `f` in `[lo, hi]`.
+///
+/// The interval is scanned for sign changes, each bracket is refined with
+/// Brent's method, and local minima of |f| are polished with Newton's method
+/// to catch roots that touch zero without crossing it. If an initial guess is
+/// given, Newton's method is also started from there (the root may then lie
+/// outside the interval).
+pub fn solve(
+    f: impl Fn(f64) -> f64,
+    lo: f64,
+    hi: f64,
+    guess: Option<f64>,
+) -> Result<Report, String> {
+    if !lo.is_finite() || !hi.is_finite() || lo >= hi {
+        return Err("Invalid interval".to_string());
+    }
+
+    let mut report = Report::default();
+    let step = (hi - lo) / SCAN_STEPS as f64;
+    let samples: Vec<(f64, f64)> = (0..=SCAN_STEPS)
+        .map(|i| {
+            let x = lo + step * i as f64;
+            (x, f(x))
+        })
+        .collect();
+
+    for (i, window) in samples.windows(2).enumerate() {
+        let (a, fa) = window[0];
+        let (b, fb) = window[1];
+        if !fa.is_finite() || !fb.is_finite() {
+            continue;
+        }
+
+        if fa == 0.0 {
+            push_root(&mut report.roots, a);
+        } else if fa.signum() != fb.signum() && fb != 0.0 {
+            match brent(&f, a, b, fa, fb) {
+                Some(x) if f(x).abs() <= RESIDUAL_TOLERANCE => push_root(&mut report.roots, x),
+                Some(x) => report
+                    .warnings
+                    .push(format!("Sign change at x ≈ {} is not a root", short(x))),
+                None => report
+                    .warnings
+                    .push(format!("No convergence near x ≈ {}", short(0.5 * (a + b)))),
+            }
+        } else if i > 0 {
+            // Touching root: |f| has a local minimum without a sign change
+            let (_, f_prev) = samples[i - 1];
+            if f_prev.is_finite() && fa.abs() < f_prev.abs() && fa.abs() <= fb.abs() {
+                if let Some(x) = newton(&f, a) {
+                    if (lo..=hi).contains(&x) {
+                        push_root(&mut report.roots, x);
+                    }
+                }
+            }
+        }
+    }
+    if let Some(&(b, fb)) = samples.last() {
+        if fb == 0.0 {
+            push_root(&mut report.roots, b);
+        }
+    }
+
+    if let Some(x0) = guess {
+        match newton(&f, x0) {
+            Some(x) => push_root(&mut report.roots, x),
+            None => report
+                .warnings
+                .push(format!("No convergence from initial guess {}", short(x0))),
+        }
+    }
+
+    report.roots.sort_by(|a, b| a.total_cmp(b));
+    Ok(report)
+}
+
+/// Add a root unless an equal one (within tolerance) is already present
+fn push_root(roots: &mut Vec<f64>, x: f64) {
+    // Snap values like 2.0000000000000004 to the nearby round number
+    let rounded = (x * 1e9).round() / 1e9;
+    let x = if (x - rounded).abs() < 1e-12 {
+        rounded
+    } else {
+        x
+    };
+
+    if !roots
+        .iter()
+        .any(|r| (r - x).abs() <= 1e-7 * (1.0 + x.abs()))
+    {
+        roots.push(x);
+    }
+}
+
+/// Number with at most 6 decimals, for messages and labels
+pub fn short(x: f64) -> String {
+    let formatted = format!("{:.6}", x);
+    formatted
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+/// Brent's method on a bracket with f(a)·f(b) < 0
+fn brent(f: &impl Fn(f64) -> f64, mut a: f64, mut b: f64, mut fa: f64, mut fb: f64) -> Option<f64> {
+    if fa.abs() < fb.abs() {
+        std::mem::swap(&mut a, &mut b);
+        std::mem::swap(&mut fa, &mut fb);
+    }
+    let mut c = a;
+    let mut fc = fa;
+    let mut d = b - a;
+    let mut bisected = true;
+
+    for _ in 0..MAX_ITERATIONS {
+        if fb == 0.0 || (b - a).abs() <= 4.0 * f64::EPSILON * b.abs().max(1e-300) {
+            return Some(b);
+        }
+
+        let mut s = if fa != fc && fb != fc {
+            // Inverse quadratic interpolation
+            a * fb * fc / ((fa - fb) * (fa - fc))
+                + b * fa * fc / ((fb - fa) * (fb - fc))
+                + c * fa * fb / ((fc - fa) * (fc - fb))
+        } else {
+            // Secant
+            b - fb * (b - a) / (fb - fa)
+        };
+
+        let lower = (3.0 * a + b) / 4.0;
+        let use_bisection = !((lower.min(b) < s) && (s < lower.max(b)))
+            || (bisected && (s - b).abs() >= (b - c).abs() / 2.0)
+            || (!bisected && (s - b).abs() >= (c - d).abs() / 2.0);
+        if use_bisection {
+            s = (a + b) / 2.0;
+        }
+        bisected = use_bisection;
+
+        let fs = f(s);
+        if !fs.is_finite() {
+            return None;
+        }
+        d = c;
+        c = b;
+        fc = fb;
+
+        if fa.signum() != fs.signum() {
+            b = s;
+            fb = fs;
+        } else {
+            a = s;
+            fa = fs;
+        }
+        if fa.abs() < fb.abs() {
+            std::mem::swap(&mut a, &mut b);
+            std::mem::swap(&mut fa, &mut fb);
+        }
+    }
+
+    None
+}
+
+/// Newton's method with a central-difference derivative
+fn newton(f: &impl Fn(f64) -> f64, x0: f64) -> Option<f64> {
+    let mut x = x0;
+    for _ in 0..MAX_ITERATIONS {
+        let fx = f(x);
+        if !fx.is_finite() {
+            return None;
+        }
+        if fx == 0.0 {
+            return Some(x);
+        }
+        let h = 1e-6 * x.abs().max(1.0);
+        let dfx = (f(x + h) - f(x - h)) / (2.0 * h);
+        if dfx == 0.0 || !dfx.is_finite() {
+            break;
+        }
+        let next = x - fx / dfx;
+        if (next - x).abs() <= 1e-12 * next.abs().max(1.0) {
+            x = next;
+            break;
+        }
+        x = next;
+    }
+
+    let fx = f(x);
+    (fx.is_finite() && fx.abs() <= RESIDUAL_TOLERANCE).then_some(x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quadratic_roots() {
+        let report = solve(|x| x * x - 5.0 * x + 6.0, -10.0, 10.0, None).unwrap();
+        assert_eq!(report.roots, vec![2.0, 3.0]);
+        assert!(report.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_touching_root() {
+        let report = solve(|x| (x - 1.5) * (x - 1.5), -10.0, 10.0, None).unwrap();
+        assert_eq!(report.roots.len(), 1);
+        assert!((report.roots[0] - 1.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_pole_is_not_a_root() {
+        let report = solve(|x| 1.0 / (x - 0.3), -1.0, 1.0, None).unwrap();
+        assert!(report.roots.is_empty());
+        assert_eq!(report.warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_initial_guess() {
+        // Root outside the interval is still found from the guess
+        let report = solve(|x| x.ln() - 3.0, 1.0, 2.0, Some(10.0)).unwrap();
+        assert_eq!(report.roots.len(), 1);
+        assert!((report.roots[0] - 3f64.exp()).abs() < 1e-9);
+
+        let report = solve(|x| x * x + 1.0, -1.0, 1.0, Some(0.5)).unwrap();
+        assert!(report.roots.is_empty());
+        assert_eq!(report.warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_invalid_interval() {
+        assert!(solve(|x| x, 1.0, 1.0, None).is_err());
+        assert!(solve(|x| x, 2.0, 1.0, None).is_err());
+    }
+}
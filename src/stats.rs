@@ -0,0 +1,350 @@
+/// Single entry of a statistics data set
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DataPoint {
+    pub x: f64,
+    /// Paired value for two-variable data
+    pub y: Option<f64>,
+    pub freq: u32,
+}
+
+/// Editable list of data points used by the statistics mode
+#[derive(Clone, Default)]
+pub struct DataSet {
+    pub points: Vec<DataPoint>,
+}
+
+/// One-variable summary of a data set
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Summary {
+    pub n: f64,
+    pub sum: f64,
+    pub mean: f64,
+    pub median: f64,
+    pub mode: f64,
+    pub sample_var: f64,
+    pub pop_var: f64,
+    pub sample_sd: f64,
+    pub pop_sd: f64,
+    pub min: f64,
+    pub max: f64,
+    pub q1: f64,
+    pub q3: f64,
+    pub iqr: f64,
+}
+
+/// Largest frequency of a single data point
+pub const MAX_FREQUENCY: u32 = 1_000_000;
+
+/// Names of the summary values, usable both as named values (data set)
+/// and as list functions, e.g. `mean` and `mean(3,5,8,13)`
+pub const SUMMARY_NAMES: [&str; 14] = [
+    "count", "sum", "mean", "median", "mode", "var", "pvar", "stdev", "pstdev", "min", "max", "q1",
+    "q3", "iqr",
+];
+
+impl DataSet {
+    pub fn push(&mut self, x: f64, freq: u32) {
+        self.points.push(DataPoint { x, y: None, freq });
+    }
+
+    pub fn push_pair(&mut self, x: f64, y: f64, freq: u32) {
+        self.points.push(DataPoint {
+            x,
+            y: Some(y),
+            freq,
+        });
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        if index < self.points.len() {
+            self.points.remove(index);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.points.clear();
+    }
+
+    /// Values with their frequencies; points with zero frequency are skipped
+    pub fn values(&self) -> Vec<(f64, u32)> {
+        self.points
+            .iter()
+            .filter(|p| p.freq > 0)
+            .map(|p| (p.x, p.freq))
+            .collect()
+    }
+
+    /// Paired (x, y) values and their frequencies as weights; points without
+    /// a y value or with zero frequency are skipped
+    pub fn pairs(&self) -> (Vec<f64>, Vec<f64>, Vec<f64>) {
+        let mut xs = Vec::new();
+        let mut ys = Vec::new();
+        let mut weights = Vec::new();
+        for p in self.points.iter().filter(|p| p.freq > 0) {
+            if let Some(y) = p.y {
+                xs.push(p.x);
+                ys.push(y);
+                weights.push(f64::from(p.freq));
+            }
+        }
+        (xs, ys, weights)
+    }
+
+    pub fn summary(&self) -> Option<Summary> {
+        summarize_weighted(&self.values())
+    }
+}
+
+impl Summary {
+    /// Summary values paired with their names (see `SUMMARY_NAMES`)
+    pub fn named_values(&self) -> [(&'static str, f64); 14] {
+        let values = [
+            self.n,
+            self.sum,
+            self.mean,
+            self.median,
+            self.mode,
+            self.sample_var,
+            self.pop_var,
+            self.sample_sd,
+            self.pop_sd,
+            self.min,
+            self.max,
+            self.q1,
+            self.q3,
+            self.iqr,
+        ];
+        std::array::from_fn(|i| (SUMMARY_NAMES[i], values[i]))
+    }
+}
+
+/// Compute the summary of a list of values (None for an empty list)
+pub fn summarize(values: &[f64]) -> Option<Summary> {
+    let weighted: Vec<(f64, u32)> = values.iter().map(|&x| (x, 1)).collect();
+    summarize_weighted(&weighted)
+}
+
+/// Compute the summary of values with frequencies, as if each value were
+/// repeated that many times (None when the frequencies add up to zero)
+pub fn summarize_weighted(values: &[(f64, u32)]) -> Option<Summary> {
+    let mut sorted: Vec<(f64, u64)> = values
+        .iter()
+        .filter(|(_, freq)| *freq > 0)
+        .map(|&(x, freq)| (x, u64::from(freq)))
+        .collect();
+    if sorted.is_empty() {
+        return None;
+    }
+    sorted.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let count: u64 = sorted.iter().map(|(_, freq)| freq).sum();
+    let n = count as f64;
+    let sum: f64 = sorted.iter().map(|&(x, freq)| x * freq as f64).sum();
+    let mean = sum / n;
+    let ss: f64 = sorted
+        .iter()
+        .map(|&(x, freq)| (x - mean).powi(2) * freq as f64)
+        .sum();
+    let pop_var = ss / n;
+    let sample_var = if count > 1 { ss / (n - 1.0) } else { f64::NAN };
+
+    // Quartiles use the median of each half, excluding the median itself
+    // for odd counts (the convention of most hand-held calculators)
+    let half = count / 2;
+    let (q1, q3) = if count == 1 {
+        (sorted[0].0, sorted[0].0)
+    } else {
+        (
+            median_sorted(&sorted, 0, half),
+            median_sorted(&sorted, count - half, half),
+        )
+    };
+
+    Some(Summary {
+        n,
+        sum,
+        mean,
+        median: median_sorted(&sorted, 0, count),
+        mode: mode_sorted(&sorted),
+        sample_var,
+        pop_var,
+        sample_sd: sample_var.sqrt(),
+        pop_sd: pop_var.sqrt(),
+        min: sorted[0].0,
+        max: sorted[sorted.len() - 1].0,
+        q1,
+        q3,
+        iqr: q3 - q1,
+    })
+}
+
+/// Look up a single summary value of a list by name
+pub fn summary_value(name: &str, values: &[f64]) -> f64 {
+    summarize(values)
+        .and_then(|s| {
+            s.named_values()
+                .iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, v)| *v)
+        })
+        .unwrap_or(f64::NAN)
+}
+
+/// Value at position `index` of the sorted values with frequencies expanded
+fn nth_sorted(sorted: &[(f64, u64)], index: u64) -> f64 {
+    let mut end = 0;
+    for &(x, freq) in sorted {
+        end += freq;
+        if index < end {
+            return x;
+        }
+    }
+    sorted[sorted.len() - 1].0
+}
+
+/// Median of the `len` expanded values from position `start`
+fn median_sorted(sorted: &[(f64, u64)], start: u64, len: u64) -> f64 {
+    let mid = start + len / 2;
+    if len % 2 == 1 {
+        nth_sorted(sorted, mid)
+    } else {
+        (nth_sorted(sorted, mid - 1) + nth_sorted(sorted, mid)) / 2.0
+    }
+}
+
+/// Most frequent value; ties resolve to the smallest value
+fn mode_sorted(sorted: &[(f64, u64)]) -> f64 {
+    let mut best = sorted[0].0;
+    let mut best_count = 0;
+    let mut i = 0;
+    while i < sorted.len() {
+        let mut count = 0;
+        let mut j = i;
+        while j < sorted.len() && sorted[j].0 == sorted[i].0 {
+            count += sorted[j].1;
+            j += 1;
+        }
+        if count > best_count {
+            best_count = count;
+            best = sorted[i].0;
+        }
+        i = j;
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn test_summary_odd() {
+        let s = summarize(&[7.0, 1.0, 3.0, 9.0, 5.0]).unwrap();
+        assert_eq!(s.n, 5.0);
+        assert_eq!(s.sum, 25.0);
+        assert_eq!(s.mean, 5.0);
+        assert_eq!(s.median, 5.0);
+        assert_eq!(s.min, 1.0);
+        assert_eq!(s.max, 9.0);
+        assert_eq!(s.q1, 2.0);
+        assert_eq!(s.q3, 8.0);
+        assert_eq!(s.iqr, 6.0);
+        assert!(approx(s.pop_var, 8.0));
+        assert!(approx(s.sample_var, 10.0));
+        assert!(approx(s.sample_sd, 10f64.sqrt()));
+    }
+
+    #[test]
+    fn test_summary_even() {
+        let s = summarize(&[3.0, 5.0, 8.0, 13.0]).unwrap();
+        assert_eq!(s.median, 6.5);
+        assert_eq!(s.q1, 4.0);
+        assert_eq!(s.q3, 10.5);
+        assert_eq!(s.mean, 7.25);
+    }
+
+    #[test]
+    fn test_mode() {
+        let s = summarize(&[4.0, 2.0, 4.0, 2.0, 9.0, 4.0]).unwrap();
+        assert_eq!(s.mode, 4.0);
+        // Ties resolve to the smallest value
+        let s = summarize(&[3.0, 1.0, 2.0]).unwrap();
+        assert_eq!(s.mode, 1.0);
+    }
+
+    #[test]
+    fn test_single_value() {
+        let s = summarize(&[42.0]).unwrap();
+        assert_eq!(s.median, 42.0);
+        assert_eq!(s.pop_var, 0.0);
+        assert!(s.sample_var.is_nan());
+        assert!(summarize(&[]).is_none());
+    }
+
+    #[test]
+    fn test_frequencies() {
+        let mut data = DataSet::default();
+        data.push(1.0, 2);
+        data.push(10.0, 1);
+        data.push(5.0, 0);
+        assert_eq!(data.values(), vec![(1.0, 2), (10.0, 1)]);
+        let s = data.summary().unwrap();
+        assert_eq!(s.n, 3.0);
+        assert_eq!(s.mean, 4.0);
+        assert_eq!(s.mode, 1.0);
+    }
+
+    #[test]
+    fn test_weighted_matches_expanded() {
+        let weighted = [(5.0, 3), (1.0, 2), (9.0, 1), (5.0, 1), (2.0, 4)];
+        for len in 1..=weighted.len() {
+            let expanded: Vec<f64> = weighted[..len]
+                .iter()
+                .flat_map(|&(x, freq)| std::iter::repeat_n(x, freq as usize))
+                .collect();
+            let w = summarize_weighted(&weighted[..len]).unwrap();
+            let e = summarize(&expanded).unwrap();
+            for ((name, a), (_, b)) in w.named_values().iter().zip(e.named_values()) {
+                assert!(approx(*a, b) || (a.is_nan() && b.is_nan()), "{name}");
+            }
+        }
+        assert!(summarize_weighted(&[(3.0, 0)]).is_none());
+    }
+
+    #[test]
+    fn test_large_frequency() {
+        let mut data = DataSet::default();
+        data.push(2.0, MAX_FREQUENCY);
+        data.push(4.0, MAX_FREQUENCY);
+        data.push(9.0, 1);
+        let s = data.summary().unwrap();
+        assert_eq!(s.n, 2_000_001.0);
+        assert_eq!(s.median, 4.0);
+        assert_eq!(s.q1, 2.0);
+        assert_eq!(s.mode, 2.0);
+    }
+
+    #[test]
+    fn test_pairs() {
+        let mut data = DataSet::default();
+        data.push_pair(1.0, 2.0, 2);
+        data.push(7.0, 1);
+        data.push_pair(3.0, 4.0, 1);
+        data.push_pair(5.0, 6.0, 0);
+        assert_eq!(
+            data.pairs(),
+            (vec![1.0, 3.0], vec![2.0, 4.0], vec![2.0, 1.0])
+        );
+        assert_eq!(data.values(), vec![(1.0, 2), (7.0, 1), (3.0, 1)]);
+    }
+
+    #[test]
+    fn test_summary_value_by_name() {
+        assert_eq!(summary_value("iqr", &[1.0, 3.0, 5.0, 7.0, 9.0]), 6.0);
+        assert!(summary_value("stdev", &[1.0]).is_nan());
+    }
+}
@@ -0,0 +1,451 @@
+use crate::expr::{Expr, Op, CALCULUS};
+
+const CANNOT_DIFFERENTIATE: &str = "Cannot differentiate";
+
+/// Body and variable of a symbolic derivative `deriv(f, x)` or `diff(f, x)`
+pub fn as_derivative(expr: &Expr) -> Option<(&Expr, &str)> {
+    match expr {
+        Expr::Call(name, args) if name == "deriv" || name == "diff" => match args.as_slice() {
+            [body, Expr::Var(var)] => Some((body, var.as_str())),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Replace every symbolic derivative in `expr` by the derived expression.
+/// Returns None if the result does not depend on any of the
+/// differentiation variables, i.e. it is a plain number.
+pub fn expand(expr: &Expr, degrees: bool) -> Result<Option<Expr>, String> {
+    let mut vars = Vec::new();
+    let expanded = simplify(expand_derivatives(expr, degrees, &mut vars)?);
+    if vars.iter().any(|var| depends_on(&expanded, var)) {
+        Ok(Some(expanded))
+    } else {
+        Ok(None)
+    }
+}
+
+fn expand_derivatives(expr: &Expr, degrees: bool, vars: &mut Vec<String>) -> Result<Expr, String> {
+    if let Some((body, var)) = as_derivative(expr) {
+        // Inner derivatives first, e.g. d/dx(d/dx(x³,x),x)
+        let body = expand_derivatives(body, degrees, vars)?;
+        vars.push(var.to_string());
+        return differentiate(&body, var, degrees);
+    }
+
+    Ok(match expr {
+        Expr::Neg(a) => Expr::Neg(Box::new(expand_derivatives(a, degrees, vars)?)),
+        Expr::Binary(op, a, b) => Expr::Binary(
+            *op,
+            Box::new(expand_derivatives(a, degrees, vars)?),
+            Box::new(expand_derivatives(b, degrees, vars)?),
+        ),
+        Expr::Call(name, args) => Expr::Call(
+            name.clone(),
+            args.iter()
+                .map(|arg| expand_derivatives(arg, degrees, vars))
+                .collect::<Result<_, _>>()?,
+        ),
+        e => e.clone(),
+    })
+}
+
+/// Simplified derivative of `expr` with respect to `var`. In degree mode the
+/// derivatives of sin, cos and tan carry a factor π/180, and those of asin,
+/// acos and atan a factor 180/π.
+pub fn differentiate(expr: &Expr, var: &str, degrees: bool) -> Result<Expr, String> {
+    Ok(simplify(derivative(expr, var, degrees)?))
+}
+
+fn derivative(expr: &Expr, var: &str, degrees: bool) -> Result<Expr, String> {
+    if !depends_on(expr, var) {
+        return Ok(num(0.0));
+    }
+    let d = |e: &Expr| derivative(e, var, degrees);
+
+    Ok(match expr {
+        Expr::Var(_) => num(1.0),
+        Expr::Neg(a) => neg(d(a)?),
+        Expr::Binary(op, a, b) => {
+            let (a, b) = (a.as_ref().clone(), b.as_ref().clone());
+            match op {
+                Op::Add => add(d(&a)?, d(&b)?),
+                Op::Sub => sub(d(&a)?, d(&b)?),
+                Op::Mul => add(mul(d(&a)?, b.clone()), mul(a.clone(), d(&b)?)),
+                Op::Div => div(
+                    sub(mul(d(&a)?, b.clone()), mul(a.clone(), d(&b)?)),
+                    pow(b, num(2.0)),
+                ),
+                // Power rule, exponential rule and the general case
+                Op::Pow if !depends_on(&b, var) => {
+                    mul(mul(b.clone(), pow(a.clone(), sub(b, num(1.0)))), d(&a)?)
+                }
+                Op::Pow if !depends_on(&a, var) => mul(mul(expr.clone(), call("ln", a)), d(&b)?),
+                Op::Pow => mul(
+                    expr.clone(),
+                    add(mul(d(&b)?, call("ln", a.clone())), div(mul(b, d(&a)?), a)),
+                ),
+                Op::Rem => return Err(CANNOT_DIFFERENTIATE.to_string()),
+            }
+        }
+        Expr::Call(name, args) if args.len() == 1 => {
+            let u = args[0].clone();
+            // Degree arguments are scaled by π/180 before the function
+            let angle = |e: Expr| {
+                if degrees {
+                    mul(div(Expr::Var("pi".to_string()), num(180.0)), e)
+                } else {
+                    e
+                }
+            };
+            // and degree results of the inverse functions by 180/π after it
+            let inverse = |e: Expr| {
+                if degrees {
+                    mul(div(num(180.0), Expr::Var("pi".to_string())), e)
+                } else {
+                    e
+                }
+            };
+            let outer = match name.as_str() {
+                "sin" => angle(call("cos", u.clone())),
+                "cos" => angle(neg(call("sin", u.clone()))),
+                "tan" => angle(div(num(1.0), pow(call("cos", u.clone()), num(2.0)))),
+                "asin" => inverse(div(
+                    num(1.0),
+                    call("sqrt", sub(num(1.0), pow(u.clone(), num(2.0)))),
+                )),
+                "acos" => inverse(neg(div(
+                    num(1.0),
+                    call("sqrt", sub(num(1.0), pow(u.clone(), num(2.0)))),
+                ))),
+                "atan" => inverse(div(num(1.0), add(num(1.0), pow(u.clone(), num(2.0))))),
+                "sinh" => call("cosh", u.clone()),
+                "cosh" => call("sinh", u.clone()),
+                "tanh" => div(num(1.0), pow(call("cosh", u.clone()), num(2.0))),
+                "asinh" => div(
+                    num(1.0),
+                    call("sqrt", add(pow(u.clone(), num(2.0)), num(1.0))),
+                ),
+                "acosh" => div(
+                    num(1.0),
+                    call("sqrt", sub(pow(u.clone(), num(2.0)), num(1.0))),
+                ),
+                "atanh" => div(num(1.0), sub(num(1.0), pow(u.clone(), num(2.0)))),
+                "exp" => call("exp", u.clone()),
+                "ln" => div(num(1.0), u.clone()),
+                "log10" => div(num(1.0), mul(u.clone(), call("ln", num(10.0)))),
+                "sqrt" => div(num(1.0), mul(num(2.0), call("sqrt", u.clone()))),
+                "abs" => call("signum", u.clone()),
+                _ => return Err(CANNOT_DIFFERENTIATE.to_string()),
+            };
+            mul(outer, d(&u)?)
+        }
+        _ => return Err(CANNOT_DIFFERENTIATE.to_string()),
+    })
+}
+
+/// Whether `var` occurs free in `expr` (not bound by a calculus operation)
+pub fn depends_on(expr: &Expr, var: &str) -> bool {
+    match expr {
+        Expr::Num(_) => false,
+        Expr::Var(name) => name == var,
+        Expr::Neg(a) => depends_on(a, var),
+        Expr::Binary(_, a, b) => depends_on(a, var) || depends_on(b, var),
+        Expr::Call(name, args) => {
+            let binds_var = matches!(args.get(1), Some(Expr::Var(v)) if v == var)
+                && (CALCULUS.iter().any(|(n, _)| n == name) || as_derivative(expr).is_some());
+            args.iter()
+                .enumerate()
+                .any(|(i, arg)| !(binds_var && i < 2) && depends_on(arg, var))
+        }
+        Expr::List(items) => items.iter().any(|item| depends_on(item, var)),
+    }
+}
+
+/// Algebraic simplification: constant folding, neutral and absorbing
+/// elements, signs, numeric coefficients in front, like terms and powers
+/// of the same base
+pub fn simplify(expr: Expr) -> Expr {
+    match expr {
+        Expr::Neg(a) => simplify_neg(simplify(*a)),
+        Expr::Binary(op, a, b) => simplify_binary(op, simplify(*a), simplify(*b)),
+        Expr::Call(name, args) => Expr::Call(name, args.into_iter().map(simplify).collect()),
+        e => e,
+    }
+}
+
+fn simplify_neg(a: Expr) -> Expr {
+    match a {
+        Expr::Num(v) => num(-v),
+        Expr::Neg(b) => *b,
+        Expr::Binary(Op::Mul, c, t) => match *c {
+            Expr::Num(n) => simplify_binary(Op::Mul, num(-n), *t),
+            c => neg(mul(c, *t)),
+        },
+        a => neg(a),
+    }
+}
+
+fn simplify_binary(op: Op, a: Expr, b: Expr) -> Expr {
+    if let (Expr::Num(x), Expr::Num(y)) = (&a, &b) {
+        if let Some(v) = fold(op, *x, *y) {
+            return num(v);
+        }
+    }
+    let is = |e: &Expr, v: f64| matches!(e, Expr::Num(n) if *n == v);
+
+    match op {
+        Op::Add => {
+            if is(&a, 0.0) {
+                return b;
+            }
+            if is(&b, 0.0) {
+                return a;
+            }
+            match (a, b) {
+                (a, Expr::Neg(c)) => simplify_binary(Op::Sub, a, *c),
+                (a, Expr::Num(n)) if n < 0.0 => simplify_binary(Op::Sub, a, num(-n)),
+                (Expr::Neg(c), b) => simplify_binary(Op::Sub, b, *c),
+                (a, b) => {
+                    let ((ca, ta), (cb, tb)) = (coefficient(&a), coefficient(&b));
+                    if ta == tb {
+                        simplify_binary(Op::Mul, num(ca + cb), ta)
+                    } else {
+                        add(a, b)
+                    }
+                }
+            }
+        }
+        Op::Sub => {
+            if is(&b, 0.0) {
+                return a;
+            }
+            if is(&a, 0.0) {
+                return simplify_neg(b);
+            }
+            match (a, b) {
+                (a, Expr::Neg(c)) => simplify_binary(Op::Add, a, *c),
+                (a, Expr::Num(n)) if n < 0.0 => simplify_binary(Op::Add, a, num(-n)),
+                (a, b) => {
+                    let ((ca, ta), (cb, tb)) = (coefficient(&a), coefficient(&b));
+                    if ta == tb {
+                        simplify_binary(Op::Mul, num(ca - cb), ta)
+                    } else {
+                        sub(a, b)
+                    }
+                }
+            }
+        }
+        Op::Mul => {
+            if is(&a, 0.0) || is(&b, 0.0) {
+                return num(0.0);
+            }
+            if is(&a, 1.0) {
+                return b;
+            }
+            if is(&b, 1.0) {
+                return a;
+            }
+            match (a, b) {
+                (Expr::Neg(c), b) => simplify_neg(simplify_binary(Op::Mul, *c, b)),
+                (a, Expr::Neg(c)) => simplify_neg(simplify_binary(Op::Mul, a, *c)),
+                (a, b) => {
+                    // Numbers in front: x·2·3y = 6x·y
+                    let ((ca, ta), (cb, tb)) = (coefficient(&a), coefficient(&b));
+                    if ca != 1.0 || cb != 1.0 {
+                        let c = ca * cb;
+                        return match simplify_binary(Op::Mul, ta, tb) {
+                            Expr::Num(r) => num(c * r),
+                            rest if c == 1.0 => rest,
+                            rest if c == -1.0 => simplify_neg(rest),
+                            rest => mul(num(c), rest),
+                        };
+                    }
+
+                    // Powers of the same base: x·x² = x³
+                    let ((base_a, exp_a), (base_b, exp_b)) = (power(&a), power(&b));
+                    if base_a == base_b {
+                        simplify_binary(Op::Pow, base_a, simplify_binary(Op::Add, exp_a, exp_b))
+                    } else {
+                        mul(a, b)
+                    }
+                }
+            }
+        }
+        Op::Div => {
+            if is(&a, 0.0) {
+                return num(0.0);
+            }
+            if is(&b, 1.0) {
+                return a;
+            }
+            if a == b {
+                return num(1.0);
+            }
+            match (a, b) {
+                (Expr::Neg(c), b) => simplify_neg(simplify_binary(Op::Div, *c, b)),
+                (a, Expr::Neg(c)) => simplify_neg(simplify_binary(Op::Div, a, *c)),
+                // 6x/3 = 2x
+                (a, Expr::Num(m)) if coefficient(&a).0 % m == 0.0 => {
+                    let (c, t) = coefficient(&a);
+                    simplify_binary(Op::Mul, num(c / m), t)
+                }
+                (a, b) => div(a, b),
+            }
+        }
+        Op::Pow => {
+            if is(&b, 0.0) || is(&a, 1.0) {
+                return num(1.0);
+            }
+            if is(&b, 1.0) {
+                return a;
+            }
+            match (a, b) {
+                (Expr::Num(z), Expr::Num(n)) if z == 0.0 && n > 0.0 => num(0.0),
+                // (x²)³ = x⁶
+                (Expr::Binary(Op::Pow, base, m), Expr::Num(n)) => match *m {
+                    Expr::Num(m) => simplify_binary(Op::Pow, *base, num(m * n)),
+                    m => pow(pow(*base, m), num(n)),
+                },
+                (a, b) => pow(a, b),
+            }
+        }
+        Op::Rem => Expr::Binary(op, Box::new(a), Box::new(b)),
+    }
+}
+
+/// Fold an operation on two numbers if the result stays exact
+fn fold(op: Op, x: f64, y: f64) -> Option<f64> {
+    let value = match op {
+        Op::Add => x + y,
+        Op::Sub => x - y,
+        Op::Mul => x * y,
+        Op::Div if y != 0.0 && x % y == 0.0 => x / y,
+        Op::Pow if y.fract() == 0.0 && y >= 0.0 => x.powf(y),
+        Op::Rem if y != 0.0 => x % y,
+        _ => return None,
+    };
+    value.is_finite().then_some(value)
+}
+
+/// Split `3·t` into (3, t) and a number n into (n, 1); other terms have
+/// coefficient 1
+fn coefficient(expr: &Expr) -> (f64, Expr) {
+    match expr {
+        Expr::Num(n) => (*n, num(1.0)),
+        Expr::Binary(Op::Mul, c, t) => match **c {
+            Expr::Num(n) => (n, t.as_ref().clone()),
+            _ => (1.0, expr.clone()),
+        },
+        _ => (1.0, expr.clone()),
+    }
+}
+
+/// Split `b^n` into (b, n); other expressions have exponent 1
+fn power(expr: &Expr) -> (Expr, Expr) {
+    match expr {
+        Expr::Binary(Op::Pow, base, exp) => (base.as_ref().clone(), exp.as_ref().clone()),
+        _ => (expr.clone(), num(1.0)),
+    }
+}
+
+fn num(v: f64) -> Expr {
+    Expr::Num(v)
+}
+
+fn neg(a: Expr) -> Expr {
+    Expr::Neg(Box::new(a))
+}
+
+fn add(a: Expr, b: Expr) -> Expr {
+    Expr::Binary(Op::Add, Box::new(a), Box::new(b))
+}
+
+fn sub(a: Expr, b: Expr) -> Expr {
+    Expr::Binary(Op::Sub, Box::new(a), Box::new(b))
+}
+
+fn mul(a: Expr, b: Expr) -> Expr {
+    Expr::Binary(Op::Mul, Box::new(a), Box::new(b))
+}
+
+fn div(a: Expr, b: Expr) -> Expr {
+    Expr::Binary(Op::Div, Box::new(a), Box::new(b))
+}
+
+fn pow(a: Expr, b: Expr) -> Expr {
+    Expr::Binary(Op::Pow, Box::new(a), Box::new(b))
+}
+
+fn call(name: &str, arg: Expr) -> Expr {
+    Expr::Call(name.to_string(), vec![arg])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn derive(text: &str) -> String {
+        let expr: Expr = text.parse().unwrap();
+        differentiate(&expr, "x", false).unwrap().to_string()
+    }
+
+    fn simplified(text: &str) -> String {
+        simplify(text.parse().unwrap()).to_string()
+    }
+
+    #[test]
+    fn test_differentiate() {
+        assert_eq!(derive("x^2*sin(x)"), "2x·sin(x)+x²·cos(x)");
+        assert_eq!(derive("3x^4-2x+7"), "12x³−2");
+        assert_eq!(derive("exp(2x)"), "2exp(2x)");
+        assert_eq!(derive("ln(x)"), "1/x");
+        assert_eq!(derive("1/x"), "−1/x²");
+        assert_eq!(derive("cos(x)"), "−sin(x)");
+        assert_eq!(derive("2^x"), "2^x·ln(2)");
+        assert_eq!(derive("a*x"), "a");
+        assert!(differentiate(&"floor(x)".parse().unwrap(), "x", false).is_err());
+    }
+
+    #[test]
+    fn test_differentiate_degrees() {
+        let expr: Expr = "sin(x)".parse().unwrap();
+        assert_eq!(
+            differentiate(&expr, "x", true).unwrap().to_string(),
+            "π/180·cos(x)"
+        );
+        let expr: Expr = "atan(x)".parse().unwrap();
+        assert_eq!(
+            differentiate(&expr, "x", true).unwrap().to_string(),
+            "180/π·1/(1+x²)"
+        );
+    }
+
+    #[test]
+    fn test_simplify() {
+        assert_eq!(simplified("0*x+1*y"), "y");
+        assert_eq!(simplified("x*x*x"), "x³");
+        assert_eq!(simplified("2x+3x"), "5x");
+        assert_eq!(simplified("x-x"), "0");
+        assert_eq!(simplified("x*2*3"), "6x");
+        assert_eq!(simplified("(x^2)^3"), "x^6");
+        assert_eq!(simplified("--x"), "x");
+        assert_eq!(simplified("a+-b"), "a−b");
+        assert_eq!(simplified("6x/3"), "2x");
+    }
+
+    #[test]
+    fn test_expand() {
+        let expand_text = |text: &str| {
+            expand(&text.parse().unwrap(), false)
+                .unwrap()
+                .map(|e| e.to_string())
+        };
+        assert_eq!(expand_text("diff(diff(x^3,x),x)"), Some("6x".to_string()));
+        assert_eq!(expand_text("deriv(x^2,x)+1"), Some("2x+1".to_string()));
+        // No free differentiation variable left: a plain number
+        assert_eq!(expand_text("deriv(5x,x)"), None);
+        assert_eq!(expand_text("summation(deriv(k^2,k),k,1,3)"), None);
+    }
+}
@@ -0,0 +1,108 @@
+use crate::format::{DisplayFormat, RoundingMode};
+
+/// Largest number of rows in a table of values
+pub const MAX_ROWS: usize = 1000;
+
+/// Values of `f` at `start`, `start + step`, ... up to `stop`. Undefined
+/// values (NaN, infinity) are kept in the table.
+pub fn generate(
+    f: impl Fn(f64) -> f64,
+    start: f64,
+    stop: f64,
+    step: f64,
+) -> Result<Vec<(f64, f64)>, String> {
+    if !start.is_finite() || !stop.is_finite() || !step.is_finite() || step == 0.0 {
+        return Err("Invalid range".to_string());
+    }
+    if (stop - start) / step < 0.0 {
+        return Err("Step has the wrong sign".to_string());
+    }
+    // Allow for rounding so that 0, 0.1, ..., 1 includes 1
+    let count = ((stop - start) / step + 1e-9).floor() + 1.0;
+    if count > MAX_ROWS as f64 {
+        return Err(format!("More than {} rows", MAX_ROWS));
+    }
+    Ok((0..count as usize)
+        .map(|i| {
+            let x = start + step * i as f64;
+            (x, f(x))
+        })
+        .collect())
+}
+
+/// Table cell: the value in the display format, or "undefined" for NaN and
+/// infinity
+pub fn cell(value: f64, format: DisplayFormat, mode: RoundingMode) -> String {
+    if value.is_finite() {
+        format.format(value, mode)
+    } else {
+        "undefined".to_string()
+    }
+}
+
+/// Row as comma-separated values
+pub fn csv_row((x, y): (f64, f64), format: DisplayFormat, mode: RoundingMode) -> String {
+    format!("{},{}", cell(x, format, mode), cell(y, format, mode))
+}
+
+/// Whole table as CSV with a header line
+pub fn to_csv(
+    expression: &str,
+    rows: &[(f64, f64)],
+    format: DisplayFormat,
+    mode: RoundingMode,
+) -> String {
+    // Quote the expression, which may itself contain commas
+    let mut csv = format!("x,\"{}\"\n", expression.replace('"', "\"\""));
+    for row in rows {
+        csv.push_str(&csv_row(*row, format, mode));
+        csv.push('\n');
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate() {
+        let rows = generate(|x| x * x, 0.0, 1.0, 0.1).unwrap();
+        assert_eq!(rows.len(), 11);
+        assert!((rows[10].1 - 1.0).abs() < 1e-12);
+
+        let rows = generate(|x| x, 3.0, 1.0, -1.0).unwrap();
+        assert_eq!(rows, vec![(3.0, 3.0), (2.0, 2.0), (1.0, 1.0)]);
+
+        assert!(generate(|x| x, 0.0, 1.0, 0.0).is_err());
+        assert!(generate(|x| x, 0.0, 1.0, -1.0).is_err());
+        assert!(generate(|x| x, 0.0, 1e6, 1.0).is_err());
+    }
+
+    #[test]
+    fn test_undefined_values() {
+        // The pole at 0 does not end the table
+        let rows = generate(|x| 1.0 / x, -1.0, 1.0, 1.0).unwrap();
+        assert_eq!(rows.len(), 3);
+        let (auto, mode) = (DisplayFormat::Auto, RoundingMode::HalfUp);
+        assert_eq!(cell(rows[1].1, auto, mode), "undefined");
+        assert_eq!(cell(rows[2].1, auto, mode), "1");
+        assert_eq!(cell(f64::NAN, auto, mode), "undefined");
+    }
+
+    #[test]
+    fn test_csv() {
+        let rows = generate(|x| 1.0 / x, 0.0, 2.0, 1.0).unwrap();
+        let (auto, mode) = (DisplayFormat::Auto, RoundingMode::HalfUp);
+        assert_eq!(
+            to_csv("1/x", &rows, auto, mode),
+            "x,\"1/x\"\n0,undefined\n1,1\n2,0.5\n"
+        );
+        assert_eq!(csv_row((2.0, 0.5), auto, mode), "2,0.5");
+        // Exports follow the display format
+        assert_eq!(
+            csv_row((2.0, 0.5), DisplayFormat::Fix(2), mode),
+            "2.00,0.50"
+        );
+    }
+}
@@ -0,0 +1,566 @@
+use std::f32::consts::PI;
+
+use crate::calculator::format_result;
+use crate::expr::{display_name, precedence, Expr, Op};
+use crate::format::Locale;
+use crate::highlight::{self, Kind};
+
+/// Size of exponents and subscripts relative to their base
+const SCRIPT_SCALE: f32 = 0.7;
+/// Size of numerators and denominators relative to the fraction
+const FRACTION_SCALE: f32 = 0.85;
+/// Smallest font size of nested scripts and fractions
+const MIN_SIZE: f32 = 9.0;
+/// Height of a line of text above (and below) its center line, relative to
+/// the font size
+const HALF_LINE: f32 = 0.6;
+
+/// Input to typeset: the parsed expression, its number literals as typed
+/// and trailing text that does not parse yet (such as an operator waiting
+/// for its operand)
+pub struct Typeset {
+    pub expr: Expr,
+    pub numbers: Vec<String>,
+    pub rest: String,
+}
+
+/// Settings of the typeset display
+#[derive(Clone, Copy)]
+pub struct Style {
+    pub size: f32,
+    /// Show `a÷b` as a stacked fraction
+    pub stacked_fractions: bool,
+    pub locale: Locale,
+}
+
+/// Part of a laid out expression, in coordinates relative to the left end
+/// of the block's center line (y grows downwards)
+#[derive(Clone, Debug, PartialEq)]
+pub enum Piece {
+    /// Text whose left end and vertical center are at `at`
+    Text {
+        at: (f32, f32),
+        text: String,
+        size: f32,
+        kind: Kind,
+    },
+    /// Fraction bar, radical sign or tall bracket
+    Line {
+        points: Vec<(f32, f32)>,
+        width: f32,
+        kind: Kind,
+    },
+}
+
+/// Laid out expression: its extent around the center line and what to draw
+#[derive(Clone, Debug, Default)]
+pub struct Block {
+    pub width: f32,
+    pub ascent: f32,
+    pub descent: f32,
+    pub pieces: Vec<Piece>,
+}
+
+impl Block {
+    fn text(text: &str, size: f32, kind: Kind, measure: &dyn Fn(&str, f32) -> f32) -> Self {
+        Self {
+            width: measure(text, size),
+            ascent: size * HALF_LINE,
+            descent: size * HALF_LINE,
+            pieces: vec![Piece::Text {
+                at: (0.0, 0.0),
+                text: text.to_string(),
+                size,
+                kind,
+            }],
+        }
+    }
+
+    pub fn height(&self) -> f32 {
+        self.ascent + self.descent
+    }
+
+    /// Place `other` at `x`, with its center line `dy` below this one
+    fn place(&mut self, other: Block, x: f32, dy: f32) {
+        for piece in other.pieces {
+            self.pieces.push(match piece {
+                Piece::Text {
+                    at,
+                    text,
+                    size,
+                    kind,
+                } => Piece::Text {
+                    at: (at.0 + x, at.1 + dy),
+                    text,
+                    size,
+                    kind,
+                },
+                Piece::Line {
+                    points,
+                    width,
+                    kind,
+                } => Piece::Line {
+                    points: points.iter().map(|&(px, py)| (px + x, py + dy)).collect(),
+                    width,
+                    kind,
+                },
+            });
+        }
+        self.ascent = self.ascent.max(other.ascent - dy);
+        self.descent = self.descent.max(other.descent + dy);
+        self.width = self.width.max(x + other.width);
+    }
+
+    /// Append `other` after this block, its center line `dy` lower
+    fn push(&mut self, other: Block, dy: f32) {
+        self.place(other, self.width, dy);
+    }
+
+    /// Append display text such as an unfinished operator, coloured by
+    /// its syntax
+    pub fn push_text(
+        &mut self,
+        text: &str,
+        size: f32,
+        locale: Locale,
+        measure: &dyn Fn(&str, f32) -> f32,
+    ) {
+        let chars: Vec<char> = text.chars().collect();
+        let kinds = highlight::classify(text, locale);
+        let mut start = 0;
+        for end in 1..=chars.len() {
+            if end == chars.len() || kinds[end] != kinds[start] {
+                let run: String = chars[start..end].iter().collect();
+                self.push(Block::text(&run, size, kinds[start], measure), 0.0);
+                start = end;
+            }
+        }
+    }
+
+    fn line(&mut self, points: Vec<(f32, f32)>, size: f32, kind: Kind) {
+        let width = (size * 0.06).max(1.0);
+        self.pieces.push(Piece::Line {
+            points,
+            width,
+            kind,
+        });
+    }
+}
+
+/// Brackets around a group, drawn to the group's height
+#[derive(Clone, Copy)]
+enum Delimiter {
+    Paren,
+    Square,
+    Bar,
+}
+
+/// Lay out an expression as two-dimensional math: exponents as
+/// superscripts, `sqrt` under a radical bar, `÷` as a stacked fraction if
+/// enabled and brackets as tall as their contents. `measure` gives the
+/// width of text at a font size.
+pub fn layout(input: &Typeset, style: Style, measure: &dyn Fn(&str, f32) -> f32) -> Block {
+    let mut layouter = Layouter {
+        style,
+        measure,
+        numbers: input.numbers.iter(),
+    };
+    let mut block = layouter.expr(&input.expr, style.size);
+    let rest = style.locale.localize(&input.rest);
+    block.push_text(&rest, style.size, style.locale, measure);
+    block
+}
+
+struct Layouter<'a> {
+    style: Style,
+    measure: &'a dyn Fn(&str, f32) -> f32,
+    numbers: std::slice::Iter<'a, String>,
+}
+
+impl Layouter<'_> {
+    fn text(&self, text: &str, size: f32, kind: Kind) -> Block {
+        Block::text(text, size, kind, self.measure)
+    }
+
+    fn expr(&mut self, expr: &Expr, size: f32) -> Block {
+        match expr {
+            Expr::Num(value) => {
+                let literal = match self.numbers.next() {
+                    Some(literal) => literal.clone(),
+                    None => format_result(*value),
+                };
+                self.text(&self.style.locale.localize(&literal), size, Kind::Number)
+            }
+            Expr::Var(name) => {
+                let kind = match name.as_str() {
+                    "pi" | "e" => Kind::Constant,
+                    _ => Kind::Variable,
+                };
+                self.text(display_name(name), size, kind)
+            }
+            Expr::Neg(a) => {
+                let mut block = self.text("−", size, Kind::Operator);
+                block.push(self.wrapped(a, precedence(a) < 2, size), 0.0);
+                block
+            }
+            Expr::Binary(Op::Pow, a, b) => {
+                let mut block = self.wrapped(a, precedence(a) < 5, size);
+                let exponent = self.expr(b, (size * SCRIPT_SCALE).max(MIN_SIZE));
+                // Raised to the upper part of the base, and further for tall
+                // exponents such as fractions
+                let raise = block.ascent * 0.85 + (exponent.descent - exponent.ascent).max(0.0);
+                block.push(exponent, -raise);
+                block
+            }
+            Expr::Binary(Op::Div, a, b) if self.style.stacked_fractions => {
+                self.fraction(a, b, size)
+            }
+            Expr::Binary(op, a, b) => {
+                let (pa, pb) = (precedence(a), precedence(b));
+                let (symbol, left, right) = match op {
+                    Op::Add => ("+", false, pb == 3),
+                    Op::Sub => ("−", false, pb <= 1 || pb == 3),
+                    Op::Mul => ("×", pa < 2, pb <= 1 || pb == 3),
+                    Op::Div => ("÷", pa < 2, pb <= 3),
+                    _ => ("%", pa < 2, pb <= 3),
+                };
+                let mut block = self.wrapped(a, left, size);
+                block.push(self.text(symbol, size, Kind::Operator), 0.0);
+                block.push(self.wrapped(b, right, size), 0.0);
+                block
+            }
+            Expr::Call(name, args) => match (name.as_str(), args.as_slice()) {
+                ("sqrt", [arg]) => {
+                    let inner = self.expr(arg, size);
+                    radical(inner, size)
+                }
+                ("abs", [arg]) => {
+                    let inner = self.expr(arg, size);
+                    self.delimit(inner, Delimiter::Bar, size)
+                }
+                ("log10", _) => {
+                    let mut block = self.text("log", size, Kind::Function);
+                    let base_size = (size * SCRIPT_SCALE).max(MIN_SIZE);
+                    let base = self.text("10", base_size, Kind::Function);
+                    block.push(base, size * 0.35);
+                    block.push(self.arguments(args, Delimiter::Paren, size), 0.0);
+                    block
+                }
+                ("deriv", [_, Expr::Var(var), ..]) => {
+                    let mut block = self.text(&format!("d/d{}", var), size, Kind::Function);
+                    block.push(self.arguments(args, Delimiter::Paren, size), 0.0);
+                    block
+                }
+                _ => {
+                    let mut block = self.text(display_name(name), size, Kind::Function);
+                    block.push(self.arguments(args, Delimiter::Paren, size), 0.0);
+                    block
+                }
+            },
+            Expr::List(items) => {
+                let rows: Option<Vec<&[Expr]>> = items
+                    .iter()
+                    .map(|item| match item {
+                        Expr::List(row) => Some(row.as_slice()),
+                        _ => None,
+                    })
+                    .collect();
+                match rows {
+                    Some(rows) => self.matrix(&rows, size),
+                    None => self.arguments(items, Delimiter::Square, size),
+                }
+            }
+        }
+    }
+
+    fn wrapped(&mut self, expr: &Expr, parens: bool, size: f32) -> Block {
+        let inner = self.expr(expr, size);
+        if parens {
+            self.delimit(inner, Delimiter::Paren, size)
+        } else {
+            inner
+        }
+    }
+
+    /// Items separated by the locale's argument separator, in brackets
+    fn arguments(&mut self, items: &[Expr], delimiter: Delimiter, size: f32) -> Block {
+        let separator = self.style.locale.localize(",");
+        let mut inner = Block::default();
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 {
+                inner.push(self.text(&separator, size, Kind::Bracket), 0.0);
+            }
+            inner.push(self.expr(item, size), 0.0);
+        }
+        self.delimit(inner, delimiter, size)
+    }
+
+    fn fraction(&mut self, a: &Expr, b: &Expr, size: f32) -> Block {
+        let part_size = (size * FRACTION_SCALE).max(MIN_SIZE);
+        let numerator = self.expr(a, part_size);
+        let denominator = self.expr(b, part_size);
+        let gap = size * 0.15;
+        let pad = size * 0.15;
+        let width = numerator.width.max(denominator.width) + 2.0 * pad;
+
+        let mut block = Block::default();
+        let dy = gap + numerator.descent;
+        block.place(numerator.clone(), (width - numerator.width) / 2.0, -dy);
+        let dy = gap + denominator.ascent;
+        block.place(denominator.clone(), (width - denominator.width) / 2.0, dy);
+        let bar = vec![(pad / 2.0, 0.0), (width - pad / 2.0, 0.0)];
+        block.line(bar, size, Kind::Operator);
+        block.width = width;
+        block
+    }
+
+    fn matrix(&mut self, rows: &[&[Expr]], size: f32) -> Block {
+        let cells: Vec<Vec<Block>> = rows
+            .iter()
+            .map(|row| row.iter().map(|item| self.expr(item, size)).collect())
+            .collect();
+        let columns = cells.iter().map(Vec::len).max().unwrap_or(0);
+        let widths: Vec<f32> = (0..columns)
+            .map(|j| {
+                cells
+                    .iter()
+                    .filter_map(|row| row.get(j))
+                    .map(|cell| cell.width)
+                    .fold(0.0, f32::max)
+            })
+            .collect();
+        let column_gap = size * 0.6;
+        let row_gap = size * 0.2;
+        let heights: Vec<(f32, f32)> = cells
+            .iter()
+            .map(|row| {
+                row.iter().fold((0.0, 0.0), |(a, d), cell| {
+                    (cell.ascent.max(a), cell.descent.max(d))
+                })
+            })
+            .collect();
+        let total: f32 = heights.iter().map(|(a, d)| a + d).sum::<f32>()
+            + row_gap * heights.len().saturating_sub(1) as f32;
+
+        let mut inner = Block::default();
+        let mut top = -total / 2.0;
+        for (row, (ascent, descent)) in cells.into_iter().zip(heights) {
+            let mut x = 0.0;
+            for (j, cell) in row.into_iter().enumerate() {
+                // Right aligned, like numbers in a table
+                let offset = widths[j] - cell.width;
+                inner.place(cell, x + offset, top + ascent);
+                x += widths[j] + column_gap;
+            }
+            top += ascent + descent + row_gap;
+        }
+        inner.width = widths.iter().sum::<f32>() + column_gap * columns.saturating_sub(1) as f32;
+        inner.ascent = inner.ascent.max(total / 2.0);
+        inner.descent = inner.descent.max(total / 2.0);
+        self.delimit(inner, Delimiter::Square, size)
+    }
+
+    /// Brackets around `inner`: the font's glyphs for a single line, lines
+    /// drawn to the height of taller contents
+    fn delimit(&self, inner: Block, delimiter: Delimiter, size: f32) -> Block {
+        let line = size * HALF_LINE * 1.1;
+        if inner.ascent <= line && inner.descent <= line {
+            let (open, close) = match delimiter {
+                Delimiter::Paren => ("(", ")"),
+                Delimiter::Square => ("[", "]"),
+                Delimiter::Bar => ("|", "|"),
+            };
+            let mut block = self.text(open, size, Kind::Bracket);
+            block.push(inner, 0.0);
+            block.push(self.text(close, size, Kind::Bracket), 0.0);
+            return block;
+        }
+
+        let top = -inner.ascent;
+        let bottom = inner.descent;
+        let width = size * 0.4;
+        let mut block = Block {
+            ascent: inner.ascent,
+            descent: inner.descent,
+            ..Block::default()
+        };
+        block.place(inner.clone(), width, 0.0);
+        let right = width + inner.width;
+        for (x, side) in [(width * 0.8, -1.0), (right + width * 0.2, 1.0)] {
+            let points = match delimiter {
+                Delimiter::Paren => (0..=12)
+                    .map(|k| {
+                        let t = k as f32 / 12.0;
+                        let bulge = (t * PI).sin() * width * 0.5;
+                        (x + side * bulge, top + t * (bottom - top))
+                    })
+                    .collect(),
+                Delimiter::Square => {
+                    let tip = x - side * width * 0.4;
+                    vec![(tip, top), (x, top), (x, bottom), (tip, bottom)]
+                }
+                Delimiter::Bar => vec![(x, top), (x, bottom)],
+            };
+            block.line(points, size, Kind::Bracket);
+        }
+        block.width = right + width;
+        block
+    }
+}
+
+/// `inner` under a radical sign whose bar spans it
+fn radical(inner: Block, size: f32) -> Block {
+    let gap = size * 0.12;
+    let hook = size * 0.5;
+    let pad = size * 0.08;
+    let top = -(inner.ascent + gap);
+    let bottom = inner.descent;
+    let height = bottom - top;
+
+    let mut block = Block::default();
+    block.place(inner.clone(), hook + pad, 0.0);
+    block.line(
+        vec![
+            (0.0, bottom - height * 0.4),
+            (hook * 0.3, bottom - height * 0.5),
+            (hook * 0.6, bottom),
+            (hook, top),
+            (hook + inner.width + 2.0 * pad, top),
+        ],
+        size,
+        Kind::Function,
+    );
+    block.ascent = -top + size * 0.05;
+    block.width = hook + inner.width + 2.0 * pad;
+    block
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::number_literals;
+
+    /// Monospace text of 0.6 em per character
+    fn measure(text: &str, size: f32) -> f32 {
+        text.chars().count() as f32 * size * 0.6
+    }
+
+    fn typeset(text: &str, stacked_fractions: bool) -> Block {
+        let input = Typeset {
+            expr: text.parse().unwrap(),
+            numbers: number_literals(text),
+            rest: String::new(),
+        };
+        let style = Style {
+            size: 20.0,
+            stacked_fractions,
+            locale: Locale::default(),
+        };
+        layout(&input, style, &measure)
+    }
+
+    /// Text pieces with their vertical positions
+    fn texts(block: &Block) -> Vec<(String, f32)> {
+        block
+            .pieces
+            .iter()
+            .filter_map(|piece| match piece {
+                Piece::Text { at, text, .. } => Some((text.clone(), at.1)),
+                Piece::Line { .. } => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_superscripts() {
+        let block = typeset("2^10+1.50", false);
+        let texts = texts(&block);
+        assert_eq!(texts[0], ("2".to_string(), 0.0));
+        // The exponent is raised; numbers keep their typed digits
+        assert_eq!(texts[1].0, "10");
+        assert!(texts[1].1 < -5.0);
+        assert_eq!(texts[3], ("1.50".to_string(), 0.0));
+        assert!(block.ascent > 20.0 * HALF_LINE);
+    }
+
+    #[test]
+    fn test_parentheses() {
+        let text = |expr| -> String {
+            texts(&typeset(expr, false))
+                .into_iter()
+                .map(|t| t.0)
+                .collect()
+        };
+        assert_eq!(text("(1+2)*3"), "(1+2)×3");
+        assert_eq!(text("1-(2-3)"), "1−(2−3)");
+        assert_eq!(text("(-2)^2"), "(−2)2");
+        assert_eq!(text("pi*log10(100)"), "π×log10(100)");
+        assert_eq!(text("max(1,2)"), "max(1,2)");
+    }
+
+    #[test]
+    fn test_kinds() {
+        let block = typeset("2*sin(pi)+x", false);
+        let kinds: Vec<Kind> = block
+            .pieces
+            .iter()
+            .filter_map(|piece| match piece {
+                Piece::Text { kind, .. } => Some(*kind),
+                Piece::Line { .. } => None,
+            })
+            .collect();
+        use Kind::*;
+        let expected = [
+            Number, Operator, Function, Bracket, Constant, Bracket, Operator, Variable,
+        ];
+        assert_eq!(kinds, expected);
+    }
+
+    #[test]
+    fn test_fractions() {
+        let flat = typeset("1/(2+3)", false);
+        assert_eq!(flat.pieces.len(), 7);
+        assert!(flat.pieces.iter().all(|p| matches!(p, Piece::Text { .. })));
+
+        let stacked = typeset("1/(2+3)", true);
+        assert!(stacked.height() > flat.height());
+        let texts = texts(&stacked);
+        // Numerator above the bar, denominator below, without parentheses
+        assert_eq!(texts.len(), 4);
+        assert!(texts[0].1 < 0.0 && texts[1].1 > 0.0);
+        assert!(stacked
+            .pieces
+            .iter()
+            .any(|p| matches!(p, Piece::Line { points, .. } if points.len() == 2)));
+
+        // Brackets around a fraction are drawn to its height
+        let tall = typeset("2*(1/2+1)", true);
+        let lines = tall
+            .pieces
+            .iter()
+            .filter(|p| matches!(p, Piece::Line { .. }))
+            .count();
+        assert_eq!(lines, 3);
+    }
+
+    #[test]
+    fn test_radical() {
+        let block = typeset("sqrt(2)", false);
+        assert_eq!(texts(&block), [("2".to_string(), 0.0)]);
+        let Some(Piece::Line { points, .. }) = block.pieces.last() else {
+            panic!("no radical sign");
+        };
+        // The bar spans the argument
+        assert!(points.last().unwrap().0 >= measure("2", 20.0));
+        assert!(block.ascent > 20.0 * HALF_LINE);
+    }
+
+    #[test]
+    fn test_matrix() {
+        let block = typeset("[[1,2],[3,4]]", false);
+        let texts = texts(&block);
+        assert_eq!(texts.len(), 4);
+        assert!(texts[0].1 < 0.0 && texts[2].1 > 0.0);
+        assert_eq!(texts[0].1, texts[1].1);
+    }
+}
@@ -0,0 +1,452 @@
+use crate::calculator::format_result;
+use crate::currency::RateTable;
+use crate::dates::{self, SECONDS_PER_DAY};
+use crate::expr::{Context, Expr, Op, CALCULUS};
+
+/// Time units and their length in seconds
+pub const TIME_UNITS: [(&str, f64); 5] = [
+    ("s", 1.0),
+    ("h", 3600.0),
+    ("d", SECONDS_PER_DAY),
+    ("days", SECONDS_PER_DAY),
+    ("wk", 7.0 * SECONDS_PER_DAY),
+];
+
+/// Functions of dates and durations, callable from expressions:
+/// `date(y,m,d[,h,min,s])`, `hms(h,m[,s])`, business days between two dates
+/// (`workdays`) and after a date (`workday`), and Unix timestamps
+pub const DATE_FUNCTIONS: [&str; 6] = ["date", "hms", "workdays", "workday", "unix", "fromunix"];
+
+/// Target unit that shows a duration as h:mm:ss
+pub const HMS: &str = "hms";
+
+const INCOMPATIBLE: &str = "Incompatible units";
+const INVALID_DATE: &str = "Invalid date";
+
+/// Value in base units (base currency, seconds) with the exponents of
+/// currency and time. Dates are points in time, counted in seconds since
+/// 1970-01-01 (UTC).
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Quantity {
+    value: f64,
+    currency: i32,
+    time: i32,
+    date: bool,
+}
+
+impl Quantity {
+    fn number(value: f64) -> Self {
+        Self {
+            value,
+            currency: 0,
+            time: 0,
+            date: false,
+        }
+    }
+
+    fn seconds(value: f64) -> Self {
+        Self {
+            time: 1,
+            ..Self::number(value)
+        }
+    }
+
+    fn date(value: f64) -> Self {
+        Self {
+            date: true,
+            ..Self::number(value)
+        }
+    }
+
+    fn same_units(&self, other: &Self) -> bool {
+        self.currency == other.currency && self.time == other.time && self.date == other.date
+    }
+
+    fn is_number(&self) -> bool {
+        self.same_units(&Self::number(0.0))
+    }
+
+    fn is_duration(&self) -> bool {
+        self.same_units(&Self::seconds(0.0))
+    }
+}
+
+/// Quantity of one unit named `name`: a time unit, a currency code from
+/// the rate table, or `today`
+fn unit(name: &str, rates: &RateTable) -> Option<Quantity> {
+    if name == "today" {
+        return Some(Quantity::date(dates::today() as f64 * SECONDS_PER_DAY));
+    }
+    if let Some(&(_, seconds)) = TIME_UNITS.iter().find(|(n, _)| *n == name) {
+        return Some(Quantity::seconds(seconds));
+    }
+    rates.rate(name).map(|rate| Quantity {
+        currency: 1,
+        ..Quantity::number(1.0 / rate)
+    })
+}
+
+fn is_time_unit(name: &str) -> bool {
+    TIME_UNITS.iter().any(|(n, _)| *n == name)
+}
+
+/// Whether `name` is a time unit or a currency code from the rate table
+pub fn is_unit(name: &str, rates: &RateTable) -> bool {
+    is_time_unit(name) || rates.rate(name).is_some()
+}
+
+/// Whether the expression names a unit or date outside of calculus
+/// operations, whose bound variables may shadow unit names
+pub fn uses_units(expr: &Expr, rates: &RateTable) -> bool {
+    match expr {
+        Expr::Num(_) => false,
+        Expr::Var(name) => unit(name, rates).is_some(),
+        Expr::Neg(a) => uses_units(a, rates),
+        Expr::Binary(_, a, b) => uses_units(a, rates) || uses_units(b, rates),
+        Expr::Call(name, args) => {
+            DATE_FUNCTIONS.contains(&name.as_str())
+                || !CALCULUS.iter().any(|(n, _)| n == name)
+                    && args.iter().any(|a| uses_units(a, rates))
+        }
+        Expr::List(items) => items.iter().any(|a| uses_units(a, rates)),
+    }
+}
+
+/// Formatted value of an expression with units, shown in the units of
+/// `target` (e.g. `EUR/h`, or `hms` for h:mm:ss), or else in the first
+/// currency and time unit named in the expression. Dates are shown as ISO
+/// dates; durations given as h:mm:ss are shown the same way.
+pub fn convert(
+    expr: &Expr,
+    target: Option<(&Expr, &str)>,
+    ctx: &Context,
+    rates: &RateTable,
+) -> Result<String, String> {
+    let quantity = evaluate(expr, ctx, rates)?;
+    if !quantity.value.is_finite() {
+        return Err("Undefined".to_string());
+    }
+    if let Some((target, text)) = target {
+        let text = text.trim();
+        if text == HMS {
+            return match quantity.is_duration() {
+                true => Ok(dates::format_hms(quantity.value)),
+                false => Err(INCOMPATIBLE.to_string()),
+            };
+        }
+        if !uses_units(target, rates) {
+            return Err("Not a unit".to_string());
+        }
+        let unit = evaluate(target, ctx, rates)?;
+        if !quantity.same_units(&unit) || unit.date {
+            return Err(INCOMPATIBLE.to_string());
+        }
+        return Ok(format!(
+            "{} {}",
+            format_result(quantity.value / unit.value),
+            text
+        ));
+    }
+    if quantity.date {
+        return Ok(dates::format_timestamp(quantity.value));
+    }
+    if quantity.is_duration() && uses_hms(expr) {
+        return Ok(dates::format_hms(quantity.value));
+    }
+
+    let mut names = Vec::new();
+    unit_names(expr, rates, &mut names);
+    let currency = names
+        .iter()
+        .copied()
+        .find(|name| rates.rate(name).is_some());
+    // Differences of dates are shown in days
+    let time = names
+        .iter()
+        .copied()
+        .find(|name| is_time_unit(name))
+        .or(Some("d"));
+
+    let mut value = quantity.value;
+    let mut numerator = Vec::new();
+    let mut denominator = Vec::new();
+    for (name, exponent) in [(currency, quantity.currency), (time, quantity.time)] {
+        let Some(name) = name.filter(|_| exponent != 0) else {
+            continue;
+        };
+        let size = unit(name, rates).map_or(1.0, |u| u.value);
+        value /= size.powi(exponent);
+        let part = match exponent.abs() {
+            1 => name.to_string(),
+            2 => format!("{}²", name),
+            3 => format!("{}³", name),
+            n => format!("{}^{}", name, n),
+        };
+        if exponent > 0 {
+            numerator.push(part);
+        } else {
+            denominator.push(part);
+        }
+    }
+    let mut text = numerator.join("·");
+    if !denominator.is_empty() {
+        if text.is_empty() {
+            text.push('1');
+        }
+        text.push('/');
+        text.push_str(&denominator.join("·"));
+    }
+    Ok(format!("{} {}", format_result(value), text)
+        .trim_end()
+        .to_string())
+}
+
+/// Units named in the expression, in order
+fn unit_names<'a>(expr: &'a Expr, rates: &RateTable, names: &mut Vec<&'a str>) {
+    match expr {
+        Expr::Var(name) if unit(name, rates).is_some() => names.push(name),
+        Expr::Neg(a) => unit_names(a, rates, names),
+        Expr::Binary(_, a, b) => {
+            unit_names(a, rates, names);
+            unit_names(b, rates, names);
+        }
+        _ => {}
+    }
+}
+
+/// Whether the expression has a duration written as h:mm:ss
+fn uses_hms(expr: &Expr) -> bool {
+    match expr {
+        Expr::Call(name, _) if name == "hms" => true,
+        Expr::Neg(a) => uses_hms(a),
+        Expr::Binary(_, a, b) => uses_hms(a) || uses_hms(b),
+        _ => false,
+    }
+}
+
+/// Evaluate an expression with units. Parts without units go to the scalar
+/// evaluator.
+fn evaluate(expr: &Expr, ctx: &Context, rates: &RateTable) -> Result<Quantity, String> {
+    if !uses_units(expr, rates) {
+        return Ok(Quantity::number(expr.eval_with_error(ctx)?.0));
+    }
+    match expr {
+        Expr::Var(name) => Ok(unit(name, rates).expect("unit name")),
+        Expr::Neg(a) => {
+            let a = evaluate(a, ctx, rates)?;
+            if a.date {
+                return Err("Dates cannot be negated".to_string());
+            }
+            Ok(Quantity {
+                value: -a.value,
+                ..a
+            })
+        }
+        Expr::Binary(op, a, b) => binary(*op, evaluate(a, ctx, rates)?, evaluate(b, ctx, rates)?),
+        Expr::Call(name, args) if DATE_FUNCTIONS.contains(&name.as_str()) => {
+            let args = args
+                .iter()
+                .map(|arg| evaluate(arg, ctx, rates))
+                .collect::<Result<Vec<_>, _>>()?;
+            date_function(name, &args)
+        }
+        _ => Err("Units not allowed here".to_string()),
+    }
+}
+
+fn binary(op: Op, a: Quantity, b: Quantity) -> Result<Quantity, String> {
+    // A date plus or minus a duration is a date; two dates only subtract
+    if a.date || b.date {
+        return match (op, a.date, b.date) {
+            (Op::Add, true, false) if b.is_duration() => checked_date(a.value + b.value),
+            (Op::Add, false, true) if a.is_duration() => checked_date(a.value + b.value),
+            (Op::Sub, true, false) if b.is_duration() => checked_date(a.value - b.value),
+            (Op::Sub, true, true) => Ok(Quantity::seconds(a.value - b.value)),
+            _ => Err("Invalid date arithmetic".to_string()),
+        };
+    }
+    match op {
+        Op::Add | Op::Sub | Op::Rem if !a.same_units(&b) => Err(INCOMPATIBLE.to_string()),
+        Op::Add => Ok(Quantity {
+            value: a.value + b.value,
+            ..a
+        }),
+        Op::Sub => Ok(Quantity {
+            value: a.value - b.value,
+            ..a
+        }),
+        Op::Rem => Ok(Quantity {
+            value: a.value % b.value,
+            ..a
+        }),
+        Op::Mul => Ok(Quantity {
+            value: a.value * b.value,
+            currency: a.currency + b.currency,
+            time: a.time + b.time,
+            date: false,
+        }),
+        Op::Div => Ok(Quantity {
+            value: a.value / b.value,
+            currency: a.currency - b.currency,
+            time: a.time - b.time,
+            date: false,
+        }),
+        Op::Pow => {
+            let n = b.value;
+            if !b.is_number() || n.fract() != 0.0 || n.abs() > 9.0 {
+                return Err("Units need a small whole power".to_string());
+            }
+            Ok(Quantity {
+                value: a.value.powf(n),
+                currency: a.currency * n as i32,
+                time: a.time * n as i32,
+                date: false,
+            })
+        }
+    }
+}
+
+/// A date, or an error outside the years 1 to 9999
+fn checked_date(seconds: f64) -> Result<Quantity, String> {
+    match dates::in_range(seconds) {
+        true => Ok(Quantity::date(seconds)),
+        false => Err(INVALID_DATE.to_string()),
+    }
+}
+
+fn date_function(name: &str, args: &[Quantity]) -> Result<Quantity, String> {
+    let kinds: Vec<bool> = args.iter().map(|a| a.date).collect();
+    let numbers = || -> Result<Vec<f64>, String> {
+        args.iter()
+            .map(|a| match a.is_number() {
+                true => Ok(a.value),
+                false => Err(format!("{} needs numbers", name)),
+            })
+            .collect()
+    };
+    let day = |q: &Quantity| (q.value / SECONDS_PER_DAY).floor() as i64;
+    let invalid = || format!("Invalid arguments to {}", name);
+
+    match (name, kinds.as_slice()) {
+        ("date", _) if (3..=6).contains(&args.len()) => {
+            let v = numbers()?;
+            let at = |i: usize| v.get(i).copied().unwrap_or(0.0);
+            dates::timestamp(v[0], v[1], v[2], at(3), at(4), at(5))
+                .map(Quantity::date)
+                .ok_or_else(|| INVALID_DATE.to_string())
+        }
+        ("hms", _) if (2..=3).contains(&args.len()) => {
+            let v = numbers()?;
+            let seconds = v.get(2).copied().unwrap_or(0.0);
+            Ok(Quantity::seconds(v[0] * 3600.0 + v[1] * 60.0 + seconds))
+        }
+        ("workdays", [true, true]) => Ok(Quantity::number(dates::workdays(
+            day(&args[0]),
+            day(&args[1]),
+        ) as f64)),
+        ("workday", [true, false]) if args[1].is_number() && args[1].value.fract() == 0.0 => {
+            // More weekdays than there are days would leave the years 1 to 9999
+            if args[1].value.abs() > dates::DAYS_IN_RANGE as f64 {
+                return Err(INVALID_DATE.to_string());
+            }
+            let days = dates::add_workdays(day(&args[0]), args[1].value as i64);
+            checked_date(days as f64 * SECONDS_PER_DAY)
+        }
+        ("unix", [true]) => Ok(Quantity::number(args[0].value)),
+        ("fromunix", [false]) if args[0].is_number() => checked_date(args[0].value),
+        _ => Err(invalid()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn convert_text(text: &str, target: Option<&str>) -> Result<String, String> {
+        let rates = RateTable::parse("base EUR\nEUR 1\nUSD 1.25\n").unwrap();
+        let expr: Expr = dates::replace_literals(text).parse()?;
+        let target_expr: Option<Expr> = target.map(|t| t.parse()).transpose()?;
+        let target = target_expr.as_ref().zip(target);
+        convert(&expr, target, &Context::new(), &rates)
+    }
+
+    fn converted(text: &str, target: Option<&str>) -> String {
+        convert_text(text, target).unwrap()
+    }
+
+    #[test]
+    fn test_currency() {
+        assert_eq!(converted("100*USD", Some("EUR")), "80 EUR");
+        assert_eq!(converted("100*EUR", Some("USD")), "125 USD");
+        // Shown in the first currency named
+        assert_eq!(converted("10*EUR+5*USD", None), "14 EUR");
+        assert!(convert_text("100*USD", Some("h")).is_err());
+        assert!(convert_text("100*USD", Some("2")).is_err());
+        assert!(convert_text("EUR+h", None).is_err());
+    }
+
+    #[test]
+    fn test_rates_over_time() {
+        assert_eq!(converted("50*EUR/h*8*h", None), "400 EUR");
+        assert_eq!(converted("400*EUR/(8*h)", None), "50 EUR/h");
+        assert_eq!(converted("50*EUR/h", Some("USD/d")), "1500 USD/d");
+        assert_eq!(converted("2*d", Some("h")), "48 h");
+        assert_eq!(converted("1/h^2", None), "1 1/h²");
+        // Units cancel to a plain number
+        assert_eq!(converted("6*EUR/(3*EUR)", None), "2");
+    }
+
+    #[test]
+    fn test_dates() {
+        assert_eq!(converted("2026-03-01+90*days", None), "2026-05-30");
+        assert_eq!(converted("2026-12-25-2026-03-01", None), "299 d");
+        assert_eq!(
+            converted("2026-12-25-2026-03-01", Some("wk")),
+            "42.7142857143 wk"
+        );
+        assert_eq!(converted("2026-03-01T12:00-36*h", None), "2026-02-28");
+        assert!(convert_text("2026-03-01+2026-03-02", None).is_err());
+        assert!(convert_text("2*2026-03-01", None).is_err());
+        assert!(convert_text("2026-02-30", None).is_err());
+        // today is a date
+        assert!(converted("today", None).starts_with("20"));
+    }
+
+    #[test]
+    fn test_durations() {
+        assert_eq!(converted("1:45:30+0:20:15", None), "2:05:45");
+        assert_eq!(converted("0:20-1:00", None), "−0:40:00");
+        assert_eq!(converted("5000*s", Some("hms")), "1:23:20");
+        assert_eq!(converted("1:30", Some("h")), "1.5 h");
+        assert!(convert_text("EUR", Some("hms")).is_err());
+    }
+
+    #[test]
+    fn test_date_functions() {
+        assert_eq!(converted("workdays(2026-03-02,2026-03-13)", None), "10");
+        assert_eq!(converted("workday(2026-03-06,1)", None), "2026-03-09");
+        assert_eq!(converted("unix(2026-03-01)", None), "1772323200");
+        assert_eq!(converted("fromunix(1772323200)", None), "2026-03-01");
+        assert!(convert_text("workdays(2026-03-02,5)", None).is_err());
+        assert!(convert_text("unix(5)", None).is_err());
+    }
+
+    #[test]
+    fn test_date_range() {
+        let invalid = Err(INVALID_DATE.to_string());
+        assert_eq!(convert_text("2026-03-01+1e20*days", None), invalid);
+        assert_eq!(convert_text("2026-03-01-3e6*days", None), invalid);
+        assert_eq!(convert_text("fromunix(1e300)", None), invalid);
+        assert_eq!(convert_text("fromunix(-1e300)", None), invalid);
+        assert_eq!(convert_text("workday(2026-03-01,9e18)", None), invalid);
+        assert_eq!(convert_text("workday(2026-03-01,-1e19)", None), invalid);
+        assert_eq!(convert_text("workday(2026-03-01,3e6)", None), invalid);
+        assert_eq!(
+            convert_text("workdays(2026-03-01,fromunix(1e300))", None),
+            invalid
+        );
+        assert_eq!(converted("9999-12-31+23*h", None), "9999-12-31T23:00:00");
+        assert_eq!(convert_text("9999-12-31+1*days", None), invalid);
+        assert_eq!(converted("0001-01-02-1*days", None), "0001-01-01");
+    }
+}